@@ -1,17 +1,25 @@
 use arboard::{Clipboard, ImageData};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, Host, Stream, StreamConfig};
+use crate::store::{ClipboardRestore, OutputMode};
 use enigo::{Enigo, Key, KeyboardControllable};
-use serde::Serialize;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
 use std::borrow::Cow;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
-use std::time::Duration;
-use stt::{create_adapter, AudioFormat as SttAudioFormat, SttAdapter, SttConfig};
+use std::time::{Duration, Instant};
+use stt::{
+    AudioFormat as SttAudioFormat, DecodeProgress, ModelRegistry, ProgressCallback, SttAdapter,
+    SttError,
+};
+use text_processor::{FormattingMode, ProcessingResult, TextProcessor};
 use tauri::{AppHandle, Manager};
 use tokio::sync::Mutex as AsyncMutex;
 #[cfg(target_os = "windows")]
@@ -33,6 +41,17 @@ pub struct AudioCapture {
     format: Arc<Mutex<SttAudioFormat>>,
     stt_adapter: Arc<AsyncMutex<Option<Box<dyn SttAdapter>>>>,
     loaded_model: Arc<AsyncMutex<Option<String>>>,
+    stt_last_used: Arc<Mutex<Option<Instant>>>,
+    last_transcript: Arc<Mutex<Option<String>>>,
+    previous_partial: Arc<Mutex<String>>,
+    processing: Arc<AtomicBool>,
+    text_processor: Arc<AsyncMutex<Option<CachedTextProcessor>>>,
+}
+
+/// Formatting processor kept loaded between dictations, keyed by provider and model.
+struct CachedTextProcessor {
+    key: String,
+    processor: TextProcessor,
 }
 
 impl AudioCapture {
@@ -43,6 +62,18 @@ impl AudioCapture {
             format: Arc::new(Mutex::new(SttAudioFormat::default())),
             stt_adapter: Arc::new(AsyncMutex::new(None)),
             loaded_model: Arc::new(AsyncMutex::new(None)),
+            stt_last_used: Arc::new(Mutex::new(None)),
+            last_transcript: Arc::new(Mutex::new(None)),
+            previous_partial: Arc::new(Mutex::new(String::new())),
+            processing: Arc::new(AtomicBool::new(false)),
+            text_processor: Arc::new(AsyncMutex::new(None)),
+        }
+    }
+
+    /// Records that the speech model was just needed, for [`unload_idle_model`].
+    fn mark_stt_used(&self) {
+        if let Ok(mut last_used) = self.stt_last_used.lock() {
+            *last_used = Some(Instant::now());
         }
     }
 }
@@ -55,8 +86,63 @@ impl Clone for AudioCapture {
             format: self.format.clone(),
             stt_adapter: self.stt_adapter.clone(),
             loaded_model: self.loaded_model.clone(),
+            stt_last_used: self.stt_last_used.clone(),
+            last_transcript: self.last_transcript.clone(),
+            previous_partial: self.previous_partial.clone(),
+            processing: self.processing.clone(),
+            text_processor: self.text_processor.clone(),
+        }
+    }
+}
+
+/// Marks a transcription cycle as in flight; cleared on drop so every exit path releases it.
+struct ProcessingGuard {
+    flag: Arc<AtomicBool>,
+}
+
+impl ProcessingGuard {
+    fn acquire(flag: &Arc<AtomicBool>) -> Option<Self> {
+        if flag.swap(true, Ordering::AcqRel) {
+            return None;
         }
+        Some(Self { flag: flag.clone() })
+    }
+}
+
+impl Drop for ProcessingGuard {
+    fn drop(&mut self) {
+        self.flag.store(false, Ordering::Release);
+    }
+}
+
+/// What a stop request gets to work with.
+enum StopClaim {
+    /// A previous dictation is still processing; the recording carries on untouched.
+    Busy,
+    NotRecording,
+    Captured {
+        guard: ProcessingGuard,
+        samples: Vec<f32>,
+    },
+}
+
+/// Claims the processing slot first, then stops the stream and takes its samples. A stop
+/// rejected as busy therefore leaves the stream recording and its samples in place, so the
+/// user can stop again once the previous dictation is done.
+fn claim_capture(
+    processing: &Arc<AtomicBool>,
+    stop_stream: impl FnOnce() -> bool,
+    samples: &Mutex<Vec<f32>>,
+) -> StopClaim {
+    let Some(guard) = ProcessingGuard::acquire(processing) else {
+        return StopClaim::Busy;
+    };
+    if !stop_stream() {
+        return StopClaim::NotRecording;
     }
+    // Take the samples right after stopping the stream so a racing start cannot mix captures.
+    let samples = std::mem::take(&mut *samples.lock().unwrap());
+    StopClaim::Captured { guard, samples }
 }
 
 #[derive(Clone, Serialize)]
@@ -69,11 +155,137 @@ struct TranscriptionStatusEvent {
 struct TranscriptionResultEvent {
     text: String,
     language: Option<String>,
+    language_confidence: Option<f32>,
     confidence: Option<f32>,
+    quality: Option<f32>,
     is_final: bool,
+    started_at: String,
+    audio_duration_s: f32,
+    processing_ms: u64,
+    model: String,
+    review: bool,
+    /// Whisper decode metrics, only sent with verbose logging or the `show_scores` setting.
+    debug_scores: Option<stt::DecodeScores>,
 }
 
-fn emit_transcription_status(app: &AppHandle, status: &str, error: Option<String>) {
+/// Audio pipeline failures, serialized to the UI as `{ code, message }` so it can react
+/// per category (e.g. prompt for microphone access vs. retry a model download).
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioPipelineError {
+    NoInputDevice(String),
+    ModelUnavailable(String),
+    TranscriptionFailed(String),
+    PasteFailed(String),
+    FfmpegMissing,
+    Other(String),
+}
+
+impl AudioPipelineError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NoInputDevice(_) => "no_input_device",
+            Self::ModelUnavailable(_) => "model_unavailable",
+            Self::TranscriptionFailed(_) => "transcription_failed",
+            Self::PasteFailed(_) => "paste_failed",
+            Self::FfmpegMissing => "ffmpeg_missing",
+            Self::Other(_) => "other",
+        }
+    }
+}
+
+impl fmt::Display for AudioPipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoInputDevice(msg)
+            | Self::ModelUnavailable(msg)
+            | Self::TranscriptionFailed(msg)
+            | Self::PasteFailed(msg)
+            | Self::Other(msg) => f.write_str(msg),
+            Self::FfmpegMissing => f.write_str("ffmpeg binary not found"),
+        }
+    }
+}
+
+impl std::error::Error for AudioPipelineError {}
+
+impl Serialize for AudioPipelineError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut payload = serializer.serialize_struct("AudioPipelineError", 2)?;
+        payload.serialize_field("code", self.code())?;
+        payload.serialize_field("message", &self.to_string())?;
+        payload.end()
+    }
+}
+
+// Helpers that still report plain strings map to `Other` until they are categorized.
+impl From<String> for AudioPipelineError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+/// Live caption update: `stable` words agreed with the previous partial, `tentative` may change.
+#[derive(Clone, Serialize, Debug, PartialEq)]
+struct PartialTranscriptionEvent {
+    stable: String,
+    tentative: String,
+    audio_duration_s: f32,
+}
+
+/// Split `current` into the word prefix it shares with `previous` and the unstable tail.
+fn merge_partial(previous: &str, current: &str) -> (String, String) {
+    let previous_words: Vec<&str> = previous.split_whitespace().collect();
+    let current_words: Vec<&str> = current.split_whitespace().collect();
+    let stable_len = previous_words
+        .iter()
+        .zip(current_words.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (
+        current_words[..stable_len].join(" "),
+        current_words[stable_len..].join(" "),
+    )
+}
+
+/// Surfaces interim text from the final decode as live captions; heartbeats keep the
+/// `processing` status current while no new text is available.
+fn final_decode_progress(
+    app: AppHandle,
+    previous_partial: Arc<Mutex<String>>,
+    audio_duration_s: f32,
+) -> ProgressCallback {
+    Arc::new(move |progress| match progress {
+        DecodeProgress::Partial(text) => {
+            let (stable, tentative) = match previous_partial.lock() {
+                Ok(mut previous) => {
+                    let merged = merge_partial(&previous, &text);
+                    *previous = text;
+                    merged
+                }
+                Err(_) => (String::new(), text),
+            };
+            let _ = app.emit_all(
+                "partial-transcription",
+                PartialTranscriptionEvent {
+                    stable,
+                    tentative,
+                    audio_duration_s,
+                },
+            );
+        }
+        DecodeProgress::Decoding => emit_processing_heartbeat(&app),
+    })
+}
+
+/// Token of a deferred `processing` status that has not been shown yet.
+fn pending_processing_status() -> &'static Mutex<Option<u64>> {
+    static SLOT: OnceLock<Mutex<Option<u64>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+static NEXT_PROCESSING_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+fn send_transcription_status(app: &AppHandle, status: &str, error: Option<String>) {
     let _ = app.emit_all(
         "transcription-status",
         TranscriptionStatusEvent {
@@ -83,12 +295,53 @@ fn emit_transcription_status(app: &AppHandle, status: &str, error: Option<String
     );
 }
 
+/// Emits a status and supersedes any deferred `processing` emit. The slot lock is held
+/// while sending so a late `processing` can never land after `idle`.
+fn emit_transcription_status(app: &AppHandle, status: &str, error: Option<String>) {
+    match pending_processing_status().lock() {
+        Ok(mut pending) => {
+            *pending = None;
+            send_transcription_status(app, status, error);
+        }
+        Err(_) => send_transcription_status(app, status, error),
+    }
+}
+
+/// Re-sends `processing` during a long decode. Skipped while a deferred `processing` is
+/// pending so heartbeats never show the pill before `min_processing_display_ms`.
+fn emit_processing_heartbeat(app: &AppHandle) {
+    if let Ok(pending) = pending_processing_status().lock() {
+        if pending.is_none() {
+            send_transcription_status(app, "processing", None);
+        }
+    }
+}
+
+/// Shows `processing` only once decoding has taken `delay_ms`, so fast models don't flash
+/// the pill. A delay of 0 emits immediately.
+fn emit_processing_status_deferred(app: &AppHandle, delay_ms: u64) {
+    if delay_ms == 0 {
+        emit_transcription_status(app, "processing", None);
+        return;
+    }
+    let token = NEXT_PROCESSING_TOKEN.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut pending) = pending_processing_status().lock() {
+        *pending = Some(token);
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        if let Ok(mut pending) = pending_processing_status().lock() {
+            if *pending == Some(token) {
+                *pending = None;
+                send_transcription_status(&app, "processing", None);
+            }
+        }
+    });
+}
+
 fn verbose_logs_enabled() -> bool {
-    std::env::var("OPENWISPR_VERBOSE_LOGS")
-        .ok()
-        .as_deref()
-        .map(|v| v == "1")
-        .unwrap_or(false)
+    stt::verbose_logs_enabled()
 }
 
 enum ClipboardSnapshot {
@@ -225,13 +478,53 @@ pub fn remember_active_paste_target() {
     capture_active_paste_target();
 }
 
+/// Mirrors the Windows `IsWindow` check: the captured app may have quit since recording began.
 #[cfg(target_os = "macos")]
-fn restore_active_paste_target() {
-    let target = paste_target_slot()
+fn process_is_alive(pid: i32) -> bool {
+    Command::new("ps")
+        .args(["-p", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// The captured paste target, or `None` (and the slot cleared) if its process has exited,
+/// in which case pasting falls back to whatever app is frontmost now.
+#[cfg(target_os = "macos")]
+fn live_paste_target() -> Option<MacPasteTarget> {
+    let mut slot = paste_target_slot().lock().ok()?;
+    let target = slot.clone()?;
+    if process_is_alive(target.pid) {
+        return Some(target);
+    }
+    if verbose_logs_enabled() {
+        eprintln!(
+            "[paste] captured app pid={} name='{}' has exited, pasting into the current frontmost app",
+            target.pid, target.name
+        );
+    }
+    *slot = None;
+    None
+}
+
+/// Name of the app captured as the paste target, where the platform exposes one.
+#[cfg(target_os = "macos")]
+fn paste_target_app_name() -> Option<String> {
+    paste_target_slot()
         .lock()
         .ok()
-        .and_then(|slot| slot.clone());
-    let Some(target) = target else {
+        .and_then(|slot| slot.as_ref().map(|target| target.name.clone()))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn paste_target_app_name() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn restore_active_paste_target() {
+    let Some(target) = live_paste_target() else {
         if verbose_logs_enabled() {
             eprintln!("[paste] no captured app to restore on macOS");
         }
@@ -291,29 +584,43 @@ fn restore_active_paste_target() {
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 fn restore_active_paste_target() {}
 
-fn capture_clipboard(clipboard: &mut Clipboard) -> ClipboardSnapshot {
+/// Snapshot the clipboard for restoring after paste. `None` means nothing should be
+/// restored and the staged transcription stays on the clipboard.
+fn capture_clipboard(
+    clipboard: &mut Clipboard,
+    restore: ClipboardRestore,
+) -> Option<ClipboardSnapshot> {
+    if restore == ClipboardRestore::Never {
+        return None;
+    }
+
     if let Ok(html) = clipboard.get().html() {
         let alt_text = clipboard.get_text().unwrap_or_else(|_| html.clone());
-        return ClipboardSnapshot::Html { html, alt_text };
+        return Some(ClipboardSnapshot::Html { html, alt_text });
     }
 
     if let Ok(text) = clipboard.get_text() {
-        return ClipboardSnapshot::Text(text);
+        return Some(ClipboardSnapshot::Text(text));
+    }
+
+    // Reading and re-writing large images/file lists is slow and flaky on some platforms.
+    if restore == ClipboardRestore::TextOnly {
+        return None;
     }
 
     if let Ok(image) = clipboard.get_image() {
-        return ClipboardSnapshot::Image {
+        return Some(ClipboardSnapshot::Image {
             width: image.width,
             height: image.height,
             bytes: image.bytes.as_ref().to_vec(),
-        };
+        });
     }
 
     if let Ok(files) = clipboard.get().file_list() {
-        return ClipboardSnapshot::FileList(files);
+        return Some(ClipboardSnapshot::FileList(files));
     }
 
-    ClipboardSnapshot::Clear
+    Some(ClipboardSnapshot::Clear)
 }
 
 fn restore_clipboard(
@@ -424,7 +731,8 @@ fn paste_text_preserving_clipboard(text: &str) -> Result<(), String> {
         }
     };
 
-    let snapshot = capture_clipboard(&mut clipboard);
+    let restore_mode = crate::store::get_settings().clipboard_restore;
+    let snapshot = capture_clipboard(&mut clipboard, restore_mode);
 
     if let Err(err) = clipboard.set_text(text.to_string()) {
         if verbose_logs_enabled() {
@@ -434,16 +742,16 @@ fn paste_text_preserving_clipboard(text: &str) -> Result<(), String> {
         insert_text_directly(text);
         return Ok(());
     }
+    // Park the snapshot where shutdown can find it if we quit mid-paste.
+    if let Ok(mut pending) = pending_clipboard_restore().lock() {
+        *pending = snapshot;
+    }
 
     // Step 2: focus target and paste.
     let mut paste_done = false;
     #[cfg(target_os = "macos")]
     {
-        let target = paste_target_slot()
-            .lock()
-            .ok()
-            .and_then(|slot| slot.clone());
-        if let Some(target) = target {
+        if let Some(target) = live_paste_target() {
             let script = format!(
                 r#"tell application "System Events"
     set frontmost of (first application process whose unix id is {}) to true
@@ -479,11 +787,323 @@ end tell"#,
 
     // Step 3: restore original clipboard (reliable retries).
     thread::sleep(Duration::from_millis(120));
-    restore_clipboard_with_retry(snapshot);
+    restore_pending_clipboard();
 
     Ok(())
 }
 
+fn pending_clipboard_restore() -> &'static Mutex<Option<ClipboardSnapshot>> {
+    static SLOT: OnceLock<Mutex<Option<ClipboardSnapshot>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+/// Restores the clipboard snapshot of an in-flight paste, if any. Called after each paste
+/// and on shutdown so quitting mid-dictation does not leave the transcript on the clipboard.
+pub fn restore_pending_clipboard() {
+    let snapshot = pending_clipboard_restore()
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.take());
+    if let Some(snapshot) = snapshot {
+        restore_clipboard_with_retry(snapshot);
+    }
+}
+
+fn copy_text_to_clipboard(text: &str) -> Result<(), String> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text.to_string()))
+        .map_err(|e| format!("failed to set clipboard: {}", e))
+}
+
+/// Whether `text` is short enough to type directly under the `type_below_chars` setting.
+fn should_type_text(text: &str, type_below_chars: Option<usize>) -> bool {
+    type_below_chars.is_some_and(|limit| text.chars().count() < limit)
+}
+
+/// Blank transcripts end the cycle early unless `paste_empty_transcripts` is on; even then
+/// [`output_transcript`] never pastes whitespace.
+fn should_skip_empty_transcript(text: &str, paste_empty_transcripts: bool) -> bool {
+    !paste_empty_transcripts && text.trim().is_empty()
+}
+
+/// Deliver a transcript to the focused app: short text is typed to avoid clipboard churn,
+/// everything else is pasted through the clipboard.
+fn output_transcript(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Ok(());
+    }
+    if should_type_text(text, crate::store::get_settings().type_below_chars) {
+        if verbose_logs_enabled() {
+            println!("[paste] typing {} chars directly", text.chars().count());
+        }
+        restore_active_paste_target();
+        insert_text_directly(text);
+        return Ok(());
+    }
+    paste_text_preserving_clipboard(text)
+}
+
+/// Whether to flatten output to one line: globally, or for apps listed as single-line targets.
+fn should_collapse_newlines(
+    collapse_newlines: bool,
+    single_line_apps: &[String],
+    target_app: Option<&str>,
+) -> bool {
+    collapse_newlines
+        || target_app.is_some_and(|app| {
+            single_line_apps
+                .iter()
+                .any(|listed| listed.trim().eq_ignore_ascii_case(app.trim()))
+        })
+}
+
+/// Replace runs of whitespace (including newlines) with single spaces.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Uppercase the first alphabetic character, skipping leading quotes/punctuation.
+fn capitalize_first_letter(text: &str) -> String {
+    match text.char_indices().find(|(_, c)| c.is_alphabetic()) {
+        Some((idx, c)) if c.is_lowercase() => {
+            let mut out = String::with_capacity(text.len());
+            out.push_str(&text[..idx]);
+            out.extend(c.to_uppercase());
+            out.push_str(&text[idx + c.len_utf8()..]);
+            out
+        }
+        _ => text.to_string(),
+    }
+}
+
+/// Frees the cached formatting model so a newly selected one is not loaded alongside it.
+pub(crate) async fn unload_text_processor(capture: &AudioCapture) {
+    if let Some(mut entry) = capture.text_processor.lock().await.take() {
+        if verbose_logs_enabled() {
+            println!("[formatting] unloading {}", entry.key);
+        }
+        entry.processor.unload().await;
+    }
+}
+
+/// Runs `task` with the cached formatting model unloaded, holding its slot so dictation
+/// cannot reload it meanwhile. Model validation uses this to never keep two llama models in
+/// memory; the next formatted dictation loads its model again.
+pub(crate) async fn with_text_processor_unloaded<T>(
+    capture: &AudioCapture,
+    task: impl std::future::Future<Output = T>,
+) -> T {
+    let mut cached = capture.text_processor.lock().await;
+    if let Some(mut entry) = cached.take() {
+        if verbose_logs_enabled() {
+            println!(
+                "[formatting] unloading {} while a model is validated",
+                entry.key
+            );
+        }
+        entry.processor.unload().await;
+    }
+    task.await
+}
+
+async fn format_transcript(
+    capture: &AudioCapture,
+    raw_text: &str,
+    mode_str: &str,
+) -> text_processor::Result<ProcessingResult> {
+    let settings = crate::store::get_settings();
+    let mode = FormattingMode::from_str(mode_str);
+
+    // Get active model for formatting
+    let format_model = settings
+        .system_llm_model
+        .clone()
+        .unwrap_or_else(|| "SmolLM2-135M-Instruct-Q4_K_M".to_string());
+    let local_key = format!("system:{}", format_model);
+
+    let ollama = if settings.llm_provider.as_deref() == Some("ollama") {
+        settings
+            .ollama_model
+            .clone()
+            .filter(|m| !m.trim().is_empty())
+            .map(|model| {
+                let base_url = settings
+                    .ollama_base_url
+                    .clone()
+                    .unwrap_or_else(|| llm::adapters::ollama::DEFAULT_OLLAMA_BASE_URL.to_string());
+                (base_url, model)
+            })
+    } else {
+        None
+    };
+
+    let mut cached = capture.text_processor.lock().await;
+    if let Some((base_url, ollama_model)) = &ollama {
+        let ollama_key = format!("ollama:{}:{}", base_url, ollama_model);
+        if !cached.as_ref().is_some_and(|entry| entry.key == ollama_key) {
+            match TextProcessor::with_ollama(base_url, ollama_model, mode).await {
+                Ok(processor) => {
+                    *cached = Some(CachedTextProcessor {
+                        key: ollama_key.clone(),
+                        processor,
+                    });
+                }
+                Err(err) => {
+                    eprintln!(
+                        "[formatting] ollama unavailable at {}, falling back to local model: {}",
+                        base_url, err
+                    );
+                }
+            }
+        }
+        if let Some(entry) = cached.as_mut().filter(|entry| entry.key == ollama_key) {
+            entry.processor.set_mode(mode);
+            entry
+                .processor
+                .set_pinned_terms(settings.personal_dictionary.clone());
+            // A server that went away after connecting fails here rather than at setup
+            match entry.processor.process(raw_text).await {
+                Err(text_processor::ProcessorError::LlmError(err)) => {
+                    eprintln!(
+                        "[formatting] ollama at {} failed, falling back to local model: {}",
+                        base_url, err
+                    );
+                }
+                result => return result,
+            }
+        }
+    }
+
+    let entry = match cached.take().filter(|entry| entry.key == local_key) {
+        Some(entry) => entry,
+        None => CachedTextProcessor {
+            processor: TextProcessor::new(&format_model, mode).await?,
+            key: local_key,
+        },
+    };
+    let entry = cached.insert(entry);
+    entry.processor.set_mode(mode);
+    entry.processor.set_pinned_terms(settings.personal_dictionary);
+    entry.processor.process(raw_text).await
+}
+
+/// Consecutive all-zero captures before we suspect the OS is withholding microphone access.
+const SILENT_CAPTURES_BEFORE_PERMISSION_HINT: u32 = 3;
+
+static CONSECUTIVE_SILENT_CAPTURES: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Clone, Serialize)]
+struct MicrophonePermissionHint {
+    message: String,
+    /// Deep link to the OS privacy page, when the platform has one.
+    settings_url: Option<&'static str>,
+}
+
+/// A microphone blocked by OS privacy settings still delivers frames, just exact zeros;
+/// a real room never does, even when the user says nothing.
+fn is_digital_silence(samples: &[f32]) -> bool {
+    samples.iter().all(|sample| sample.abs() < 1e-7)
+}
+
+/// Counts back-to-back silent captures; true every time the count reaches the hint threshold.
+fn record_capture_silence(counter: &AtomicU32, silent: bool) -> bool {
+    if !silent {
+        counter.store(0, Ordering::Relaxed);
+        return false;
+    }
+    let count = counter.fetch_add(1, Ordering::Relaxed) + 1;
+    count % SILENT_CAPTURES_BEFORE_PERMISSION_HINT == 0
+}
+
+/// Emits `microphone-permission` when captures keep coming back as pure silence, so the UI
+/// can point at the privacy settings instead of failing quietly.
+fn check_microphone_permission(app: &AppHandle, samples: &[f32]) {
+    let silent = is_digital_silence(samples);
+    let repeated = record_capture_silence(&CONSECUTIVE_SILENT_CAPTURES, silent);
+    if !repeated && !(silent && microphone_access_denied()) {
+        return;
+    }
+    let hint = microphone_permission_hint();
+    eprintln!("[audio] capture is digital silence: {}", hint.message);
+    let _ = app.emit_all("microphone-permission", hint);
+}
+
+#[cfg(target_os = "windows")]
+fn microphone_permission_hint() -> MicrophonePermissionHint {
+    MicrophonePermissionHint {
+        message: "The microphone is only returning silence. Windows may be blocking access: \
+                  open Settings → Privacy & security → Microphone and allow desktop apps to \
+                  use it."
+            .to_string(),
+        settings_url: Some("ms-settings:privacy-microphone"),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn microphone_permission_hint() -> MicrophonePermissionHint {
+    MicrophonePermissionHint {
+        message: "The microphone is only returning silence. Allow OpenWispr in System \
+                  Settings → Privacy & Security → Microphone."
+            .to_string(),
+        settings_url: Some(
+            "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone",
+        ),
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn microphone_permission_hint() -> MicrophonePermissionHint {
+    MicrophonePermissionHint {
+        message: "The microphone is only returning silence. Check that it is unmuted and \
+                  that OpenWispr is allowed to record."
+            .to_string(),
+        settings_url: None,
+    }
+}
+
+/// Reads the Windows capability consent store; `Deny` on either the global or the
+/// desktop-app ("NonPackaged") switch means cpal will capture zeros.
+#[cfg(target_os = "windows")]
+fn microphone_access_denied() -> bool {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_SZ};
+
+    const CONSENT_STORE: &str =
+        r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\microphone";
+    let wide = |s: &str| s.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let read_consent = |subkey: &str| -> Option<String> {
+        let subkey = wide(subkey);
+        let value_name = wide("Value");
+        let mut buffer = [0_u16; 32];
+        let mut size = std::mem::size_of_val(&buffer) as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                subkey.as_ptr(),
+                value_name.as_ptr(),
+                RRF_RT_REG_SZ,
+                std::ptr::null_mut(),
+                buffer.as_mut_ptr().cast(),
+                &mut size,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return None;
+        }
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len]))
+    };
+
+    [CONSENT_STORE.to_string(), format!(r"{CONSENT_STORE}\NonPackaged")]
+        .iter()
+        .any(|key| read_consent(key).is_some_and(|value| value.eq_ignore_ascii_case("Deny")))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn microphone_access_denied() -> bool {
+    false
+}
+
 fn calculate_rms(samples: &[f32]) -> f32 {
     if samples.is_empty() {
         return 0.0;
@@ -493,7 +1113,7 @@ fn calculate_rms(samples: &[f32]) -> f32 {
     (sum / samples.len() as f32).sqrt()
 }
 
-fn select_input_device(host: &Host) -> Result<Device, String> {
+fn select_input_device(host: &Host) -> Result<Device, AudioPipelineError> {
     // 1. Check persistent store
     if let Some(preferred_id) = crate::store::get_input_device_id() {
         if let Ok(devices) = host.input_devices() {
@@ -513,9 +1133,12 @@ fn select_input_device(host: &Host) -> Result<Device, String> {
     if let Ok(requested) = std::env::var("OPENWISPR_INPUT_DEVICE") {
         let needle = requested.trim().to_lowercase();
         if !needle.is_empty() {
-            let devices = host
-                .input_devices()
-                .map_err(|e| format!("Failed to enumerate input devices: {}", e))?;
+            let devices = host.input_devices().map_err(|e| {
+                AudioPipelineError::NoInputDevice(format!(
+                    "Failed to enumerate input devices: {}",
+                    e
+                ))
+            })?;
             for device in devices {
                 let name = device
                     .name()
@@ -527,10 +1150,10 @@ fn select_input_device(host: &Host) -> Result<Device, String> {
                     return Ok(device);
                 }
             }
-            return Err(format!(
+            return Err(AudioPipelineError::NoInputDevice(format!(
                 "No input device matching OPENWISPR_INPUT_DEVICE='{}'",
                 requested
-            ));
+            )));
         }
     }
 
@@ -545,7 +1168,7 @@ fn select_input_device(host: &Host) -> Result<Device, String> {
         return Ok(device);
     }
 
-    Err("No input device available".to_string())
+    Err(AudioPipelineError::NoInputDevice("No input device available".to_string()))
 }
 
 fn ffmpeg_binary_candidates() -> &'static [&'static str] {
@@ -646,12 +1269,7 @@ fn read_wav_to_f32(path: &Path) -> Result<Vec<f32>, String> {
         .collect())
 }
 
-fn normalize_audio_for_stt_with_ffmpeg(
-    audio_data: &[f32],
-    format: &SttAudioFormat,
-) -> Result<(Vec<f32>, SttAudioFormat), String> {
-    let ffmpeg = resolve_ffmpeg_binary().ok_or_else(|| "ffmpeg binary not found".to_string())?;
-
+fn temp_audio_path(prefix: &str) -> Result<PathBuf, String> {
     let millis = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map_err(|e| format!("Clock error: {}", e))?
@@ -659,8 +1277,65 @@ fn normalize_audio_for_stt_with_ffmpeg(
     let pid = std::process::id();
     let temp_dir = std::env::temp_dir().join("openwispr");
     fs::create_dir_all(&temp_dir).map_err(|e| format!("Failed to create temp dir: {}", e))?;
-    let input_path = temp_dir.join(format!("raw_{pid}_{millis}.wav"));
-    let output_path = temp_dir.join(format!("normalized_{pid}_{millis}.wav"));
+    Ok(temp_dir.join(format!("{prefix}_{pid}_{millis}.wav")))
+}
+
+/// Decodes any ffmpeg-readable container (mp3, m4a, flac, aiff, ...) into a
+/// 16 kHz mono s16 WAV in the temp dir. The caller owns the returned file.
+fn ffmpeg_decode_to_wav(input: &Path) -> Result<PathBuf, AudioPipelineError> {
+    if !input.exists() {
+        return Err(AudioPipelineError::Other(format!(
+            "Audio file not found: {}",
+            input.display()
+        )));
+    }
+    let ffmpeg = resolve_ffmpeg_binary().ok_or(AudioPipelineError::FfmpegMissing)?;
+
+    let output_path = temp_audio_path("decoded")?;
+    let args = ffmpeg_normalize_args(input, &output_path);
+    let status = Command::new(&ffmpeg)
+        .args(&args)
+        .status()
+        .map_err(|e| format!("Failed to spawn ffmpeg '{}': {}", ffmpeg, e))?;
+
+    if !status.success() {
+        let _ = fs::remove_file(&output_path);
+        return Err(AudioPipelineError::Other(format!(
+            "ffmpeg failed to decode {} with status {:?}",
+            input.display(),
+            status.code()
+        )));
+    }
+
+    Ok(output_path)
+}
+
+/// Loads an arbitrary audio file as samples ready for `SttAdapter::transcribe`.
+fn load_audio_file_for_stt(
+    input: &Path,
+) -> Result<(Vec<f32>, SttAudioFormat), AudioPipelineError> {
+    let decoded_path = ffmpeg_decode_to_wav(input)?;
+    let samples = read_wav_to_f32(&decoded_path);
+    let _ = fs::remove_file(&decoded_path);
+
+    Ok((
+        samples?,
+        SttAudioFormat {
+            sample_rate: 16_000,
+            channels: 1,
+            bits_per_sample: 16,
+        },
+    ))
+}
+
+fn normalize_audio_for_stt_with_ffmpeg(
+    audio_data: &[f32],
+    format: &SttAudioFormat,
+) -> Result<(Vec<f32>, SttAudioFormat), String> {
+    let ffmpeg = resolve_ffmpeg_binary().ok_or_else(|| "ffmpeg binary not found".to_string())?;
+
+    let input_path = temp_audio_path("raw")?;
+    let output_path = temp_audio_path("normalized")?;
 
     write_wav_from_f32(&input_path, audio_data, format)?;
 
@@ -693,7 +1368,10 @@ fn normalize_audio_for_stt_with_ffmpeg(
     ))
 }
 
-pub fn start_recording_for_capture(capture: &AudioCapture, app: AppHandle) -> Result<(), String> {
+pub fn start_recording_for_capture(
+    capture: &AudioCapture,
+    app: AppHandle,
+) -> Result<(), AudioPipelineError> {
     let mut stream_lock = capture.stream.lock().unwrap();
     if stream_lock.stream.is_some() {
         return Ok(());
@@ -706,9 +1384,9 @@ pub fn start_recording_for_capture(capture: &AudioCapture, app: AppHandle) -> Re
     let device = select_input_device(&host)?;
 
     // Get the default input config
-    let config = device
-        .default_input_config()
-        .map_err(|e| format!("Failed to get input config: {}", e))?;
+    let config = device.default_input_config().map_err(|e| {
+        AudioPipelineError::NoInputDevice(format!("Failed to get input config: {}", e))
+    })?;
     if verbose_logs_enabled() {
         println!(
             "[audio] input format sample_rate={} channels={} sample_format={:?}",
@@ -723,6 +1401,9 @@ pub fn start_recording_for_capture(capture: &AudioCapture, app: AppHandle) -> Re
         let mut samples = capture.samples.lock().unwrap();
         samples.clear();
     }
+    if let Ok(mut previous) = capture.previous_partial.lock() {
+        previous.clear();
+    }
     {
         let mut format = capture.format.lock().unwrap();
         *format = SttAudioFormat {
@@ -734,6 +1415,7 @@ pub fn start_recording_for_capture(capture: &AudioCapture, app: AppHandle) -> Re
 
     emit_transcription_status(&app, "listening", None);
 
+    let app_for_partials = app.clone();
     // Build the input stream
     let stream = match config.sample_format() {
         cpal::SampleFormat::F32 => {
@@ -745,7 +1427,7 @@ pub fn start_recording_for_capture(capture: &AudioCapture, app: AppHandle) -> Re
         cpal::SampleFormat::U16 => {
             build_input_stream::<u16>(&device, &config.into(), app, capture.samples.clone())?
         }
-        _ => return Err("Unsupported sample format".to_string()),
+        _ => return Err(AudioPipelineError::Other("Unsupported sample format".to_string())),
     };
 
     stream
@@ -753,16 +1435,175 @@ pub fn start_recording_for_capture(capture: &AudioCapture, app: AppHandle) -> Re
         .map_err(|e| format!("Failed to play stream: {}", e))?;
 
     stream_lock.stream = Some(stream);
+    drop(stream_lock);
+
+    let settings = crate::store::get_settings();
+    if partials_enabled_for_model(settings.partials_enabled, &crate::models::active_model_value()) {
+        tauri::async_runtime::spawn(run_partial_transcription(capture.clone(), app_for_partials));
+    } else if verbose_logs_enabled() {
+        println!("[stt] partial transcriptions disabled for this recording");
+    }
 
     Ok(())
 }
 
-#[tauri::command]
-pub fn start_recording(state: tauri::State<AudioCapture>, app: AppHandle) -> Result<(), String> {
-    start_recording_for_capture(state.inner(), app)
+const PARTIAL_TRANSCRIPTION_INTERVAL: Duration = Duration::from_millis(1500);
+/// Longest recording that adapters without streaming re-transcribe for live captions. Their
+/// decode holds the adapter lock, so this bounds how long the final transcription can wait.
+const WHOLE_BUFFER_PARTIAL_MAX_SECONDS: f32 = 10.0;
+
+/// MLX cannot stream while recording and re-transcribing the growing buffer is too costly;
+/// its captions arrive during the final decode instead.
+fn partials_enabled_for_model(partials_enabled: bool, model: &str) -> bool {
+    partials_enabled && !stt::is_mlx_model_name(model)
 }
 
-fn build_input_stream<T>(
+fn is_capture_recording(capture: &AudioCapture) -> bool {
+    capture
+        .stream
+        .lock()
+        .map(|stream| stream.stream.is_some())
+        .unwrap_or(false)
+}
+
+/// Decoding state for one recording's live captions.
+#[derive(Default)]
+struct PartialDecoder {
+    stream: Option<Box<dyn stt::SttStream>>,
+    fed_samples: usize,
+    // The adapter cannot stream this model; re-transcribe the whole buffer instead
+    whole_buffer: bool,
+}
+
+/// Periodically updates live captions while recording. Audio is fed to an incremental
+/// [`stt::SttStream`] so each update decodes only recent speech; adapters without streaming
+/// re-transcribe the whole buffer, but only for the first
+/// [`WHOLE_BUFFER_PARTIAL_MAX_SECONDS`]. Partials only reuse an already-loaded adapter; they
+/// never trigger a model load, and the final transcription waits for at most one short decode.
+async fn run_partial_transcription(capture: AudioCapture, app: AppHandle) {
+    let target_model = crate::models::active_model_value();
+    let mut decoder = PartialDecoder::default();
+    loop {
+        tokio::time::sleep(PARTIAL_TRANSCRIPTION_INTERVAL).await;
+        if !is_capture_recording(&capture) || capture.processing.load(Ordering::Acquire) {
+            break;
+        }
+
+        let format = capture
+            .format
+            .lock()
+            .map(|format| format.clone())
+            .unwrap_or_default();
+        let Ok(adapter_guard) = capture.stt_adapter.try_lock() else {
+            continue;
+        };
+        let model_matches = capture
+            .loaded_model
+            .try_lock()
+            .map(|loaded| loaded.as_deref() == Some(target_model.as_str()))
+            .unwrap_or(false);
+        let Some(adapter) = adapter_guard.as_ref().filter(|_| model_matches) else {
+            continue;
+        };
+
+        if decoder.stream.is_none() && !decoder.whole_buffer {
+            match adapter.create_stream(format.clone()) {
+                Ok(stream) => decoder.stream = Some(stream),
+                Err(SttError::StreamingUnsupported) => decoder.whole_buffer = true,
+                Err(err) => {
+                    if verbose_logs_enabled() {
+                        eprintln!("[stt] failed to start partial stream: {}", err);
+                    }
+                    continue;
+                }
+            }
+        }
+
+        let (pending, total_samples) = capture
+            .samples
+            .lock()
+            .map(|samples| {
+                let from = if decoder.whole_buffer {
+                    0
+                } else {
+                    decoder.fed_samples.min(samples.len())
+                };
+                (samples[from..].to_vec(), samples.len())
+            })
+            .unwrap_or_default();
+        if total_samples == 0 {
+            continue;
+        }
+        let audio_seconds = if format.sample_rate > 0 && format.channels > 0 {
+            total_samples as f32 / format.sample_rate as f32 / format.channels as f32
+        } else {
+            0.0
+        };
+        if decoder.whole_buffer && audio_seconds > WHOLE_BUFFER_PARTIAL_MAX_SECONDS {
+            // The buffer only grows; later decodes would keep the adapter locked even longer
+            break;
+        }
+
+        let text = match decoder.stream.take() {
+            Some(mut stream) => {
+                drop(adapter_guard);
+                let decoded = tauri::async_runtime::spawn_blocking(move || {
+                    stream.feed(&pending);
+                    let text = stream.partial();
+                    (stream, text)
+                })
+                .await;
+                let Ok((stream, text)) = decoded else {
+                    break;
+                };
+                decoder.stream = Some(stream);
+                decoder.fed_samples = total_samples;
+                Ok(text)
+            }
+            None => adapter.transcribe(&pending, format).await.map(|result| result.text),
+        };
+
+        match text {
+            Ok(text) if !text.trim().is_empty() => {
+                if !is_capture_recording(&capture) {
+                    break;
+                }
+                let (stable, tentative) = match capture.previous_partial.lock() {
+                    Ok(mut previous) => {
+                        let merged = merge_partial(&previous, &text);
+                        *previous = text;
+                        merged
+                    }
+                    Err(_) => (String::new(), text),
+                };
+                let _ = app.emit_all(
+                    "partial-transcription",
+                    PartialTranscriptionEvent {
+                        stable,
+                        tentative,
+                        audio_duration_s: audio_seconds,
+                    },
+                );
+            }
+            Ok(_) => {}
+            Err(err) => {
+                if verbose_logs_enabled() {
+                    eprintln!("[stt] partial transcription failed: {}", err);
+                }
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub fn start_recording(
+    state: tauri::State<AudioCapture>,
+    app: AppHandle,
+) -> Result<(), AudioPipelineError> {
+    start_recording_for_capture(state.inner(), app)
+}
+
+fn build_input_stream<T>(
     device: &Device,
     config: &StreamConfig,
     app: AppHandle,
@@ -820,39 +1661,225 @@ where
     Ok(stream)
 }
 
-pub async fn stop_recording_for_capture(
-    capture: AudioCapture,
-    app: AppHandle,
-) -> Result<(), String> {
-    let had_stream = {
-        let mut stream_lock = capture.stream.lock().unwrap();
-        stream_lock.stream.take().is_some()
-    };
-    if !had_stream {
+async fn ensure_adapter_for_model(
+    adapter_slot: &mut Option<Box<dyn SttAdapter>>,
+    loaded_model_slot: &mut Option<String>,
+    target_model: &str,
+) -> Result<(), AudioPipelineError> {
+    if adapter_slot.is_some() && loaded_model_slot.as_deref() == Some(target_model) {
         if verbose_logs_enabled() {
-            println!("[stt] stop_recording called but no active stream");
+            println!("[stt] reusing existing adapter for model: {}", target_model);
         }
         return Ok(());
     }
 
+    if let Some(mut previous) = adapter_slot.take() {
+        // Free the old model before loading the new one so both never sit in memory at once
+        if let Err(e) = previous.unload().await {
+            eprintln!("[stt] failed to unload previous model: {}", e);
+        }
+        *loaded_model_slot = None;
+    }
+
     if verbose_logs_enabled() {
-        println!("[stt] stop_recording: stream stopped, starting transcription");
+        println!("[stt] initializing adapter for model: {}", target_model);
+    }
+    // The registry holds just this model: the previous one was unloaded above
+    let mut adapter: Box<dyn SttAdapter> = Box::new(ModelRegistry::new(None).map_err(|e| {
+        let err_msg = format!("Failed to create adapter: {}", e);
+        eprintln!("{}", err_msg);
+        AudioPipelineError::ModelUnavailable(err_msg)
+    })?);
+    if let Err(e) = adapter
+        .initialize(crate::models::stt_config(target_model))
+        .await
+    {
+        let err_msg = format!("Failed to initialize adapter: {}", e);
+        eprintln!("{}", err_msg);
+        // Clean up on initialization failure
+        *adapter_slot = None;
+        *loaded_model_slot = None;
+        return Err(AudioPipelineError::ModelUnavailable(err_msg));
+    }
+    *adapter_slot = Some(adapter);
+    *loaded_model_slot = Some(target_model.to_string());
+    if verbose_logs_enabled() {
+        println!(
+            "[stt] adapter initialized successfully for model: {}",
+            target_model
+        );
     }
-    emit_transcription_status(&app, "processing", None);
+    Ok(())
+}
 
-    let audio_data = {
-        let mut samples = capture.samples.lock().unwrap();
-        std::mem::take(&mut *samples)
+/// Transcribes `samples` with `model` loaded into the dictation adapter, so checking a model
+/// never keeps a second copy of one in memory. If `model` is not the dictation model, the
+/// next dictation swaps its own back in.
+pub(crate) async fn transcribe_with_model(
+    capture: &AudioCapture,
+    model: &str,
+    samples: &[f32],
+    format: SttAudioFormat,
+) -> Result<String, AudioPipelineError> {
+    let mut adapter_guard = capture.stt_adapter.lock().await;
+    let mut loaded_model_guard = capture.loaded_model.lock().await;
+    ensure_adapter_for_model(&mut adapter_guard, &mut loaded_model_guard, model).await?;
+    capture.mark_stt_used();
+    let adapter = adapter_guard.as_ref().ok_or_else(|| {
+        AudioPipelineError::ModelUnavailable(format!("No adapter loaded for {}", model))
+    })?;
+    adapter
+        .transcribe(samples, format)
+        .await
+        .map(|transcription| transcription.text)
+        .map_err(|e| AudioPipelineError::TranscriptionFailed(e.to_string()))
+}
+
+/// Loads `model` into the dictation adapter and runs a throwaway decode, so the first
+/// dictation after selecting it skips model loading and GPU kernel compilation.
+pub async fn warm_up_model(capture: &AudioCapture, model: &str) {
+    let mut adapter_guard = capture.stt_adapter.lock().await;
+    let mut loaded_model_guard = capture.loaded_model.lock().await;
+    if let Err(err) =
+        ensure_adapter_for_model(&mut adapter_guard, &mut loaded_model_guard, model).await
+    {
+        eprintln!("[stt] warm-up skipped for {}: {}", model, err);
+        return;
+    }
+    capture.mark_stt_used();
+    let Some(adapter) = adapter_guard.as_ref() else {
+        return;
     };
 
+    let started = Instant::now();
+    match adapter.warm_up().await {
+        Ok(()) => {
+            if verbose_logs_enabled() {
+                println!(
+                    "[stt] warmed up model {} in {} ms",
+                    model,
+                    started.elapsed().as_millis()
+                );
+            }
+        }
+        Err(err) => eprintln!("[stt] warm-up failed for {}: {}", model, err),
+    }
+}
+
+/// How often [`unload_idle_model`] checks when the speech model was last used.
+const IDLE_UNLOAD_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Unloads the speech model once no dictation or file transcription has needed it for the
+/// `unload_model_after_mins` setting, returning its RAM/VRAM to the system. The next
+/// dictation loads it again.
+pub async fn unload_idle_model(capture: AudioCapture) {
+    loop {
+        tokio::time::sleep(IDLE_UNLOAD_POLL_INTERVAL).await;
+        let Some(minutes) = crate::store::get_settings().unload_model_after_mins else {
+            continue;
+        };
+        let Some(last_used) = capture
+            .stt_last_used
+            .lock()
+            .ok()
+            .and_then(|last_used| *last_used)
+        else {
+            continue;
+        };
+        if last_used.elapsed() < Duration::from_secs(minutes * 60) {
+            continue;
+        }
+
+        // A dictation holding the adapter is not idle; check again next round
+        let (Ok(mut adapter_guard), Ok(mut loaded_model_guard)) =
+            (capture.stt_adapter.try_lock(), capture.loaded_model.try_lock())
+        else {
+            continue;
+        };
+        if let Some(mut adapter) = adapter_guard.take() {
+            match adapter.unload().await {
+                Ok(()) => {
+                    if verbose_logs_enabled() {
+                        println!(
+                            "[stt] unloaded idle model {} after {} min",
+                            loaded_model_guard.as_deref().unwrap_or("unknown"),
+                            minutes
+                        );
+                    }
+                }
+                Err(e) => eprintln!("[stt] failed to unload idle model: {}", e),
+            }
+        }
+        *loaded_model_guard = None;
+        if let Ok(mut last_used) = capture.stt_last_used.lock() {
+            *last_used = None;
+        }
+    }
+}
+
+/// Frees `model` if it is the one loaded, e.g. before its files are deleted.
+pub async fn unload_model(capture: &AudioCapture, model: &str) {
+    let mut adapter_guard = capture.stt_adapter.lock().await;
+    let mut loaded_model_guard = capture.loaded_model.lock().await;
+    if loaded_model_guard.as_deref() != Some(model) {
+        return;
+    }
+    if let Some(mut adapter) = adapter_guard.take() {
+        if let Err(e) = adapter.unload().await {
+            eprintln!("[stt] failed to unload model {}: {}", model, e);
+        }
+    }
+    *loaded_model_guard = None;
+}
+
+/// Final `idle` of a dictation cycle; also hides the pill when it is only shown while dictating.
+fn emit_dictation_idle(app: &AppHandle) {
+    emit_transcription_status(app, "idle", None);
+    crate::release_overlay_after_dictation(app);
+}
+
+pub async fn stop_recording_for_capture(
+    capture: AudioCapture,
+    app: AppHandle,
+) -> Result<(), AudioPipelineError> {
+    let stop_stream = || capture.stream.lock().unwrap().stream.take().is_some();
+    let (_processing_guard, audio_data) =
+        match claim_capture(&capture.processing, stop_stream, &capture.samples) {
+            StopClaim::Captured { guard, samples } => (guard, samples),
+            StopClaim::NotRecording => {
+                if verbose_logs_enabled() {
+                    println!("[stt] stop_recording called but no active stream");
+                }
+                return Ok(());
+            }
+            StopClaim::Busy => {
+                eprintln!("[stt] previous dictation still processing, still recording");
+                emit_transcription_status(
+                    &app,
+                    "busy",
+                    Some("Previous dictation is still processing".to_string()),
+                );
+                return Ok(());
+            }
+        };
+
+    if verbose_logs_enabled() {
+        println!("[stt] stop_recording: stream stopped, starting transcription");
+    }
+    emit_processing_status_deferred(
+        &app,
+        crate::store::get_settings().min_processing_display_ms,
+    );
+
     if audio_data.is_empty() {
         if verbose_logs_enabled() {
             println!("[stt] no audio captured, skipping transcription");
         }
         // No audio to process, go idle immediately
-        emit_transcription_status(&app, "idle", None);
+        emit_dictation_idle(&app);
         return Ok(());
     }
+    check_microphone_permission(&app, &audio_data);
 
     let format = {
         let format = capture.format.lock().unwrap();
@@ -861,43 +1888,11 @@ pub async fn stop_recording_for_capture(
     let target_model = crate::models::active_model_value();
     let mut adapter_guard = capture.stt_adapter.lock().await;
     let mut loaded_model_guard = capture.loaded_model.lock().await;
-    if adapter_guard.is_none() || loaded_model_guard.as_deref() != Some(target_model.as_str()) {
-        if verbose_logs_enabled() {
-            println!("[stt] initializing adapter for model: {}", target_model);
-        }
-        let mut adapter = create_adapter().map_err(|e| {
-            let err_msg = format!("Failed to create adapter: {}", e);
-            eprintln!("{}", err_msg);
-            err_msg
-        })?;
-        adapter
-            .initialize(SttConfig {
-                model_name: target_model.clone(),
-                ..Default::default()
-            })
-            .await
-            .map_err(|e| {
-                let err_msg = format!("Failed to initialize adapter: {}", e);
-                eprintln!("{}", err_msg);
-                // Clean up on initialization failure
-                *adapter_guard = None;
-                *loaded_model_guard = None;
-                err_msg
-            })?;
-        *adapter_guard = Some(adapter);
-        *loaded_model_guard = Some(target_model.clone());
-        if verbose_logs_enabled() {
-            println!(
-                "[stt] adapter initialized successfully for model: {}",
-                target_model
-            );
-        }
-    } else if verbose_logs_enabled() {
-        println!("[stt] reusing existing adapter for model: {}", target_model);
-    }
-    let adapter = adapter_guard
-        .as_ref()
-        .ok_or_else(|| "STT adapter unavailable".to_string())?;
+    ensure_adapter_for_model(&mut adapter_guard, &mut loaded_model_guard, &target_model).await?;
+    capture.mark_stt_used();
+    let adapter = adapter_guard.as_ref().ok_or_else(|| {
+        AudioPipelineError::ModelUnavailable("STT adapter unavailable".to_string())
+    })?;
 
     let model_name = loaded_model_guard
         .as_deref()
@@ -941,8 +1936,16 @@ pub async fn stop_recording_for_capture(
         );
     }
 
-    match adapter.transcribe(&audio_data, format).await {
+    let started_at = chrono::Local::now().to_rfc3339();
+    let transcription_started = std::time::Instant::now();
+    let on_progress =
+        final_decode_progress(app.clone(), capture.previous_partial.clone(), audio_seconds);
+    match adapter
+        .transcribe_with_progress(&audio_data, format, on_progress)
+        .await
+    {
         Ok(result) => {
+            let processing_ms = transcription_started.elapsed().as_millis() as u64;
             let language = result
                 .language
                 .clone()
@@ -955,6 +1958,10 @@ pub async fn stop_recording_for_capture(
             );
             println!("[stt] transcript: {}", result.text);
 
+            if let Ok(mut last) = capture.last_transcript.lock() {
+                *last = Some(result.text.clone());
+            }
+
             // Update stats
             let duration = audio_seconds as f64;
             let word_count = result.text.split_whitespace().count() as u64;
@@ -963,6 +1970,9 @@ pub async fn stop_recording_for_capture(
             // Get formatting settings
             let settings = crate::store::get_settings();
             let mut final_text = result.text.clone();
+            if settings.spoken_punctuation {
+                final_text = text_processor::apply_spoken_punctuation(&final_text);
+            }
 
             // Apply text formatting if enabled
             if settings.text_formatting_enabled {
@@ -973,27 +1983,20 @@ pub async fn stop_recording_for_capture(
                     );
                 }
 
-                // Get active model for formatting
-                let format_model = settings
-                    .system_llm_model
-                    .unwrap_or_else(|| "SmolLM2-135M-Instruct-Q4_K_M".to_string());
-
-                let transcribed_text = result.text.clone();
+                let transcribed_text = final_text.clone();
                 let mode_str = settings.text_formatting_mode.clone();
 
                 // Process text asynchronously using tokio::spawn (we're already in async context)
                 // Use block_in_place to avoid blocking the runtime thread
                 match tokio::task::block_in_place(|| {
-                    tokio::runtime::Handle::current().block_on(async {
-                        use text_processor::{FormattingMode, TextProcessor};
-
-                        let mode = FormattingMode::from_str(&mode_str);
-                        let processor = TextProcessor::new(&format_model, mode).await?;
-                        processor.process(&transcribed_text).await
-                    })
+                    tokio::runtime::Handle::current()
+                        .block_on(format_transcript(&capture, &transcribed_text, &mode_str))
                 }) {
                     Ok(processing_result) => {
                         final_text = processing_result.formatted_text;
+                        let stats = text_processor::formatting_stats(&transcribed_text, &final_text);
+                        let _ = app.emit_all("session-stats", stats);
+                        crate::store::update_formatting_analytics(&app, &stats);
                         if verbose_logs_enabled() {
                             println!(
                                 "[formatting] complete in {}ms: {} -> {}",
@@ -1010,41 +2013,88 @@ pub async fn stop_recording_for_capture(
                 }
             }
 
-            // Paste synchronously BEFORE emitting events to ensure it completes
-            if verbose_logs_enabled() {
-                println!(
-                    "[paste] attempting to paste {} chars to active window",
-                    final_text.chars().count()
-                );
+            let formatting_disabled = !settings.text_formatting_enabled
+                || FormattingMode::try_from_str(&settings.text_formatting_mode)
+                    == Some(FormattingMode::Disabled);
+            if formatting_disabled && settings.always_capitalize_first {
+                final_text = capitalize_first_letter(&final_text);
             }
 
-            if let Err(err) = paste_text_preserving_clipboard(&final_text) {
-                eprintln!("[paste] ERROR failed to paste text: {}", err);
-            } else if verbose_logs_enabled() {
-                println!("[paste] paste completed successfully");
+            let target_app = paste_target_app_name();
+            if should_collapse_newlines(
+                settings.collapse_newlines,
+                &settings.single_line_apps,
+                target_app.as_deref(),
+            ) {
+                final_text = collapse_whitespace(&final_text);
             }
 
-            // Wait for paste to physically complete (osascript has 80ms delay on macOS)
-            // This ensures the text is actually typed before we emit "idle"
-            #[cfg(target_os = "macos")]
-            std::thread::sleep(std::time::Duration::from_millis(150));
-            
-            #[cfg(not(target_os = "macos"))]
-            std::thread::sleep(std::time::Duration::from_millis(100));
+            if should_skip_empty_transcript(&final_text, settings.paste_empty_transcripts) {
+                if verbose_logs_enabled() {
+                    println!("[paste] empty transcript, skipping paste");
+                }
+                emit_dictation_idle(&app);
+                return Ok(());
+            }
+
+            let review = settings.output_mode == OutputMode::Review;
+            if review {
+                // Review mode: leave insertion to the user and keep the captured target untouched
+                if let Err(err) = copy_text_to_clipboard(&final_text) {
+                    eprintln!("[paste] ERROR failed to copy text for review: {}", err);
+                } else if verbose_logs_enabled() {
+                    println!(
+                        "[paste] review mode, copied {} chars to clipboard",
+                        final_text.chars().count()
+                    );
+                }
+            } else {
+                // Paste synchronously BEFORE emitting events to ensure it completes
+                if verbose_logs_enabled() {
+                    println!(
+                        "[paste] attempting to paste {} chars to active window",
+                        final_text.chars().count()
+                    );
+                }
+
+                if let Err(err) = output_transcript(&final_text) {
+                    eprintln!("[paste] ERROR failed to paste text: {}", err);
+                } else if verbose_logs_enabled() {
+                    println!("[paste] paste completed successfully");
+                }
+
+                // Wait for paste to physically complete (osascript has 80ms delay on macOS)
+                // This ensures the text is actually typed before we emit "idle"
+                #[cfg(target_os = "macos")]
+                std::thread::sleep(std::time::Duration::from_millis(150));
+
+                #[cfg(not(target_os = "macos"))]
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
 
-            // Now emit result to UI
+            // Now emit result to UI; in review mode show exactly what is on the clipboard
             let _ = app.emit_all(
                 "transcription-result",
                 TranscriptionResultEvent {
-                    text: result.text.clone(),
+                    text: if review { final_text } else { result.text.clone() },
                     language: result.language.clone(),
+                    language_confidence: result.language_confidence,
                     confidence: result.confidence,
+                    quality: result.quality,
                     is_final: true,
+                    started_at,
+                    audio_duration_s: audio_seconds,
+                    processing_ms,
+                    model: model_name,
+                    review,
+                    debug_scores: result
+                        .scores
+                        .filter(|_| settings.show_scores || verbose_logs_enabled()),
                 },
             );
 
             // Set idle status AFTER paste is complete
-            emit_transcription_status(&app, "idle", None);
+            emit_dictation_idle(&app);
 
             if verbose_logs_enabled() {
                 println!("[stt] transcription cycle complete, ready for next run");
@@ -1059,7 +2109,7 @@ pub async fn stop_recording_for_capture(
             // Stay in error state - don't emit idle to avoid pill flickering
             // The next dictation cycle will reset to listening state
             println!("[stt] error reported, adapter still loaded for next run");
-            Err(message)
+            Err(AudioPipelineError::TranscriptionFailed(message))
         }
     }
 }
@@ -1068,10 +2118,153 @@ pub async fn stop_recording_for_capture(
 pub async fn stop_recording(
     state: tauri::State<'_, AudioCapture>,
     app: AppHandle,
-) -> Result<(), String> {
+) -> Result<(), AudioPipelineError> {
     stop_recording_for_capture(state.inner().clone(), app).await
 }
 
+/// Decodes and transcribes an audio file with the active model, reusing the dictation adapter.
+async fn transcribe_audio_file(
+    capture: &AudioCapture,
+    path: &str,
+) -> Result<String, AudioPipelineError> {
+    let (audio_data, format) = load_audio_file_for_stt(Path::new(path))?;
+    if audio_data.is_empty() {
+        return Err(AudioPipelineError::Other(format!(
+            "No audio samples decoded from {}",
+            path
+        )));
+    }
+
+    let target_model = crate::models::active_model_value();
+    let mut adapter_guard = capture.stt_adapter.lock().await;
+    let mut loaded_model_guard = capture.loaded_model.lock().await;
+    ensure_adapter_for_model(&mut adapter_guard, &mut loaded_model_guard, &target_model).await?;
+    capture.mark_stt_used();
+    let adapter = adapter_guard.as_ref().ok_or_else(|| {
+        AudioPipelineError::ModelUnavailable("STT adapter unavailable".to_string())
+    })?;
+
+    let result = adapter
+        .transcribe(&audio_data, format)
+        .await
+        .map_err(|e| AudioPipelineError::TranscriptionFailed(e.to_string()))?;
+    if verbose_logs_enabled() {
+        println!(
+            "[stt] file transcription complete path={} chars={}",
+            path,
+            result.text.chars().count()
+        );
+    }
+    Ok(result.text)
+}
+
+#[tauri::command]
+pub async fn transcribe_file(
+    state: tauri::State<'_, AudioCapture>,
+    path: String,
+) -> Result<String, AudioPipelineError> {
+    transcribe_audio_file(state.inner(), &path).await
+}
+
+/// Reads a WAV header so the UI can warn about, e.g., low sample rates before transcribing.
+#[tauri::command]
+pub fn probe_audio_file(path: String) -> Result<stt::WavInfo, String> {
+    stt::probe_wav(Path::new(&path)).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct FixtureTestResult {
+    passed: bool,
+    expected: String,
+    actual_text: String,
+    word_error_rate: f32,
+}
+
+/// Transcribes a recorded fixture with the current model/settings and checks that the
+/// transcript contains the words of `expected_substring` in order. Both sides are
+/// normalized as for the WER, so casing and punctuation do not matter. The reported WER is
+/// for the best-matching span of the transcript.
+#[tauri::command]
+pub async fn run_fixture_test(
+    state: tauri::State<'_, AudioCapture>,
+    wav_path: String,
+    expected_substring: String,
+) -> Result<FixtureTestResult, AudioPipelineError> {
+    let expected = stt::normalize_for_wer(&expected_substring);
+    if expected.is_empty() {
+        return Err(AudioPipelineError::Other(
+            "Fixture test needs expected text with at least one word".to_string(),
+        ));
+    }
+    let actual_text = transcribe_audio_file(state.inner(), &wav_path).await?;
+    let actual = stt::normalize_for_wer(&actual_text);
+    let passed = contains_words(&actual, &expected);
+    // Only the matched span counts; the fixture may hold more speech than the phrase
+    let word_error_rate = stt::span_word_error_rate(&expected, &actual);
+
+    Ok(FixtureTestResult {
+        passed,
+        expected: expected_substring,
+        actual_text,
+        word_error_rate,
+    })
+}
+
+/// Whether the words of `needle` appear consecutively in `haystack`, so "cat" does not
+/// match inside "concatenate".
+fn contains_words(haystack: &str, needle: &str) -> bool {
+    let haystack: Vec<&str> = haystack.split_whitespace().collect();
+    let needle: Vec<&str> = needle.split_whitespace().collect();
+    !needle.is_empty() && haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+#[tauri::command]
+pub async fn reformat_last(
+    state: tauri::State<'_, AudioCapture>,
+    mode: String,
+    repaste: Option<bool>,
+) -> Result<String, AudioPipelineError> {
+    let raw_text = state
+        .last_transcript
+        .lock()
+        .map_err(|_| "failed to read last transcript".to_string())?
+        .clone()
+        .ok_or_else(|| "No previous transcript to reformat".to_string())?;
+
+    let processing_result = format_transcript(state.inner(), &raw_text, &mode)
+        .await
+        .map_err(|e| format!("Failed to reformat transcript: {}", e))?;
+    if verbose_logs_enabled() {
+        println!(
+            "[formatting] reformatted last transcript with mode {} in {}ms",
+            mode, processing_result.processing_time_ms
+        );
+    }
+
+    if repaste.unwrap_or(false) {
+        output_transcript(&processing_result.formatted_text)
+            .map_err(AudioPipelineError::PasteFailed)?;
+    }
+
+    Ok(processing_result.formatted_text)
+}
+
+#[tauri::command]
+pub async fn set_formatting_mode(
+    state: tauri::State<'_, AudioCapture>,
+    app: AppHandle,
+    mode: String,
+) -> Result<(), String> {
+    let parsed = FormattingMode::try_from_str(&mode)
+        .ok_or_else(|| format!("Unknown formatting mode '{}'", mode))?;
+    crate::store::set_formatting_mode(&app, parsed.as_str().to_string());
+
+    if let Some(entry) = state.text_processor.lock().await.as_mut() {
+        entry.processor.set_mode(parsed);
+    }
+    Ok(())
+}
+
 #[derive(Serialize)]
 pub struct AudioDevice {
     id: String,
@@ -1079,9 +2272,11 @@ pub struct AudioDevice {
 }
 
 #[tauri::command]
-pub fn list_input_devices() -> Result<Vec<AudioDevice>, String> {
+pub fn list_input_devices() -> Result<Vec<AudioDevice>, AudioPipelineError> {
     let host = cpal::default_host();
-    let devices = host.input_devices().map_err(|e| e.to_string())?;
+    let devices = host
+        .input_devices()
+        .map_err(|e| AudioPipelineError::NoInputDevice(e.to_string()))?;
     let mut result = Vec::new();
     for device in devices {
         if let Ok(name) = device.name() {
@@ -1102,10 +2297,18 @@ pub fn set_input_device(app: AppHandle, device_id: String) -> Result<(), String>
 
 #[cfg(test)]
 mod tests {
-    use super::ffmpeg_normalize_args;
+    use super::{
+        capitalize_first_letter, claim_capture, collapse_whitespace, contains_words,
+        ffmpeg_decode_to_wav, ffmpeg_normalize_args, is_digital_silence, merge_partial,
+        output_transcript, partials_enabled_for_model, record_capture_silence,
+        should_collapse_newlines, should_skip_empty_transcript, should_type_text,
+        AudioPipelineError, ProcessingGuard, StopClaim, SILENT_CAPTURES_BEFORE_PERMISSION_HINT,
+    };
     #[cfg(target_os = "macos")]
     use super::parse_frontmost_pid;
     use std::path::Path;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn ffmpeg_normalize_args_target_whisper_contract() {
@@ -1118,6 +2321,176 @@ mod tests {
         assert!(args.iter().any(|arg| arg == "s16"));
     }
 
+    #[test]
+    fn capitalize_first_letter_skips_leading_quotes() {
+        assert_eq!(capitalize_first_letter("\"hello there\""), "\"Hello there\"");
+        assert_eq!(capitalize_first_letter("  ¿ñandú?"), "  ¿Ñandú?");
+        assert_eq!(capitalize_first_letter("Already fine"), "Already fine");
+        assert_eq!(capitalize_first_letter("123 ..."), "123 ...");
+    }
+
+    #[test]
+    fn fixture_match_is_by_whole_normalized_words() {
+        let actual = stt::normalize_for_wer("So, the CAT sat down.");
+        assert!(contains_words(&actual, &stt::normalize_for_wer("the cat sat")));
+        assert!(!contains_words(&actual, "at sat"));
+        assert!(!contains_words(&actual, "cat down"));
+        assert!(!contains_words(&actual, ""));
+    }
+
+    #[test]
+    fn merge_partial_keeps_common_word_prefix_stable() {
+        assert_eq!(
+            merge_partial("", "hello world"),
+            (String::new(), "hello world".to_string())
+        );
+        assert_eq!(
+            merge_partial("hello word", "hello world how are"),
+            ("hello".to_string(), "world how are".to_string())
+        );
+        assert_eq!(
+            merge_partial("hello world how", "hello world how are you"),
+            ("hello world how".to_string(), "are you".to_string())
+        );
+    }
+
+    #[test]
+    fn audio_pipeline_error_serializes_code_and_message() {
+        let payload = serde_json::to_value(AudioPipelineError::NoInputDevice(
+            "No input device available".to_string(),
+        ))
+        .unwrap();
+        assert_eq!(payload["code"], "no_input_device");
+        assert_eq!(payload["message"], "No input device available");
+
+        let payload = serde_json::to_value(AudioPipelineError::FfmpegMissing).unwrap();
+        assert_eq!(payload["code"], "ffmpeg_missing");
+    }
+
+    #[test]
+    fn empty_transcript_produces_no_paste() {
+        assert!(should_skip_empty_transcript("", false));
+        assert!(should_skip_empty_transcript(" \n\t", false));
+        assert!(!should_skip_empty_transcript("hello", false));
+        assert!(!should_skip_empty_transcript("  ", true));
+        // Even with the setting on, blank text returns before touching the clipboard
+        assert!(output_transcript(" \n").is_ok());
+    }
+
+    #[test]
+    fn repeated_digital_silence_triggers_permission_hint() {
+        assert!(is_digital_silence(&[0.0; 512]));
+        assert!(!is_digital_silence(&[0.0, 0.001, 0.0]));
+
+        let counter = AtomicU32::new(0);
+        for _ in 1..SILENT_CAPTURES_BEFORE_PERMISSION_HINT {
+            assert!(!record_capture_silence(&counter, true));
+        }
+        assert!(record_capture_silence(&counter, true));
+
+        // Any real signal resets the streak
+        assert!(!record_capture_silence(&counter, false));
+        assert!(!record_capture_silence(&counter, true));
+    }
+
+    #[test]
+    fn should_type_text_only_below_threshold() {
+        assert!(!should_type_text("hello", None));
+        assert!(should_type_text("hello", Some(6)));
+        assert!(!should_type_text("hello", Some(5)));
+        // Counts characters, not bytes
+        assert!(should_type_text("héllo", Some(6)));
+    }
+
+    #[test]
+    fn collapse_whitespace_flattens_paragraphs() {
+        let input = "First paragraph here.\n\nSecond one,\n  with a wrapped line.\r\n";
+        assert_eq!(
+            collapse_whitespace(input),
+            "First paragraph here. Second one, with a wrapped line."
+        );
+    }
+
+    #[test]
+    fn collapse_newlines_applies_per_app() {
+        let apps = vec!["Slack".to_string()];
+        assert!(should_collapse_newlines(true, &[], None));
+        assert!(should_collapse_newlines(false, &apps, Some("slack")));
+        assert!(!should_collapse_newlines(false, &apps, Some("Xcode")));
+        assert!(!should_collapse_newlines(false, &apps, None));
+    }
+
+    #[test]
+    fn processing_guard_rejects_overlapping_stop() {
+        let flag = Arc::new(AtomicBool::new(false));
+
+        let first = ProcessingGuard::acquire(&flag);
+        assert!(first.is_some());
+        // A second rapid stop while the first is in flight must not start another cycle.
+        assert!(ProcessingGuard::acquire(&flag).is_none());
+
+        drop(first);
+        assert!(!flag.load(Ordering::Acquire));
+        assert!(ProcessingGuard::acquire(&flag).is_some());
+    }
+
+    #[test]
+    fn rapid_second_stop_keeps_its_capture_until_the_first_finishes() {
+        let processing = Arc::new(AtomicBool::new(false));
+        let samples = Mutex::new(vec![0.1, 0.2]);
+        let recording = std::cell::Cell::new(true);
+        let stop_stream = || recording.replace(false);
+
+        let StopClaim::Captured {
+            guard: first,
+            samples: first_samples,
+        } = claim_capture(&processing, stop_stream, &samples)
+        else {
+            panic!("first stop should claim its capture");
+        };
+        assert_eq!(first_samples, [0.1, 0.2]);
+
+        // A second dictation starts and is stopped while the first is still processing
+        recording.set(true);
+        samples.lock().unwrap().extend([0.3, 0.4]);
+        assert!(matches!(
+            claim_capture(&processing, stop_stream, &samples),
+            StopClaim::Busy
+        ));
+        assert!(
+            recording.get(),
+            "a rejected stop must not end the recording"
+        );
+        assert_eq!(*samples.lock().unwrap(), [0.3, 0.4]);
+
+        drop(first);
+        let StopClaim::Captured {
+            samples: second_samples,
+            ..
+        } = claim_capture(&processing, stop_stream, &samples)
+        else {
+            panic!("stopping again after the first finished should claim the capture");
+        };
+        assert_eq!(second_samples, [0.3, 0.4]);
+        assert!(matches!(
+            claim_capture(&processing, stop_stream, &samples),
+            StopClaim::NotRecording
+        ));
+    }
+
+    #[test]
+    fn partials_skip_disabled_setting_and_mlx_models() {
+        assert!(partials_enabled_for_model(true, "base"));
+        assert!(!partials_enabled_for_model(false, "base"));
+        assert!(!partials_enabled_for_model(true, stt::MLX_PARAKEET_V2_MODEL));
+    }
+
+    #[test]
+    fn ffmpeg_decode_to_wav_rejects_missing_input() {
+        let err = ffmpeg_decode_to_wav(Path::new("definitely-missing-input.flac")).unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+
     #[cfg(target_os = "macos")]
     #[test]
     fn parse_frontmost_pid_filters_invalid_values() {
@@ -76,7 +76,7 @@ fn start_capture(state: &FnHoldState) -> Result<(), String> {
     let app_handle = state.app.clone();
     audio::remember_active_paste_target();
     crate::show_main_overlay_window(&state.app);
-    audio::start_recording_for_capture(&capture, app_handle)
+    audio::start_recording_for_capture(&capture, app_handle).map_err(|e| e.to_string())
 }
 
 fn stop_capture(state: &FnHoldState) {
@@ -1,29 +1,7 @@
+use llm::adapters::ollama::{normalize_base_url, OllamaModel, OllamaTagsResponse};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct OllamaModel {
-    pub name: String,
-    pub size: u64,
-    pub digest: String,
-    pub details: Option<OllamaModelDetails>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct OllamaModelDetails {
-    pub format: String,
-    pub family: String,
-    pub families: Option<Vec<String>>,
-    pub parameter_size: String,
-    pub quantization_level: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct OllamaTagsResponse {
-    models: Vec<OllamaModel>,
-}
-
 #[tauri::command]
 pub async fn get_ollama_models(base_url: String) -> Result<Vec<OllamaModel>, String> {
     let client = Client::builder()
@@ -31,14 +9,7 @@ pub async fn get_ollama_models(base_url: String) -> Result<Vec<OllamaModel>, Str
         .build()
         .map_err(|e| format!("Failed to build client: {}", e))?;
 
-    // Ensure URL has protocol
-    let url = if base_url.starts_with("http") {
-        base_url
-    } else {
-        format!("http://{}", base_url)
-    };
-
-    let tags_url = format!("{}/api/tags", url.trim_end_matches('/'));
+    let tags_url = format!("{}/api/tags", normalize_base_url(&base_url));
 
     let response = client
         .get(&tags_url)
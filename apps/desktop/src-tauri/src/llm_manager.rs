@@ -2,6 +2,8 @@ use llm::{models, LlmModelInfo};
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager};
 
+use crate::models::ProgressReporter;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ModelDownloadProgressEvent {
     pub model: String,
@@ -21,32 +23,59 @@ pub fn list_llm_models() -> Result<Vec<LlmModelInfo>, String> {
 
 #[tauri::command]
 pub async fn download_llm_model(app: AppHandle, model: String) -> Result<(), String> {
-    let model_clone = model.clone();
-    let app_clone = app.clone();
-    
-    models::download_model(&model, Some(Box::new(move |downloaded, total| {
-        let percent = if total > 0 {
-            (downloaded as f32 / total as f32) * 100.0
-        } else {
-            0.0
-        };
-        
-        let _ = app_clone.emit_all(
+    download_llm_model_with_progress(app, model, None).await
+}
+
+/// [`download_llm_model`], also reporting the item percent to `on_percent`. Waits its turn
+/// in the same queue as STT downloads.
+pub(crate) async fn download_llm_model_with_progress(
+    app: AppHandle,
+    model: String,
+    on_percent: Option<ProgressReporter>,
+) -> Result<(), String> {
+    let _download_slot = crate::models::enter_download_queue(|message| {
+        let _ = app.emit_all(
             "llm-model-download-progress",
             ModelDownloadProgressEvent {
-                model: model_clone.clone(),
-                stage: "downloading".to_string(),
-                downloaded_bytes: downloaded,
-                total_bytes: Some(total),
-                percent: Some(percent),
+                model: model.clone(),
+                stage: "queued".to_string(),
+                downloaded_bytes: 0,
+                total_bytes: None,
+                percent: Some(0.0),
                 done: false,
                 error: None,
-                message: None,
+                message: Some(message.to_string()),
+            },
+        );
+    })
+    .await;
+
+    let progress_app = app.clone();
+    let on_progress = move |progress: models::ModelDownloadProgress| {
+        // Verification restarts at zero, so only download bytes drive the item percent
+        if progress.stage == "download" {
+            if let (Some(report), Some(percent)) = (&on_percent, progress.percent) {
+                report(percent);
+            }
+        }
+
+        let _ = progress_app.emit_all(
+            "llm-model-download-progress",
+            ModelDownloadProgressEvent {
+                model: progress.model_name,
+                stage: progress.stage,
+                downloaded_bytes: progress.downloaded_bytes,
+                total_bytes: progress.total_bytes,
+                percent: progress.percent,
+                done: progress.done,
+                error: progress.error,
+                message: progress.message,
             },
         );
-    })))
-    .await
-    .map_err(|e| e.to_string())?;
+    };
+    models::download_model(&model, Some(on_progress))
+        .await
+        .map_err(|e| e.to_string())?;
 
     // Emit completion event
     let _ = app.emit_all(
@@ -73,7 +102,16 @@ pub fn get_active_llm_model() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub fn set_active_llm_model(app: AppHandle, model: String) -> Result<(), String> {
+pub async fn set_active_llm_model(
+    app: AppHandle,
+    capture: tauri::State<'_, crate::audio::AudioCapture>,
+    model: String,
+) -> Result<(), String> {
+    let changed = crate::store::get_system_llm_model().as_deref() != Some(model.as_str());
     crate::store::set_system_llm_model(&app, model);
+    // Stored first so a dictation formatting meanwhile loads the new model, not the old one
+    if changed {
+        crate::audio::unload_text_processor(capture.inner()).await;
+    }
     Ok(())
 }
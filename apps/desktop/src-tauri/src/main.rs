@@ -2,6 +2,7 @@
 
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 use device_query::{DeviceQuery, DeviceState, Keycode};
+use std::io::Write;
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 use std::sync::{Arc, Mutex};
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
@@ -23,14 +24,37 @@ mod llm_manager;
 mod models;
 mod store;
 use audio::AudioCapture;
-use store::init_store;
+use store::{init_store, OverlayBehavior};
 
 fn verbose_logs_enabled() -> bool {
-    std::env::var("OPENWISPR_VERBOSE_LOGS")
-        .ok()
-        .as_deref()
-        .map(|v| v == "1")
-        .unwrap_or(false)
+    stt::verbose_logs_enabled()
+}
+
+#[tauri::command]
+fn get_verbose_logging() -> bool {
+    verbose_logs_enabled()
+}
+
+/// Toggle verbose logs without a relaunch; `OPENWISPR_VERBOSE_LOGS` only sets the startup value.
+#[tauri::command]
+fn set_verbose_logging(enabled: bool) {
+    stt::set_verbose_logs_enabled(enabled);
+}
+
+/// Restores system state we may have changed mid-dictation and flushes output before quitting.
+fn shutdown(app_handle: &tauri::AppHandle<Wry>) {
+    if verbose_logs_enabled() {
+        println!("[lifecycle] shutting down");
+    }
+    release_system_state(app_handle);
+    app_handle.exit(0);
+}
+
+fn release_system_state(app_handle: &tauri::AppHandle<Wry>) {
+    audio::restore_pending_clipboard();
+    store::flush_store(app_handle);
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
 }
 
 fn show_models_window(app_handle: &tauri::AppHandle<Wry>) {
@@ -42,6 +66,9 @@ fn show_models_window(app_handle: &tauri::AppHandle<Wry>) {
 }
 
 pub(crate) fn show_main_overlay_window(app_handle: &tauri::AppHandle<Wry>) {
+    if store::get_settings().overlay_behavior == OverlayBehavior::Hidden {
+        return;
+    }
     if let Some(window) = app_handle.get_window("main") {
         if let Ok(Some(monitor)) = window.current_monitor() {
             let monitor_pos = monitor.position();
@@ -84,6 +111,19 @@ pub(crate) fn show_main_overlay_window(app_handle: &tauri::AppHandle<Wry>) {
     }
 }
 
+pub(crate) fn hide_main_overlay_window(app_handle: &tauri::AppHandle<Wry>) {
+    if let Some(window) = app_handle.get_window("main") {
+        let _ = window.hide();
+    }
+}
+
+/// Called once a dictation settles back to idle; only `DuringDictation` hides the pill.
+pub(crate) fn release_overlay_after_dictation(app_handle: &tauri::AppHandle<Wry>) {
+    if store::get_settings().overlay_behavior == OverlayBehavior::DuringDictation {
+        hide_main_overlay_window(app_handle);
+    }
+}
+
 fn main() {
     let dashboard = CustomMenuItem::new("dashboard".to_string(), "Dashboard");
     let quit = CustomMenuItem::new("quit".to_string(), "Quit");
@@ -107,7 +147,7 @@ fn main() {
                     show_models_window(app_handle);
                 }
                 "quit" => {
-                    app_handle.exit(0);
+                    shutdown(app_handle);
                 }
                 _ => {}
             },
@@ -116,6 +156,15 @@ fn main() {
         .setup(|app| {
             let handle = app.handle();
             init_store(&handle);
+            models::apply_model_cache_dir(store::get_settings().model_cache_dir.as_deref());
+            platform::mirror::set_huggingface_mirror(store::get_settings().hf_mirror);
+            tauri::async_runtime::spawn_blocking(stt::sweep_partial_downloads);
+            if store::get_settings().auto_select_best_downloaded {
+                tauri::async_runtime::spawn(models::auto_select_best_downloaded_model());
+            }
+            tauri::async_runtime::spawn(audio::unload_idle_model(
+                app.state::<AudioCapture>().inner().clone(),
+            ));
             if let Some(main_window) = app.get_window("main") {
                 // Keep overlay non-interactive so it does not block the active app
                 // while still allowing us to keep the process alive.
@@ -139,6 +188,10 @@ fn main() {
                 app.set_activation_policy(ActivationPolicy::Accessory);
             }
 
+            if store::get_settings().overlay_behavior == OverlayBehavior::AlwaysOn {
+                show_main_overlay_window(&handle);
+            }
+
             if verbose_logs_enabled() {
                 println!("\n==============================================");
                 println!("🎙️  OpenWispr Starting...");
@@ -215,10 +268,23 @@ fn main() {
             audio::stop_recording,
             audio::list_input_devices,
             audio::set_input_device,
+            audio::reformat_last,
+            audio::transcribe_file,
+            audio::probe_audio_file,
+            audio::run_fixture_test,
+            audio::set_formatting_mode,
             models::list_models,
             models::download_model,
+            models::delete_model,
+            models::cancel_model_setup,
+            models::download_recommended_models,
+            models::cancel_recommended_downloads,
             models::get_active_model,
             models::set_active_model,
+            models::validate_model,
+            models::import_model,
+            models::get_cache_paths,
+            models::move_cache_dir,
             store::get_analytics_stats,
             store::set_transcription_enabled,
             store::set_language,
@@ -226,6 +292,30 @@ fn main() {
             store::set_shortcuts,
             store::set_llm_settings,
             store::set_formatting_settings,
+            store::set_partials_enabled,
+            store::set_auto_select_best_downloaded,
+            store::set_output_mode,
+            store::set_clipboard_restore,
+            store::set_type_below_chars,
+            store::set_paste_empty_transcripts,
+            store::set_min_processing_display_ms,
+            store::set_overlay_behavior,
+            store::set_spoken_punctuation,
+            store::set_min_gpu_memory_mb,
+            store::set_unload_model_after_mins,
+            store::set_trim_silence,
+            store::set_decode_settings,
+            store::set_normalization_settings,
+            store::set_hf_mirror,
+            store::set_compute_backend,
+            store::set_sherpa_provider,
+            store::set_translate,
+            store::set_single_line_output,
+            store::set_show_scores,
+            store::set_always_capitalize_first,
+            store::set_personal_dictionary,
+            get_verbose_logging,
+            set_verbose_logging,
             llm_client::get_ollama_models,
             llm_manager::list_llm_models,
             llm_manager::download_llm_model,
@@ -263,6 +353,7 @@ fn main() {
             if verbose_logs_enabled() {
                 println!("[lifecycle] run loop exiting");
             }
+            release_system_state(app_handle);
         }
         _ => {}
     });
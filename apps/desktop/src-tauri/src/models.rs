@@ -1,10 +1,15 @@
 use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
+use llm::LlmAdapter;
+use platform::relocate::Relocation;
 use stt::{
-    create_adapter, is_mlx_model_name, is_sherpa_model_name, set_model_download_progress_handler,
-    ModelDownloadProgress, SttConfig, MLX_PARAKEET_V2_MODEL, SHERPA_PARAKEET_INT8_MODEL,
+    create_adapter, download_model_with_progress, is_mlx_model_name, is_sherpa_model_name,
+    AudioFormat, DecodeTuning, NormalizationTuning, SttConfig, SttError, TranscriptionTask,
 };
 use tauri::Manager;
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ModelInfo {
@@ -13,6 +18,12 @@ pub struct ModelInfo {
     pub downloaded: bool,
     pub can_download: bool,
     pub note: Option<String>,
+    pub approx_size_mb: u64,
+    // Actual bytes in the model cache; 0 until downloaded
+    pub disk_bytes: u64,
+    pub languages: Vec<String>,
+    pub multilingual: bool,
+    pub recommended: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -27,10 +38,49 @@ struct ModelDownloadProgressEvent {
     message: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct ModelValidationEvent {
+    model: String,
+    stage: String,
+    done: bool,
+    ok: bool,
+    error: Option<String>,
+    message: Option<String>,
+}
+
+fn emit_model_validation_event(app: &tauri::AppHandle, event: &str, payload: ModelValidationEvent) {
+    let _ = app.emit_all(event, payload);
+}
+
 fn emit_model_download_progress_event(app: &tauri::AppHandle, payload: ModelDownloadProgressEvent) {
     let _ = app.emit_all("model-download-progress", payload);
 }
 
+/// Downloads run one at a time: parallel model downloads only split the bandwidth and pile
+/// up partial files on disk. Deleting a model or moving the cache is refused while the queue
+/// is held, so no download is writing into the files being changed.
+fn download_queue() -> &'static AsyncMutex<()> {
+    static QUEUE: OnceLock<AsyncMutex<()>> = OnceLock::new();
+    QUEUE.get_or_init(|| AsyncMutex::new(()))
+}
+
+/// Waits for the [`download_queue`], first passing `on_queued` the message to show meanwhile.
+pub(crate) async fn enter_download_queue(
+    on_queued: impl FnOnce(&str),
+) -> AsyncMutexGuard<'static, ()> {
+    let queue = download_queue();
+    let slot = queue.try_lock();
+    on_queued(if slot.is_ok() {
+        "Queued for download"
+    } else {
+        "Waiting for another download to finish"
+    });
+    match slot {
+        Ok(guard) => guard,
+        Err(_) => queue.lock().await,
+    }
+}
+
 fn active_model_store() -> &'static Mutex<String> {
     static ACTIVE_MODEL: OnceLock<Mutex<String>> = OnceLock::new();
     ACTIVE_MODEL.get_or_init(|| Mutex::new("base".to_string()))
@@ -48,35 +98,24 @@ pub async fn list_models() -> Result<Vec<ModelInfo>, String> {
     let adapter = create_adapter().map_err(|e| e.to_string())?;
     let mut result = Vec::new();
 
-    for name in adapter.available_models() {
-        let downloaded = adapter.is_model_available(&name).await;
+    for model in adapter.available_model_descriptors() {
+        let downloaded = adapter.is_model_available(&model.name).await;
+        let disk_bytes = if downloaded {
+            stt::model_disk_usage(&model.name).unwrap_or(0)
+        } else {
+            0
+        };
         result.push(ModelInfo {
-            name,
-            runtime: "whisper.cpp".to_string(),
+            name: model.name,
+            runtime: model.runtime,
             downloaded,
             can_download: true,
-            note: None,
-        });
-    }
-
-    let sherpa_downloaded = adapter.is_model_available(SHERPA_PARAKEET_INT8_MODEL).await;
-    result.push(ModelInfo {
-        name: SHERPA_PARAKEET_INT8_MODEL.to_string(),
-        runtime: "sherpa-onnx".to_string(),
-        downloaded: sherpa_downloaded,
-        can_download: true,
-        note: Some("NVIDIA Parakeet TDT v2 int8".to_string()),
-    });
-
-    #[cfg(target_os = "macos")]
-    {
-        let mlx_downloaded = adapter.is_model_available(MLX_PARAKEET_V2_MODEL).await;
-        result.push(ModelInfo {
-            name: MLX_PARAKEET_V2_MODEL.to_string(),
-            runtime: "mlx-parakeet".to_string(),
-            downloaded: mlx_downloaded,
-            can_download: true,
-            note: Some("Parakeet MLX community model".to_string()),
+            note: model.note,
+            approx_size_mb: model.approx_size_mb,
+            disk_bytes,
+            languages: model.languages,
+            multilingual: model.multilingual,
+            recommended: model.recommended,
         });
     }
 
@@ -85,51 +124,66 @@ pub async fn list_models() -> Result<Vec<ModelInfo>, String> {
 
 #[tauri::command]
 pub async fn download_model(app: tauri::AppHandle, model: String) -> Result<(), String> {
-    let model_for_callback = model.clone();
-    let app_for_callback = app.clone();
-    set_model_download_progress_handler(Some(Arc::new(move |progress: ModelDownloadProgress| {
-        if progress.model_name != model_for_callback {
-            return;
-        }
+    download_stt_model(app, model, None).await
+}
+
+/// Per-item download percent (0-100) for callers aggregating several downloads.
+pub(crate) type ProgressReporter = Arc<dyn Fn(f32) + Send + Sync>;
+
+async fn download_stt_model(
+    app: tauri::AppHandle,
+    model: String,
+    on_percent: Option<ProgressReporter>,
+) -> Result<(), String> {
+    let _download_slot = enter_download_queue(|message| {
         emit_model_download_progress_event(
-            &app_for_callback,
+            &app,
             ModelDownloadProgressEvent {
-                model: progress.model_name,
-                stage: progress.stage,
-                downloaded_bytes: progress.downloaded_bytes,
-                total_bytes: progress.total_bytes,
-                percent: progress.percent,
-                done: progress.done,
-                error: progress.error,
-                message: progress.message,
+                model: model.clone(),
+                stage: "queued".to_string(),
+                downloaded_bytes: 0,
+                total_bytes: None,
+                percent: Some(0.0),
+                done: false,
+                error: None,
+                message: Some(message.to_string()),
             },
-        );
-    })));
+        )
+    })
+    .await;
 
-    emit_model_download_progress_event(
-        &app,
-        ModelDownloadProgressEvent {
-            model: model.clone(),
-            stage: "queued".to_string(),
-            downloaded_bytes: 0,
-            total_bytes: None,
-            percent: Some(0.0),
-            done: false,
-            error: None,
-            message: Some("Queued for download".to_string()),
-        },
-    );
+    let (download, mut progress_events) = download_model_with_progress(&model);
+    let app_for_progress = app.clone();
+    let forward_progress = tauri::async_runtime::spawn(async move {
+        while let Some(progress) = progress_events.recv().await {
+            if let (Some(report), Some(percent)) = (&on_percent, progress.percent) {
+                report(percent);
+            }
+            emit_model_download_progress_event(
+                &app_for_progress,
+                ModelDownloadProgressEvent {
+                    model: progress.model_name,
+                    stage: progress.stage,
+                    downloaded_bytes: progress.downloaded_bytes,
+                    total_bytes: progress.total_bytes,
+                    percent: progress.percent,
+                    done: progress.done,
+                    error: progress.error,
+                    message: progress.message,
+                },
+            );
+        }
+    });
 
-    let mut adapter = create_adapter().map_err(|e| e.to_string())?;
-    let result = adapter
-        .initialize(SttConfig {
-            model_name: model.clone(),
-            ..Default::default()
-        })
-        .await
-        .map_err(|e| e.to_string());
+    let result = download.await;
+    // The channel closes with the download; let the last events out before the final one
+    let _ = forward_progress.await;
 
-    set_model_download_progress_handler(None);
+    // The adapter already emitted the `cancelled` stage; don't report it as a failure
+    let result = match result {
+        Err(SttError::Cancelled) => return Err(SttError::Cancelled.to_string()),
+        other => other.map_err(|e| to_user_facing_download_error(&e)),
+    };
 
     match result {
         Ok(_) => {
@@ -190,6 +244,368 @@ pub async fn download_model(app: tauri::AppHandle, model: String) -> Result<(),
     }
 }
 
+/// Removes a downloaded model from the cache, unloading it first if it is in memory. When it
+/// was the active model, the best remaining downloaded model takes its place.
+#[tauri::command]
+pub async fn delete_model(
+    capture: tauri::State<'_, crate::audio::AudioCapture>,
+    model: String,
+) -> Result<(), String> {
+    let Ok(_queue) = download_queue().try_lock() else {
+        return Err("Wait for model downloads to finish before deleting a model".to_string());
+    };
+    crate::audio::unload_model(capture.inner(), &model).await;
+
+    let model_for_delete = model.clone();
+    tauri::async_runtime::spawn_blocking(move || stt::delete_model(&model_for_delete))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    if active_model_value() == model {
+        auto_select_best_downloaded_model().await;
+    }
+    Ok(())
+}
+
+/// Download failure text for the UI. A missing prerequisite already carries a hint written
+/// for users, so it is shown as is.
+fn to_user_facing_download_error(error: &SttError) -> String {
+    match error {
+        SttError::RuntimeMissing { hint, .. } => hint.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Small English whisper model fetched by the one-click setup.
+const RECOMMENDED_WHISPER_MODEL: &str = "base.en";
+
+static RECOMMENDED_DOWNLOADS_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+struct RecommendedModelsProgressEvent {
+    current_model: Option<String>,
+    /// Zero-based position of `current_model` in the set.
+    item_index: usize,
+    item_count: usize,
+    percent: f32,
+    done: bool,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+fn emit_recommended_models_progress(
+    app: &tauri::AppHandle,
+    payload: RecommendedModelsProgressEvent,
+) {
+    let _ = app.emit_all("recommended-models-progress", payload);
+}
+
+enum RecommendedModel {
+    Stt(String),
+    Llm(String),
+}
+
+impl RecommendedModel {
+    fn name(&self) -> &str {
+        match self {
+            Self::Stt(name) | Self::Llm(name) => name,
+        }
+    }
+}
+
+/// One small whisper model plus the configured formatting LLM.
+fn recommended_models() -> Vec<RecommendedModel> {
+    let llm_model = crate::store::get_system_llm_model()
+        .filter(|name| llm::models::find_model(name).is_some())
+        .unwrap_or_else(|| llm::models::AVAILABLE_MODELS[0].0.to_string());
+    vec![
+        RecommendedModel::Stt(RECOMMENDED_WHISPER_MODEL.to_string()),
+        RecommendedModel::Llm(llm_model),
+    ]
+}
+
+/// Overall percent with `item_index` items finished and the current one at `item_percent`.
+fn aggregate_percent(item_index: usize, item_count: usize, item_percent: f32) -> f32 {
+    if item_count == 0 {
+        return 100.0;
+    }
+    let item_fraction = (item_percent / 100.0).clamp(0.0, 1.0);
+    ((item_index as f32 + item_fraction) / item_count as f32) * 100.0
+}
+
+/// Downloads the recommended models one after another through the regular per-model paths,
+/// emitting `recommended-models-progress` with the overall percent. Returns the model names.
+#[tauri::command]
+pub async fn download_recommended_models(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    RECOMMENDED_DOWNLOADS_CANCELLED.store(false, Ordering::Release);
+    let items = recommended_models();
+    let item_count = items.len();
+    let progress = move |item_index: usize, current: Option<&str>, percent: f32| {
+        RecommendedModelsProgressEvent {
+            current_model: current.map(str::to_string),
+            item_index,
+            item_count,
+            percent,
+            done: false,
+            cancelled: false,
+            error: None,
+        }
+    };
+
+    for (item_index, item) in items.iter().enumerate() {
+        if RECOMMENDED_DOWNLOADS_CANCELLED.load(Ordering::Acquire) {
+            emit_recommended_models_progress(
+                &app,
+                RecommendedModelsProgressEvent {
+                    done: true,
+                    cancelled: true,
+                    ..progress(item_index, None, aggregate_percent(item_index, item_count, 0.0))
+                },
+            );
+            return Err(SttError::Cancelled.to_string());
+        }
+
+        let name = item.name().to_string();
+        emit_recommended_models_progress(
+            &app,
+            progress(item_index, Some(&name), aggregate_percent(item_index, item_count, 0.0)),
+        );
+        let report: ProgressReporter = {
+            let app = app.clone();
+            let name = name.clone();
+            Arc::new(move |item_percent: f32| {
+                emit_recommended_models_progress(
+                    &app,
+                    progress(
+                        item_index,
+                        Some(&name),
+                        aggregate_percent(item_index, item_count, item_percent),
+                    ),
+                );
+            })
+        };
+        let result = match item {
+            RecommendedModel::Stt(_) => {
+                download_stt_model(app.clone(), name.clone(), Some(report)).await
+            }
+            RecommendedModel::Llm(_) => {
+                crate::llm_manager::download_llm_model_with_progress(
+                    app.clone(),
+                    name.clone(),
+                    Some(report),
+                )
+                .await
+            }
+        };
+        if let Err(error) = result {
+            if RECOMMENDED_DOWNLOADS_CANCELLED.load(Ordering::Acquire) {
+                emit_recommended_models_progress(
+                    &app,
+                    RecommendedModelsProgressEvent {
+                        done: true,
+                        cancelled: true,
+                        ..progress(
+                            item_index,
+                            Some(&name),
+                            aggregate_percent(item_index, item_count, 0.0),
+                        )
+                    },
+                );
+                return Err(SttError::Cancelled.to_string());
+            }
+            emit_recommended_models_progress(
+                &app,
+                RecommendedModelsProgressEvent {
+                    done: true,
+                    error: Some(error.clone()),
+                    ..progress(
+                        item_index,
+                        Some(&name),
+                        aggregate_percent(item_index, item_count, 0.0),
+                    )
+                },
+            );
+            return Err(error);
+        }
+    }
+
+    emit_recommended_models_progress(
+        &app,
+        RecommendedModelsProgressEvent {
+            done: true,
+            ..progress(item_count, None, 100.0)
+        },
+    );
+    Ok(items.iter().map(|item| item.name().to_string()).collect())
+}
+
+/// Stops [`download_recommended_models`]: the model downloading now is cancelled mid-transfer
+/// (its partial file is kept for a later resume) and the rest are skipped.
+#[tauri::command]
+pub fn cancel_recommended_downloads() {
+    RECOMMENDED_DOWNLOADS_CANCELLED.store(true, Ordering::Release);
+    platform::download::cancel_model_downloads();
+}
+
+/// Switches the active model to the highest-ranked model that is already downloaded, so
+/// a stale default never shadows a better model. Never triggers a download.
+pub async fn auto_select_best_downloaded_model() {
+    let adapter = match create_adapter() {
+        Ok(adapter) => adapter,
+        Err(err) => {
+            eprintln!("[models] auto-select skipped, adapter unavailable: {}", err);
+            return;
+        }
+    };
+
+    let mut best: Option<String> = None;
+    for name in adapter.available_models() {
+        let better = match best.as_deref() {
+            Some(current) => stt::model_quality_rank(&name) > stt::model_quality_rank(current),
+            None => true,
+        };
+        if better && adapter.is_model_available(&name).await {
+            best = Some(name);
+        }
+    }
+
+    let Some(best) = best else {
+        println!("[models] auto-select found no downloaded models, keeping default");
+        return;
+    };
+    if let Ok(mut guard) = active_model_store().lock() {
+        if *guard != best {
+            println!(
+                "[models] auto-selected best downloaded model: {} (was {})",
+                best, *guard
+            );
+            *guard = best;
+        }
+    }
+}
+
+/// Aborts a stuck MLX runtime/model setup; returns whether one was running.
+#[tauri::command]
+pub fn cancel_model_setup() -> bool {
+    stt::cancel_model_setup()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CachePaths {
+    whisper: PathBuf,
+    sherpa: PathBuf,
+    mlx: Option<PathBuf>,
+    mlx_venv: Option<PathBuf>,
+    llm: PathBuf,
+}
+
+/// Where every model family lives on disk, with `OPENWISPR_MODEL_DIR`,
+/// `OPENWISPR_LLM_MODEL_DIR` and the relocated cache root applied.
+#[tauri::command]
+pub fn get_cache_paths() -> Result<CachePaths, String> {
+    let stt_paths = stt::model_cache_paths().map_err(|e| e.to_string())?;
+    let llm = llm::models::get_model_cache_dir().map_err(|e| e.to_string())?;
+    Ok(CachePaths {
+        whisper: stt_paths.whisper,
+        sherpa: stt_paths.sherpa,
+        mlx: stt_paths.mlx,
+        mlx_venv: stt_paths.mlx_venv,
+        llm,
+    })
+}
+
+/// LLM models live in this subdirectory of a relocated cache root.
+const LLM_CACHE_SUBDIR: &str = "llm-models";
+
+/// Points the STT and LLM caches at a relocated root, or back at the defaults for `None`.
+pub fn apply_model_cache_dir(root: Option<&str>) {
+    let root = root.map(PathBuf::from);
+    llm::models::set_model_cache_dir_override(root.as_ref().map(|dir| dir.join(LLM_CACHE_SUBDIR)));
+    stt::set_model_dir_override(root);
+}
+
+/// Moves every downloaded model to `new_path` and remembers it as the cache root. Models are
+/// copied first, the new root is saved, and only then are the old copies deleted; one that
+/// cannot be deleted is left behind rather than failing the move. The MLX virtualenv is
+/// deleted once moved (it hardcodes its own path) and rebuilt on next use; one outside the
+/// cache (`OPENWISPR_MLX_VENV`) stays where it is.
+#[tauri::command]
+pub async fn move_cache_dir(app: tauri::AppHandle, new_path: String) -> Result<CachePaths, String> {
+    for var in ["OPENWISPR_MODEL_DIR", "OPENWISPR_LLM_MODEL_DIR"] {
+        if std::env::var(var).is_ok_and(|dir| !dir.trim().is_empty()) {
+            return Err(format!(
+                "{var} is set; unset it to move the model cache from the app"
+            ));
+        }
+    }
+    let new_root = PathBuf::from(new_path.trim());
+    if !new_root.is_absolute() {
+        return Err("Choose an absolute folder for the model cache".to_string());
+    }
+    let Ok(_queue) = download_queue().try_lock() else {
+        return Err("Wait for model downloads to finish before moving the cache".to_string());
+    };
+
+    let current = get_cache_paths()?;
+    let new_llm = new_root.join(LLM_CACHE_SUBDIR);
+    let (old_llm, old_stt) = (current.llm.clone(), current.whisper.clone());
+    let target = new_root.clone();
+    let (llm_moved, stt_moved) = tauri::async_runtime::spawn_blocking(move || {
+        let llm_moved = relocate(&old_llm, &new_llm)?;
+        match relocate(&old_stt, &target) {
+            Ok(stt_moved) => Ok((llm_moved, stt_moved)),
+            Err(err) => {
+                // Keep both caches under one root: put the LLM models back
+                llm_moved.undo();
+                Err(err)
+            }
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // Every model now exists under the new root, so record it before deleting anything
+    let mut store = crate::store::get_store();
+    store.settings.model_cache_dir = Some(new_root.to_string_lossy().into_owned());
+    crate::store::save_store(&app, &store);
+    apply_model_cache_dir(store.settings.model_cache_dir.as_deref());
+
+    let moved_venv = current
+        .mlx_venv
+        .as_ref()
+        .and_then(|venv| venv.strip_prefix(&current.whisper).ok())
+        .map(|rel| new_root.join(rel));
+    let left_behind = tauri::async_runtime::spawn_blocking(move || {
+        if let Some(venv) = moved_venv.filter(|venv| venv.exists()) {
+            if let Err(e) = std::fs::remove_dir_all(&venv) {
+                eprintln!("[models] failed to remove {}: {e}", venv.display());
+            }
+        }
+        let mut left_behind = stt_moved.remove_sources();
+        left_behind.extend(llm_moved.remove_sources());
+        left_behind
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    for path in left_behind {
+        eprintln!(
+            "[models] old copy left behind at {}; delete it to free the space",
+            path.display()
+        );
+    }
+    get_cache_paths()
+}
+
+/// Brings `from` over to `to`; the sources stay until [`Relocation::remove_sources`].
+fn relocate(from: &Path, to: &Path) -> Result<Relocation, String> {
+    if stt::verbose_logs_enabled() {
+        println!("[models] moving {} to {}", from.display(), to.display());
+    }
+    platform::relocate::copy_dir_contents(from, to)
+        .map_err(|e| format!("failed to move {} to {}: {e}", from.display(), to.display()))
+}
+
 #[tauri::command]
 pub fn get_active_model() -> Result<String, String> {
     active_model_store()
@@ -199,7 +615,10 @@ pub fn get_active_model() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn set_active_model(model: String) -> Result<(), String> {
+pub async fn set_active_model(
+    capture: tauri::State<'_, crate::audio::AudioCapture>,
+    model: String,
+) -> Result<(), String> {
     let adapter = create_adapter().map_err(|e| e.to_string())?;
     let downloaded = adapter.is_model_available(&model).await;
     if !downloaded {
@@ -215,9 +634,309 @@ pub async fn set_active_model(model: String) -> Result<(), String> {
         // Valid special runtimes with download support.
     }
 
-    let mut guard = active_model_store()
-        .lock()
-        .map_err(|_| "failed to set active model".to_string())?;
-    *guard = model;
+    {
+        let mut guard = active_model_store()
+            .lock()
+            .map_err(|_| "failed to set active model".to_string())?;
+        *guard = model.clone();
+    }
+
+    // Load and warm the model in the background so selection returns immediately.
+    let capture = capture.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        crate::audio::warm_up_model(&capture, &model).await;
+    });
     Ok(())
 }
+
+fn normalization_tuning(settings: &crate::store::NormalizationSettings) -> NormalizationTuning {
+    let defaults = NormalizationTuning::default();
+    NormalizationTuning {
+        enabled: settings.enabled,
+        activation_peak: settings.activation_peak.unwrap_or(defaults.activation_peak),
+        target_peak: settings.target_peak.unwrap_or(defaults.target_peak),
+        max_gain: settings.max_gain.unwrap_or(defaults.max_gain),
+    }
+}
+
+/// Config for loading `model` with the user's speech settings applied.
+pub(crate) fn stt_config(model: &str) -> SttConfig {
+    stt_config_for(model, &crate::store::get_settings())
+}
+
+fn stt_config_for(model: &str, settings: &crate::store::Settings) -> SttConfig {
+    let decode = settings.decode;
+    let defaults = DecodeTuning::default();
+    SttConfig {
+        model_name: model.to_string(),
+        task: if settings.translate_to_english {
+            TranscriptionTask::Translate
+        } else {
+            TranscriptionTask::Transcribe
+        },
+        min_gpu_memory_mb: settings.min_gpu_memory_mb,
+        trim_silence: settings.trim_silence,
+        normalization: normalization_tuning(&settings.normalization),
+        compute_backend: settings.compute_backend,
+        sherpa_provider: settings.sherpa_provider,
+        decode_tuning: DecodeTuning {
+            beam_size: decode.beam_size.unwrap_or(defaults.beam_size),
+            best_of: decode.best_of.unwrap_or(defaults.best_of),
+            temperature: decode.temperature.unwrap_or(defaults.temperature),
+            no_speech_thold: decode
+                .no_speech_threshold
+                .unwrap_or(defaults.no_speech_thold),
+            ..defaults
+        },
+        ..Default::default()
+    }
+}
+
+const VALIDATION_SAMPLE_RATE: u32 = 16_000;
+const VALIDATION_PROMPT: &str = "Repeat the following sentence exactly: The quick brown fox.";
+
+/// One second of a quiet 440 Hz tone: enough to exercise the full decode path.
+fn validation_tone() -> Vec<f32> {
+    (0..VALIDATION_SAMPLE_RATE)
+        .map(|i| {
+            let t = i as f32 / VALIDATION_SAMPLE_RATE as f32;
+            (t * 440.0 * std::f32::consts::TAU).sin() * 0.1
+        })
+        .collect()
+}
+
+fn is_llm_model_name(model: &str) -> bool {
+    llm::models::find_model(model).is_some()
+}
+
+/// Checks that a speech model loads and decodes audio. The transcript of the test tone is
+/// not checked, so this cannot tell a model that transcribes badly from a good one.
+async fn validate_stt_model(
+    capture: &crate::audio::AudioCapture,
+    model: &str,
+) -> Result<(), String> {
+    let probe = create_adapter().map_err(|e| e.to_string())?;
+    if !probe.is_model_available(model).await {
+        return Err("Model is not downloaded".to_string());
+    }
+    crate::audio::transcribe_with_model(
+        capture,
+        model,
+        &validation_tone(),
+        AudioFormat {
+            sample_rate: VALIDATION_SAMPLE_RATE,
+            channels: 1,
+            bits_per_sample: 16,
+        },
+    )
+    .await
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+async fn validate_llm_model(
+    capture: &crate::audio::AudioCapture,
+    model: &str,
+) -> Result<(), String> {
+    if !llm::is_model_downloaded(model) {
+        return Err("Model is not downloaded".to_string());
+    }
+    validate_llm_config(capture, llm::LlmConfig::for_model(model)).await
+}
+
+async fn validate_llm_config(
+    capture: &crate::audio::AudioCapture,
+    config: llm::LlmConfig,
+) -> Result<(), String> {
+    crate::audio::with_text_processor_unloaded(capture, async {
+        let mut adapter = llm::adapters::LlamaCppAdapter::new();
+        adapter.initialize(config).await.map_err(|e| e.to_string())?;
+        let output = adapter
+            .run_prompt(VALIDATION_PROMPT.to_string(), 32)
+            .await
+            .map_err(|e| e.to_string());
+        adapter.unload().await;
+        if output?.trim().is_empty() {
+            return Err("Model produced no output".to_string());
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Registers a model file the user already has (`ggml-*.bin` for whisper, `.gguf` for the
+/// formatter LLM). The file is staged in the cache and only listed once it loads; a file
+/// that fails leaves nothing behind. Returns the name it is listed under.
+#[tauri::command]
+pub async fn import_model(
+    capture: tauri::State<'_, crate::audio::AudioCapture>,
+    path: String,
+) -> Result<String, String> {
+    let source = PathBuf::from(path);
+    let is_gguf = source.extension().and_then(|ext| ext.to_str()) == Some("gguf");
+    let staged = tauri::async_runtime::spawn_blocking({
+        let source = source.clone();
+        move || {
+            if is_gguf {
+                llm::stage_import(&source).map_err(|e| e.to_string())
+            } else {
+                stt::stage_whisper_import(&source).map_err(|e| e.to_string())
+            }
+        }
+    })
+    .await
+    .map_err(|e| format!("import task failed: {e}"))??;
+
+    let name = staged.name.clone();
+    let staged_path = staged.path().to_path_buf();
+    let validation = if is_gguf {
+        let config = llm::LlmConfig {
+            model_path: Some(staged_path),
+            ..llm::LlmConfig::for_model(&name)
+        };
+        validate_llm_config(&capture, config).await
+    } else {
+        // A path loads the staged copy; unload it so nothing keeps the file open
+        let staged_model = staged_path.to_string_lossy().into_owned();
+        let validation = validate_stt_model(&capture, &staged_model).await;
+        crate::audio::unload_model(&capture, &staged_model).await;
+        validation
+    };
+    validation.map_err(|e| format!("{name} failed to load and was not imported: {e}"))?;
+
+    let name = tauri::async_runtime::spawn_blocking(move || staged.commit())
+        .await
+        .map_err(|e| format!("import task failed: {e}"))??;
+    if stt::verbose_logs_enabled() {
+        println!("[models] imported {} as {}", source.display(), name);
+    }
+    Ok(name)
+}
+
+#[tauri::command]
+pub async fn validate_model(
+    app: tauri::AppHandle,
+    capture: tauri::State<'_, crate::audio::AudioCapture>,
+    model: String,
+) -> Result<(), String> {
+    emit_model_validation_event(
+        &app,
+        "validation-progress",
+        ModelValidationEvent {
+            model: model.clone(),
+            stage: "running".to_string(),
+            done: false,
+            ok: false,
+            error: None,
+            message: Some("Loading model and running a test clip".to_string()),
+        },
+    );
+
+    let result = if is_llm_model_name(&model) {
+        validate_llm_model(&capture, &model).await
+    } else {
+        validate_stt_model(&capture, &model).await
+    };
+
+    emit_model_validation_event(
+        &app,
+        "validation-result",
+        ModelValidationEvent {
+            model,
+            stage: if result.is_ok() { "ready" } else { "error" }.to_string(),
+            done: true,
+            ok: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+            message: Some(
+                if result.is_ok() {
+                    "Model validated"
+                } else {
+                    "Model validation failed"
+                }
+                .to_string(),
+            ),
+        },
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        aggregate_percent, stt_config_for, to_user_facing_download_error, validation_tone,
+        VALIDATION_SAMPLE_RATE,
+    };
+    use crate::store::{NormalizationSettings, Settings};
+    use stt::{NormalizationTuning, SttError, TranscriptionTask};
+
+    #[test]
+    fn download_errors_pass_runtime_hints_through() {
+        let missing = SttError::RuntimeMissing {
+            component: "Python".to_string(),
+            hint: "Install Python 3.10+ from python.org.".to_string(),
+        };
+        assert_eq!(
+            to_user_facing_download_error(&missing),
+            "Install Python 3.10+ from python.org."
+        );
+        assert_eq!(
+            to_user_facing_download_error(&SttError::ModelNotFound("tiny".to_string())),
+            "Model not found: tiny"
+        );
+    }
+
+    #[test]
+    fn aggregate_percent_spans_all_items() {
+        assert_eq!(aggregate_percent(0, 2, 0.0), 0.0);
+        assert_eq!(aggregate_percent(0, 2, 50.0), 25.0);
+        assert_eq!(aggregate_percent(1, 2, 100.0), 100.0);
+        assert_eq!(aggregate_percent(0, 0, 0.0), 100.0);
+    }
+
+    #[test]
+    fn translate_setting_maps_to_translate_task() {
+        let settings = Settings {
+            translate_to_english: true,
+            ..Settings::default()
+        };
+        assert!(matches!(
+            stt_config_for("base", &settings).task,
+            TranscriptionTask::Translate
+        ));
+        assert!(matches!(
+            stt_config_for("base", &Settings::default()).task,
+            TranscriptionTask::Transcribe
+        ));
+    }
+
+    #[test]
+    fn normalization_settings_override_only_set_fields() {
+        assert_eq!(
+            stt_config_for("base", &Settings::default()).normalization,
+            NormalizationTuning::default()
+        );
+
+        let settings = Settings {
+            normalization: NormalizationSettings {
+                enabled: false,
+                max_gain: Some(4.0),
+                ..NormalizationSettings::default()
+            },
+            ..Settings::default()
+        };
+        let tuning = stt_config_for("base", &settings).normalization;
+        assert!(!tuning.enabled);
+        assert_eq!(tuning.max_gain, 4.0);
+        assert_eq!(
+            tuning.target_peak,
+            NormalizationTuning::default().target_peak
+        );
+    }
+
+    #[test]
+    fn validation_tone_is_one_quiet_second() {
+        let tone = validation_tone();
+        assert_eq!(tone.len(), VALIDATION_SAMPLE_RATE as usize);
+        assert!(tone.iter().all(|sample| sample.abs() <= 0.1));
+    }
+}
@@ -1,10 +1,18 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
 
+/// Analytics changes on every dictation; batch those writes instead of rewriting store.json
+/// each time. Settings changes still save immediately.
+const ANALYTICS_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Analytics {
     pub lifetime_removed_sec: f64, // "lifetime saved"
     pub sessions_count: u64,
@@ -12,6 +20,10 @@ pub struct Analytics {
     pub last_session_date: Option<String>, // YYYY-MM-DD
     pub total_words: u64,
     pub total_seconds: f64,
+    // Formatting impact, accumulated from per-session stats
+    pub total_fillers_removed: u64,
+    pub total_punctuation_added: u64,
+    pub total_chars_changed: u64,
 }
 
 impl Default for Analytics {
@@ -23,6 +35,9 @@ impl Default for Analytics {
             last_session_date: None,
             total_words: 0,
             total_seconds: 0.0,
+            total_fillers_removed: 0,
+            total_punctuation_added: 0,
+            total_chars_changed: 0,
         }
     }
 }
@@ -41,9 +56,124 @@ pub struct Settings {
     // Text Formatting Settings
     pub text_formatting_enabled: bool,
     pub text_formatting_mode: String, // "quick", "standard", "smart"
+    // Uppercase the first letter of verbatim (unformatted) transcripts
+    pub always_capitalize_first: bool,
+    // Terms whose spelling/casing formatting must keep as written (e.g. "OpenWispr")
+    pub personal_dictionary: Vec<String>,
+    // At startup, switch to the best model that is already downloaded
+    pub auto_select_best_downloaded: bool,
+    // Live partial transcriptions while recording (skipped for expensive runtimes like MLX)
+    pub partials_enabled: bool,
+    // Insert transcripts into the focused app, or only copy them for review
+    pub output_mode: OutputMode,
+    // What to put back on the clipboard after pasting a transcription
+    pub clipboard_restore: ClipboardRestore,
+    // Include whisper decode scores in transcription results for bug reports
+    pub show_scores: bool,
+    // Flatten output to a single line before inserting (for search boxes, chat inputs)
+    pub collapse_newlines: bool,
+    // Apps (by name) that always get single-line output, even with collapse_newlines off
+    pub single_line_apps: Vec<String>,
+    // Type transcripts shorter than this many characters instead of pasting (None = always paste)
+    pub type_below_chars: Option<usize>,
+    // Run the result/paste flow even when the final transcript is blank
+    pub paste_empty_transcripts: bool,
+    // Only show the "processing" pill once decoding takes this long (0 = show immediately)
+    pub min_processing_display_ms: u64,
+    // When the dictation pill is on screen
+    pub overlay_behavior: OverlayBehavior,
+    // Turn dictated "comma", "open paren", "new line" etc. into symbols before formatting
+    pub spoken_punctuation: bool,
+    // Free GPU memory (MB) needed to load whisper on Metal/Vulkan (None = sized from the model)
+    pub min_gpu_memory_mb: Option<u64>,
+    // Minutes without dictation before the speech model is unloaded (None = keep it loaded)
+    pub unload_model_after_mins: Option<u64>,
+    // Cut silent stretches out of the recording before decoding
+    pub trim_silence: bool,
+    // Ask whisper to translate speech into English instead of transcribing it as spoken
+    pub translate_to_english: bool,
+    // Force whisper onto CPU or a specific GPU backend (OPENWISPR_STT_BACKEND wins)
+    pub compute_backend: stt::ComputeBackend,
+    // onnxruntime provider for Parakeet (sherpa) models; GPU providers fall back to CPU
+    pub sherpa_provider: stt::SherpaProvider,
+    // Root for every model cache, set by `move_cache_dir` (None = platform default)
+    pub model_cache_dir: Option<String>,
+    // HuggingFace mirror host for model downloads, e.g. hf-mirror.com (None = huggingface.co)
+    pub hf_mirror: Option<String>,
+    // Whisper decoding overrides; unset fields keep the built-in defaults
+    pub decode: DecodeSettings,
+    // Quiet-capture boost overrides; unset fields keep the built-in defaults
+    pub normalization: NormalizationSettings,
     pub shortcuts: ShortcutSettings,
 }
 
+/// Whisper speed/accuracy knobs. `beam_size: 1` decodes greedily, which is much faster on
+/// slow CPUs.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct DecodeSettings {
+    pub beam_size: Option<u32>,
+    pub best_of: Option<u32>,
+    pub temperature: Option<f32>,
+    pub no_speech_threshold: Option<f32>,
+}
+
+/// Boost applied to quiet recordings. Turn it off (or lower `max_gain`) when a quiet but
+/// clean microphone comes out hissy.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct NormalizationSettings {
+    pub enabled: bool,
+    pub activation_peak: Option<f32>,
+    pub target_peak: Option<f32>,
+    pub max_gain: Option<f32>,
+}
+
+impl Default for NormalizationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            activation_peak: None,
+            target_peak: None,
+            max_gain: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Paste (or type) the transcript into the app that was focused when recording began.
+    #[default]
+    Insert,
+    /// Copy the transcript to the clipboard and show it; never synthesize a paste.
+    Review,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayBehavior {
+    /// Keep the pill on screen from launch, including between dictations.
+    AlwaysOn,
+    /// Show the pill when recording starts and hide it once the transcript is delivered.
+    #[default]
+    DuringDictation,
+    /// Never show the pill.
+    Hidden,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardRestore {
+    /// Snapshot and restore every clipboard type, including images and file lists.
+    #[default]
+    Always,
+    /// Only restore text/html clipboards; anything else is left holding the transcription.
+    TextOnly,
+    /// Never snapshot; the transcription stays on the clipboard after pasting.
+    Never,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct ShortcutSettings {
@@ -74,6 +204,30 @@ impl Default for Settings {
             system_llm_model: Some("SmolLM2-135M-Instruct-Q4_K_M".to_string()), // Default to smallest model
             text_formatting_enabled: false, // Disabled by default - STT models already clean up speech
             text_formatting_mode: "standard".to_string(), // Balanced mode
+            always_capitalize_first: false,
+            personal_dictionary: Vec::new(),
+            auto_select_best_downloaded: false,
+            partials_enabled: true,
+            output_mode: OutputMode::Insert,
+            clipboard_restore: ClipboardRestore::Always,
+            show_scores: false,
+            collapse_newlines: false,
+            single_line_apps: Vec::new(),
+            type_below_chars: None,
+            paste_empty_transcripts: false,
+            min_processing_display_ms: 0,
+            overlay_behavior: OverlayBehavior::DuringDictation,
+            spoken_punctuation: false,
+            min_gpu_memory_mb: None,
+            unload_model_after_mins: Some(30),
+            trim_silence: false,
+            translate_to_english: false,
+            compute_backend: stt::ComputeBackend::Auto,
+            sherpa_provider: stt::SherpaProvider::Cpu,
+            model_cache_dir: None,
+            hf_mirror: None,
+            decode: DecodeSettings::default(),
+            normalization: NormalizationSettings::default(),
             shortcuts: ShortcutSettings::default(),
         }
     }
@@ -172,23 +326,64 @@ fn store_path(app: &AppHandle) -> Option<PathBuf> {
 }
 
 static STORE: OnceLock<Mutex<AppStore>> = OnceLock::new();
+/// In-memory changes that have not reached disk yet.
+static FLUSH_PENDING: AtomicBool = AtomicBool::new(false);
 
 pub fn init_store(app: &AppHandle) {
-    let path = store_path(app);
-    let store = if let Some(path) = &path {
-        if path.exists() {
-            fs::read_to_string(path)
-                .ok()
-                .and_then(|content| serde_json::from_str(&content).ok())
-                .unwrap_or_default()
-        } else {
-            AppStore::default()
+    let store = store_path(app)
+        .map(|path| load_store_file(&path))
+        .unwrap_or_default();
+
+    let _ = STORE.set(Mutex::new(store));
+}
+
+fn load_store_file(path: &Path) -> AppStore {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// `store.json.tmp`, staged next to the store so the final rename stays on one filesystem.
+fn temp_store_path(path: &Path) -> PathBuf {
+    path.with_extension("json.tmp")
+}
+
+/// Writes `contents` to a temp file, fsyncs it and renames it over `path`, so a crash
+/// mid-write leaves the previous store.json intact rather than a truncated one.
+fn write_store_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp_path = temp_store_path(path);
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&temp_path, path)
+}
+
+fn write_store(app: &AppHandle, store: &AppStore) {
+    let Some(path) = store_path(app) else {
+        return;
+    };
+    let contents = match serde_json::to_string_pretty(store) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("[store] failed to serialize store: {}", err);
+            return;
         }
-    } else {
-        AppStore::default()
     };
+    if let Err(err) = write_store_file(&path, &contents) {
+        eprintln!("[store] failed to write {}: {}", path.display(), err);
+    }
+}
 
-    let _ = STORE.set(Mutex::new(store));
+/// Writes any debounced changes now; call before quitting.
+pub fn flush_store(app: &AppHandle) {
+    if FLUSH_PENDING.swap(false, Ordering::AcqRel) {
+        write_store(app, &get_store());
+    }
 }
 
 pub fn get_store() -> AppStore {
@@ -199,17 +394,26 @@ pub fn get_store() -> AppStore {
 }
 
 pub fn save_store(app: &AppHandle, store: &AppStore) {
-    if let Some(path) = store_path(app) {
-        if let Some(parent) = path.parent() {
-            let _ = fs::create_dir_all(parent);
-        }
-        let _ = fs::write(
-            path,
-            serde_json::to_string_pretty(store).unwrap_or_default(),
-        );
+    // This write includes any debounced changes, so nothing is pending afterwards
+    FLUSH_PENDING.store(false, Ordering::Release);
+    write_store(app, store);
+    update_memory(store);
+}
+
+/// Updates memory now and writes to disk within [`ANALYTICS_FLUSH_INTERVAL`].
+fn save_store_debounced(app: &AppHandle, store: &AppStore) {
+    update_memory(store);
+    if FLUSH_PENDING.swap(true, Ordering::AcqRel) {
+        return;
     }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(ANALYTICS_FLUSH_INTERVAL).await;
+        flush_store(&app);
+    });
+}
 
-    // Update memory
+fn update_memory(store: &AppStore) {
     if let Some(guard) = STORE.get() {
         if let Ok(mut lock) = guard.lock() {
             *lock = store.clone();
@@ -252,7 +456,17 @@ pub fn update_analytics(app: &AppHandle, duration_sec: f64, word_count: u64) {
     }
     store.analytics.last_session_date = Some(today);
 
-    save_store(app, &store);
+    save_store_debounced(app, &store);
+    let _ = app.emit_all("analytics-update", &store.analytics);
+}
+
+pub fn update_formatting_analytics(app: &AppHandle, stats: &text_processor::FormattingStats) {
+    let mut store = get_store();
+    store.analytics.total_fillers_removed += stats.fillers_removed;
+    store.analytics.total_punctuation_added += stats.punctuation_added;
+    store.analytics.total_chars_changed += stats.chars_changed;
+
+    save_store_debounced(app, &store);
     let _ = app.emit_all("analytics-update", &store.analytics);
 }
 
@@ -342,6 +556,186 @@ pub fn set_formatting_settings(
     Ok(())
 }
 
+pub fn set_formatting_mode(app: &AppHandle, mode: String) {
+    let mut store = get_store();
+    store.settings.text_formatting_mode = mode;
+    save_store(app, &store);
+}
+
+#[tauri::command]
+pub fn set_auto_select_best_downloaded(app: AppHandle, enabled: bool) {
+    let mut store = get_store();
+    store.settings.auto_select_best_downloaded = enabled;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_partials_enabled(app: AppHandle, enabled: bool) {
+    let mut store = get_store();
+    store.settings.partials_enabled = enabled;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_always_capitalize_first(app: AppHandle, enabled: bool) {
+    let mut store = get_store();
+    store.settings.always_capitalize_first = enabled;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_personal_dictionary(app: AppHandle, terms: Vec<String>) {
+    let mut store = get_store();
+    store.settings.personal_dictionary = terms
+        .into_iter()
+        .map(|term| term.trim().to_string())
+        .filter(|term| !term.is_empty())
+        .collect();
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_show_scores(app: AppHandle, enabled: bool) {
+    let mut store = get_store();
+    store.settings.show_scores = enabled;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_single_line_output(app: AppHandle, collapse_newlines: bool, apps: Vec<String>) {
+    let mut store = get_store();
+    store.settings.collapse_newlines = collapse_newlines;
+    store.settings.single_line_apps = apps;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_paste_empty_transcripts(app: AppHandle, enabled: bool) {
+    let mut store = get_store();
+    store.settings.paste_empty_transcripts = enabled;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_min_processing_display_ms(app: AppHandle, ms: u64) {
+    let mut store = get_store();
+    store.settings.min_processing_display_ms = ms;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_overlay_behavior(app: AppHandle, behavior: OverlayBehavior) {
+    let mut store = get_store();
+    store.settings.overlay_behavior = behavior;
+    save_store(&app, &store);
+    match behavior {
+        OverlayBehavior::AlwaysOn => crate::show_main_overlay_window(&app),
+        OverlayBehavior::Hidden => crate::hide_main_overlay_window(&app),
+        OverlayBehavior::DuringDictation => {}
+    }
+}
+
+#[tauri::command]
+pub fn set_spoken_punctuation(app: AppHandle, enabled: bool) {
+    let mut store = get_store();
+    store.settings.spoken_punctuation = enabled;
+    save_store(&app, &store);
+}
+
+/// Takes effect the next time a whisper model is loaded.
+#[tauri::command]
+pub fn set_min_gpu_memory_mb(app: AppHandle, mb: Option<u64>) {
+    let mut store = get_store();
+    store.settings.min_gpu_memory_mb = mb;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_unload_model_after_mins(app: AppHandle, minutes: Option<u64>) {
+    let mut store = get_store();
+    store.settings.unload_model_after_mins = minutes.filter(|minutes| *minutes > 0);
+    save_store(&app, &store);
+}
+
+/// Takes effect the next time the speech model is loaded. Only multilingual whisper models
+/// can translate; Parakeet models refuse to load while this is on.
+#[tauri::command]
+pub fn set_translate(app: AppHandle, enabled: bool) {
+    let mut store = get_store();
+    store.settings.translate_to_english = enabled;
+    save_store(&app, &store);
+}
+
+/// Takes effect the next time the speech model is loaded.
+#[tauri::command]
+pub fn set_compute_backend(app: AppHandle, backend: stt::ComputeBackend) {
+    let mut store = get_store();
+    store.settings.compute_backend = backend;
+    save_store(&app, &store);
+}
+
+/// Takes effect the next time a Parakeet model is loaded.
+#[tauri::command]
+pub fn set_sherpa_provider(app: AppHandle, provider: stt::SherpaProvider) {
+    let mut store = get_store();
+    store.settings.sherpa_provider = provider;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_hf_mirror(app: AppHandle, mirror: Option<String>) {
+    let mirror = mirror.filter(|mirror| !mirror.trim().is_empty());
+    platform::mirror::set_huggingface_mirror(mirror.clone());
+    let mut store = get_store();
+    store.settings.hf_mirror = mirror;
+    save_store(&app, &store);
+}
+
+/// Takes effect the next time the speech model is loaded.
+#[tauri::command]
+pub fn set_decode_settings(app: AppHandle, decode: DecodeSettings) {
+    let mut store = get_store();
+    store.settings.decode = decode;
+    save_store(&app, &store);
+}
+
+/// Takes effect the next time the speech model is loaded.
+#[tauri::command]
+pub fn set_normalization_settings(app: AppHandle, normalization: NormalizationSettings) {
+    let mut store = get_store();
+    store.settings.normalization = normalization;
+    save_store(&app, &store);
+}
+
+/// Takes effect the next time the speech model is loaded.
+#[tauri::command]
+pub fn set_trim_silence(app: AppHandle, enabled: bool) {
+    let mut store = get_store();
+    store.settings.trim_silence = enabled;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_type_below_chars(app: AppHandle, chars: Option<usize>) {
+    let mut store = get_store();
+    store.settings.type_below_chars = chars;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_output_mode(app: AppHandle, mode: OutputMode) {
+    let mut store = get_store();
+    store.settings.output_mode = mode;
+    save_store(&app, &store);
+}
+
+#[tauri::command]
+pub fn set_clipboard_restore(app: AppHandle, mode: ClipboardRestore) {
+    let mut store = get_store();
+    store.settings.clipboard_restore = mode;
+    save_store(&app, &store);
+}
+
 #[tauri::command]
 pub fn get_settings() -> Settings {
     get_store().settings
@@ -375,4 +769,34 @@ mod tests {
     fn parse_shortcut_rejects_multiple_non_modifier_keys() {
         assert!(parse_shortcut("ctrl+k+m").is_err());
     }
+
+    #[test]
+    fn interrupted_write_keeps_previous_store() {
+        let dir = std::env::temp_dir().join(format!("openwispr-store-{}", std::process::id()));
+        let path = dir.join("store.json");
+        let mut store = AppStore::default();
+        store.analytics.sessions_count = 7;
+        write_store_file(&path, &serde_json::to_string_pretty(&store).unwrap()).unwrap();
+
+        // A crash mid-save leaves a truncated temp file and never reaches the rename
+        fs::write(temp_store_path(&path), r#"{"analytics": {"sessions_co"#).unwrap();
+        assert_eq!(load_store_file(&path).analytics.sessions_count, 7);
+
+        // The next save replaces the stale temp file
+        store.analytics.sessions_count = 8;
+        write_store_file(&path, &serde_json::to_string_pretty(&store).unwrap()).unwrap();
+        assert_eq!(load_store_file(&path).analytics.sessions_count, 8);
+        assert!(!temp_store_path(&path).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn clipboard_restore_defaults_to_always_for_old_settings() {
+        let settings: Settings = serde_json::from_str(r#"{"partials_enabled":false}"#).unwrap();
+        assert_eq!(settings.clipboard_restore, ClipboardRestore::Always);
+
+        let settings: Settings =
+            serde_json::from_str(r#"{"clipboard_restore":"text_only"}"#).unwrap();
+        assert_eq!(settings.clipboard_restore, ClipboardRestore::TextOnly);
+    }
 }
@@ -1,19 +1,123 @@
 use crate::{
-    prompts, FormattingType, LlmAdapter, LlmConfig, LlmError, Result, TextFormattingRequest,
-    TextFormattingResponse,
+    prompts, FormattingType, LlmAdapter, LlmConfig, LlmError, PromptOutput, Result,
+    TextFormattingRequest, TextFormattingResponse, TokenCallback,
 };
 use async_trait::async_trait;
 use llama_cpp_2::context::params::LlamaContextParams;
+use llama_cpp_2::context::LlamaContext;
 use llama_cpp_2::llama_backend::LlamaBackend;
 use llama_cpp_2::llama_batch::LlamaBatch;
 use llama_cpp_2::model::params::LlamaModelParams;
 use llama_cpp_2::model::LlamaModel;
 use llama_cpp_2::model::AddBos;
+use llama_cpp_2::TokenToStringError;
+use llama_cpp_2::sampling::LlamaSampler;
+use llama_cpp_2::token::data::LlamaTokenData;
+use llama_cpp_2::token::data_array::LlamaTokenDataArray;
+use llama_cpp_2::token::LlamaToken;
+use std::collections::{HashSet, VecDeque};
 use std::num::NonZeroU32;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Instant;
+use sysinfo::System;
+
+/// Weights plus KV cache and scratch buffers need headroom beyond the GGUF size.
+const MEMORY_OVERHEAD_FACTOR: f64 = 1.25;
+
+/// llama.cpp's `LLAMA_DEFAULT_SEED`: the dist sampler draws a random seed instead.
+const RANDOM_SEED: u32 = u32::MAX;
+
+/// Context window shared by prompt and output tokens.
+const CONTEXT_TOKENS: u32 = 2048;
+/// Tokens decoded per batch, which also caps the prompt length.
+const BATCH_TOKENS: usize = 512;
+
+/// The llama.cpp backend. It can only be initialized once per process, so every adapter
+/// (and model validation) shares this one instead of owning its own.
+fn shared_backend() -> Result<&'static LlamaBackend> {
+    static BACKEND: OnceLock<std::result::Result<LlamaBackend, String>> = OnceLock::new();
+    BACKEND
+        .get_or_init(|| LlamaBackend::init().map_err(|e| e.to_string()))
+        .as_ref()
+        .map_err(|e| LlmError::ModelLoadError(format!("Failed to initialize llama backend: {}", e)))
+}
+
+/// A slot holding one lazily created value that is reused across calls. The lock hands it to
+/// one caller at a time.
+struct Reusable<T> {
+    slot: Mutex<Option<T>>,
+}
+
+impl<T> Reusable<T> {
+    fn new() -> Self {
+        Self {
+            slot: Mutex::new(None),
+        }
+    }
+
+    /// Locks the slot, filling it with `create` first if it is empty.
+    fn acquire(&self, create: impl FnOnce() -> Result<T>) -> Result<MutexGuard<'_, Option<T>>> {
+        let mut slot = self
+            .slot
+            .lock()
+            .map_err(|_| LlmError::InferenceFailed("LLM context lock poisoned".to_string()))?;
+        if slot.is_none() {
+            *slot = Some(create()?);
+        }
+        Ok(slot)
+    }
+
+    /// Drops the value so the next [`Reusable::acquire`] creates a fresh one.
+    fn clear(&self) {
+        if let Ok(mut slot) = self.slot.lock() {
+            *slot = None;
+        }
+    }
+}
+
+self_cell::self_cell!(
+    /// A llama context together with the model it borrows, so the model cannot be freed
+    /// while the context still points into it.
+    struct ModelContext {
+        owner: Arc<LlamaModel>,
+        #[not_covariant]
+        dependent: LlamaContext,
+    }
+);
+
+/// A context and batch kept between prompts so each one skips their allocation.
+struct ReusableContext {
+    context: ModelContext,
+    batch: LlamaBatch,
+}
+
+// SAFETY: llama.cpp contexts and batches are not tied to the thread that created them, only
+// to one thread at a time, and the adapter only hands this out behind its lock.
+unsafe impl Send for ReusableContext {}
+
+impl ReusableContext {
+    fn new(model: &Arc<LlamaModel>, backend: &LlamaBackend) -> Result<Self> {
+        let ctx_params = LlamaContextParams::default()
+            .with_n_ctx(NonZeroU32::new(CONTEXT_TOKENS))
+            .with_n_batch(BATCH_TOKENS as u32);
+        let context = ModelContext::try_new(Arc::clone(model), |model| {
+            model.new_context(backend, ctx_params)
+        })
+        .map_err(|e| LlmError::ModelLoadError(format!("Failed to create context: {}", e)))?;
+        tracing::debug!("Created llama context ({} tokens)", CONTEXT_TOKENS);
+        Ok(Self {
+            context,
+            batch: LlamaBatch::new(BATCH_TOKENS, 1),
+        })
+    }
+}
 
 pub struct LlamaCppAdapter {
-    backend: Option<LlamaBackend>,
+    context: Reusable<ReusableContext>,
+    /// How many contexts have been created, i.e. how often reuse was not possible.
+    contexts_created: AtomicUsize,
     model: Option<Arc<LlamaModel>>,
     config: Option<LlmConfig>,
     current_model_name: Option<String>,
@@ -22,7 +126,8 @@ pub struct LlamaCppAdapter {
 impl LlamaCppAdapter {
     pub fn new() -> Self {
         Self {
-            backend: None,
+            context: Reusable::new(),
+            contexts_created: AtomicUsize::new(0),
             model: None,
             config: None,
             current_model_name: None,
@@ -30,15 +135,36 @@ impl LlamaCppAdapter {
     }
 
     fn ensure_initialized(&self) -> Result<()> {
-        if self.model.is_none() {
-            return Err(LlmError::ModelLoadError(
+        self.loaded_model().map(|_| ())
+    }
+
+    fn loaded_model(&self) -> Result<&LlamaModel> {
+        self.model.as_deref().ok_or_else(|| {
+            LlmError::ModelLoadError(
                 "Adapter not initialized. Call initialize() first.".to_string(),
-            ));
-        }
-        Ok(())
+            )
+        })
+    }
+
+    /// The model's tokens for `text`, without the BOS token prompts start with.
+    fn tokenize_text(&self, text: &str) -> Result<Vec<LlamaToken>> {
+        self.loaded_model()?
+            .str_to_token(text, AddBos::Never)
+            .map_err(|e| LlmError::InferenceFailed(format!("Tokenization failed: {}", e)))
     }
 
-    fn generate_response(&self, prompt: &str, max_tokens: u32) -> Result<String> {
+    /// Decodes up to `max_tokens` with the config's sampling (greedy at temperature 0). Past
+    /// `deadline` the loop stops between tokens and returns the partial output with
+    /// `timed_out` set. Generation also halts at the first of the config's stop sequences,
+    /// which is cut from the result. `on_piece` sees the output as it grows, minus any text
+    /// that could still turn into a stop sequence.
+    fn generate_response(
+        &self,
+        prompt: &str,
+        max_tokens: u32,
+        deadline: Option<Instant>,
+        on_piece: Option<&dyn Fn(&str)>,
+    ) -> Result<PromptOutput> {
         self.ensure_initialized()?;
 
         let model = self
@@ -46,33 +172,45 @@ impl LlamaCppAdapter {
             .as_ref()
             .ok_or_else(|| LlmError::ModelLoadError("Model not loaded".to_string()))?;
 
-        let backend = self
-            .backend
-            .as_ref()
-            .ok_or_else(|| LlmError::ModelLoadError("Backend not initialized".to_string()))?;
+        let backend = shared_backend()?;
+
+        // Back-to-back prompts share one context; concurrent ones wait for the lock
+        let mut slot = self.context.acquire(|| {
+            self.contexts_created.fetch_add(1, Ordering::Relaxed);
+            ReusableContext::new(model, backend)
+        })?;
+        let Some(ReusableContext { context, batch }) = slot.as_mut() else {
+            return Err(LlmError::ModelLoadError("Context not created".to_string()));
+        };
+        context.with_dependent_mut(|_, ctx| {
+            self.decode_prompt(ctx, batch, prompt, max_tokens, deadline, on_piece)
+        })
+    }
 
+    /// The body of [`Self::generate_response`], run on the reused context and batch.
+    fn decode_prompt(
+        &self,
+        ctx: &mut LlamaContext<'_>,
+        batch: &mut LlamaBatch,
+        prompt: &str,
+        max_tokens: u32,
+        deadline: Option<Instant>,
+        on_piece: Option<&dyn Fn(&str)>,
+    ) -> Result<PromptOutput> {
+        let model = self.loaded_model()?;
         let config = self
             .config
             .as_ref()
             .ok_or_else(|| LlmError::ConfigError("Config not set".to_string()))?;
 
-        // Create context params
-        let ctx_params = LlamaContextParams::default()
-            .with_n_ctx(NonZeroU32::new(2048))
-            .with_n_batch(512);
-
-        let mut ctx = model
-            .new_context(backend, ctx_params)
-            .map_err(|e| LlmError::ModelLoadError(format!("Failed to create context: {}", e)))?;
+        ctx.clear_kv_cache();
+        batch.clear();
 
         // Tokenize the prompt
         let tokens = model
             .str_to_token(prompt, AddBos::Always)
             .map_err(|e| LlmError::InferenceFailed(format!("Tokenization failed: {}", e)))?;
 
-        // Prepare batch
-        let mut batch = LlamaBatch::new(512, 1);
-
         for (i, token) in tokens.iter().enumerate() {
             let is_last = i == tokens.len() - 1;
             batch
@@ -81,39 +219,67 @@ impl LlamaCppAdapter {
         }
 
         // Decode the prompt
-        ctx.decode(&mut batch).map_err(|e| {
+        ctx.decode(batch).map_err(|e| {
             LlmError::InferenceFailed(format!("Failed to decode prompt: {}", e))
         })?;
 
         let mut n_cur = batch.n_tokens();
         let mut output = String::new();
+        let mut streamed = 0;
         let mut generated_tokens = 0;
+        let mut timed_out = false;
+        let mut stopped = false;
 
-        let last_index = (tokens.len() - 1) as i32;
+        let mut sampler = sampler_for(config, model);
+        let mut recent = RecentTokens::new(config.repeat_last_n);
+
+        // One decoder for the whole generation, so `output` only ever receives complete
+        // UTF-8 sequences
+        let mut pieces = PieceDecoder::new();
 
         // Generate tokens
         while generated_tokens < max_tokens {
-            // Sample next token using simple greedy sampling
-            let candidates: Vec<_> = ctx.candidates_ith(last_index).collect();
-            
-            // Get the most likely token (first in candidates list is best)
-            let token_id = if let Some(first) = candidates.first() {
-                first.id()
-            } else {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                timed_out = true;
+                break;
+            }
+
+            // Logits of the last token in the batch: the prompt's last token, then each new one
+            let mut candidates =
+                LlamaTokenDataArray::from_iter(ctx.candidates_ith(batch.n_tokens() - 1), false);
+            penalize_recent_tokens(&mut candidates.data, &recent, config.repeat_penalty);
+            candidates.apply_sampler(&mut sampler);
+            let Some(token_id) = candidates.selected_token() else {
                 break;
             };
+            recent.push(token_id);
 
             // Check for EOS
             if model.is_eog_token(token_id) {
                 break;
             }
+            // Advances the grammar past the chosen token
+            sampler.accept(token_id);
 
-            // Decode token to string using updated API with encoder
-            let mut decoder = encoding_rs::UTF_8.new_decoder();
-            let piece = model.token_to_piece(token_id, &mut decoder, false, None)
-                .map_err(|e| LlmError::InferenceFailed(format!("Token decode failed: {}", e)))?;
-
-            output.push_str(&piece);
+            output.push_str(&pieces.push(&token_bytes(model, token_id)?));
+            if let Some(stop) = find_stop(&output, &config.stop) {
+                output.truncate(stop);
+                stopped = true;
+            }
+            if let Some(on_piece) = on_piece {
+                let ready = if stopped {
+                    output.len()
+                } else {
+                    output.len() - pending_stop_len(&output, &config.stop)
+                };
+                if ready > streamed {
+                    on_piece(&output[streamed..ready]);
+                    streamed = ready;
+                }
+            }
+            if stopped {
+                break;
+            }
 
             // Prepare next batch
             batch.clear();
@@ -122,7 +288,7 @@ impl LlamaCppAdapter {
                 .map_err(|e| LlmError::InferenceFailed(format!("Batch add failed: {}", e)))?;
 
             // Decode next token
-            ctx.decode(&mut batch).map_err(|e| {
+            ctx.decode(batch).map_err(|e| {
                 LlmError::InferenceFailed(format!("Failed to decode token: {}", e))
             })?;
 
@@ -130,7 +296,24 @@ impl LlamaCppAdapter {
             generated_tokens += 1;
         }
 
-        Ok(output.trim().to_string())
+        // Generation can stop mid-character; release whatever the decoder still holds
+        if !stopped {
+            output.push_str(&pieces.finish());
+        }
+        if let Some(on_piece) = on_piece.filter(|_| output.len() > streamed) {
+            on_piece(&output[streamed..]);
+        }
+
+        if timed_out {
+            tracing::warn!(
+                "Generation hit its deadline after {} tokens, returning partial output",
+                generated_tokens
+            );
+        }
+        Ok(PromptOutput {
+            text: output.trim().to_string(),
+            timed_out,
+        })
     }
 }
 
@@ -153,22 +336,29 @@ impl LlmAdapter for LlamaCppAdapter {
             )));
         }
 
-        // Initialize backend
-        let backend = LlamaBackend::init().map_err(|e| {
-            LlmError::ModelLoadError(format!("Failed to initialize llama backend: {}", e))
-        })?;
+        if config.allow_oversized_model || memory_check_disabled_by_env() {
+            tracing::info!("Skipping memory check for model: {}", config.model_name);
+        } else {
+            let required_bytes = estimated_model_bytes(&model_path, &config.model_name);
+            let mut system = System::new();
+            system.refresh_memory();
+            check_memory_budget(required_bytes, system.available_memory())?;
+        }
+
+        let backend = shared_backend()?;
 
         // Configure model params (Metal for macOS, CUDA/Vulkan for Windows auto-detected)
         let model_params = LlamaModelParams::default();
 
         // Load model
-        let model = LlamaModel::load_from_file(&backend, model_path, &model_params).map_err(
+        let model = LlamaModel::load_from_file(backend, model_path, &model_params).map_err(
             |e| LlmError::ModelLoadError(format!("Failed to load model: {}", e)),
         )?;
 
         tracing::info!("Model loaded successfully: {}", config.model_name);
 
-        self.backend = Some(backend);
+        // The old context belongs to the model being replaced
+        self.context.clear();
         self.model = Some(Arc::new(model));
         self.config = Some(config.clone());
         self.current_model_name = Some(config.model_name.clone());
@@ -176,6 +366,16 @@ impl LlmAdapter for LlamaCppAdapter {
         Ok(())
     }
 
+    async fn unload(&mut self) {
+        if let Some(model_name) = self.current_model_name.take() {
+            tracing::info!("Unloading LlamaCpp model: {}", model_name);
+        }
+        // Context before model, the reverse of how they were created
+        self.context.clear();
+        self.model = None;
+        self.config = None;
+    }
+
     async fn format_text(&self, request: TextFormattingRequest) -> Result<TextFormattingResponse> {
         self.ensure_initialized()?;
 
@@ -194,7 +394,9 @@ impl LlmAdapter for LlamaCppAdapter {
             .ok_or_else(|| LlmError::ConfigError("Config not set".to_string()))?;
 
         // Generate formatted text
-        let formatted_text = self.generate_response(&prompt, config.max_tokens)?;
+        let formatted_text = self
+            .generate_response(&prompt, config.max_tokens, None, None)?
+            .text;
 
         Ok(TextFormattingResponse {
             formatted_text,
@@ -204,7 +406,54 @@ impl LlmAdapter for LlamaCppAdapter {
 
     async fn run_prompt(&self, prompt: String, max_tokens: u32) -> Result<String> {
         self.ensure_initialized()?;
-        self.generate_response(&prompt, max_tokens)
+        Ok(self
+            .generate_response(&prompt, max_tokens, None, None)?
+            .text)
+    }
+
+    async fn run_prompt_with_deadline(
+        &self,
+        prompt: String,
+        max_tokens: u32,
+        deadline: Instant,
+    ) -> Result<PromptOutput> {
+        self.ensure_initialized()?;
+        self.generate_response(&prompt, max_tokens, Some(deadline), None)
+    }
+
+    async fn run_prompt_stream(
+        &self,
+        prompt: String,
+        max_tokens: u32,
+        on_token: TokenCallback,
+    ) -> Result<String> {
+        self.ensure_initialized()?;
+        Ok(self
+            .generate_response(&prompt, max_tokens, None, Some(&*on_token))?
+            .text)
+    }
+
+    fn count_tokens(&self, text: &str) -> Result<usize> {
+        Ok(self.tokenize_text(text)?.len())
+    }
+
+    fn tokenize(&self, text: &str) -> Result<Vec<i32>> {
+        Ok(self
+            .tokenize_text(text)?
+            .into_iter()
+            .map(|token| token.0)
+            .collect())
+    }
+
+    fn detokenize(&self, tokens: &[i32]) -> Result<String> {
+        let model = self.loaded_model()?;
+        let mut pieces = PieceDecoder::new();
+        let mut text = String::new();
+        for &token in tokens {
+            text.push_str(&pieces.push(&token_bytes(model, LlamaToken::new(token))?));
+        }
+        text.push_str(&pieces.finish());
+        Ok(text)
     }
 
     async fn is_model_available(&self, model_name: &str) -> bool {
@@ -215,3 +464,406 @@ impl LlmAdapter for LlamaCppAdapter {
         self.current_model_name.clone()
     }
 }
+
+/// Greedy at temperature 0; otherwise top-k, then top-p, then temperature before drawing
+/// from the remaining distribution with the configured seed. A configured grammar runs
+/// first, so only tokens that keep the output valid are ever considered.
+fn sampler_for(config: &LlmConfig, model: &LlamaModel) -> LlamaSampler {
+    let grammar = config
+        .grammar
+        .as_deref()
+        .map(|grammar| LlamaSampler::grammar(model, grammar, "root"));
+    let selection = if config.temperature <= 0.0 {
+        vec![LlamaSampler::greedy()]
+    } else {
+        vec![
+            LlamaSampler::top_k(config.top_k.min(i32::MAX as u32) as i32),
+            LlamaSampler::top_p(config.top_p, 1),
+            LlamaSampler::temp(config.temperature),
+            LlamaSampler::dist(config.seed.unwrap_or(RANDOM_SEED)),
+        ]
+    };
+    LlamaSampler::chain_simple(grammar.into_iter().chain(selection))
+}
+
+/// The last few generated tokens, the window the repetition penalty looks at.
+struct RecentTokens {
+    ids: VecDeque<LlamaToken>,
+    capacity: usize,
+}
+
+impl RecentTokens {
+    fn new(capacity: usize) -> Self {
+        Self {
+            ids: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, id: LlamaToken) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.ids.len() == self.capacity {
+            self.ids.pop_front();
+        }
+        self.ids.push_back(id);
+    }
+}
+
+/// CTRL-style repetition penalty: a recently generated token's logit is divided by `penalty`
+/// when positive and multiplied when negative, so small models stop looping on their last
+/// phrase. A penalty of 1 or less disables it. Returns how many candidates were penalized.
+fn penalize_recent_tokens(
+    candidates: &mut [LlamaTokenData],
+    recent: &RecentTokens,
+    penalty: f32,
+) -> usize {
+    if penalty <= 1.0 || recent.ids.is_empty() {
+        return 0;
+    }
+    let recent: HashSet<LlamaToken> = recent.ids.iter().copied().collect();
+    let mut penalized = 0;
+    for candidate in candidates
+        .iter_mut()
+        .filter(|candidate| recent.contains(&candidate.id()))
+    {
+        let logit = candidate.logit();
+        candidate.set_logit(if logit > 0.0 {
+            logit / penalty
+        } else {
+            logit * penalty
+        });
+        penalized += 1;
+    }
+    penalized
+}
+
+/// Byte offset of the earliest stop sequence in `text`. Only matches after the first visible
+/// character count, so a model that opens with a blank line is not cut down to nothing.
+fn find_stop(text: &str, stops: &[String]) -> Option<usize> {
+    let start = text.len() - text.trim_start().len();
+    stops
+        .iter()
+        .filter(|stop| !stop.is_empty())
+        .filter_map(|stop| text[start..].find(stop.as_str()))
+        .min()
+        .map(|offset| start + offset)
+}
+
+/// Length of the longest end of `text` that is the start of a stop sequence, i.e. output
+/// that must not be streamed yet because the next token may complete the stop.
+fn pending_stop_len(text: &str, stops: &[String]) -> usize {
+    stops
+        .iter()
+        .filter_map(|stop| {
+            (1..stop.len())
+                .rev()
+                .filter(|&len| stop.is_char_boundary(len))
+                .find(|&len| text.ends_with(&stop[..len]))
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// The raw bytes of `token`'s text, which may end partway through a UTF-8 character.
+fn token_bytes(model: &LlamaModel, token: LlamaToken) -> Result<Vec<u8>> {
+    let bytes = match model.token_to_piece_bytes(token, 8, false, None) {
+        Err(TokenToStringError::InsufficientBufferSpace(needed)) => {
+            model.token_to_piece_bytes(token, needed.unsigned_abs() as usize, false, None)
+        }
+        bytes => bytes,
+    };
+    bytes.map_err(|e| LlmError::InferenceFailed(format!("Token decode failed: {}", e)))
+}
+
+/// Turns the bytes of consecutive tokens into text. A multibyte character split across two
+/// tokens stays buffered until its final byte arrives, so only complete characters come out.
+struct PieceDecoder {
+    decoder: encoding_rs::Decoder,
+}
+
+impl PieceDecoder {
+    fn new() -> Self {
+        Self {
+            decoder: encoding_rs::UTF_8.new_decoder(),
+        }
+    }
+
+    /// The text completed by `bytes`.
+    fn push(&mut self, bytes: &[u8]) -> String {
+        let capacity = self.decoder.max_utf8_buffer_length(bytes.len());
+        let mut text = String::with_capacity(capacity.unwrap_or(bytes.len() + 4));
+        let _ = self.decoder.decode_to_string(bytes, &mut text, false);
+        text
+    }
+
+    /// Drains bytes still buffered. An incomplete character becomes U+FFFD rather than being
+    /// dropped.
+    fn finish(&mut self) -> String {
+        let capacity = self.decoder.max_utf8_buffer_length(0);
+        let mut tail = String::with_capacity(capacity.unwrap_or(4));
+        let _ = self.decoder.decode_to_string(&[], &mut tail, true);
+        tail
+    }
+}
+
+fn estimated_model_bytes(model_path: &Path, model_name: &str) -> u64 {
+    let file_bytes = std::fs::metadata(model_path).map(|m| m.len()).unwrap_or(0);
+    let hint_bytes = crate::models::find_model(model_name)
+        .map(|model| model.size_mb * 1024 * 1024)
+        .unwrap_or(0);
+    file_bytes.max(hint_bytes)
+}
+
+fn check_memory_budget(model_bytes: u64, available_bytes: u64) -> Result<()> {
+    let required_bytes = (model_bytes as f64 * MEMORY_OVERHEAD_FACTOR) as u64;
+    if required_bytes <= available_bytes {
+        return Ok(());
+    }
+    Err(LlmError::ConfigError(format!(
+        "model requires ~{} MB, only {} MB available",
+        required_bytes / (1024 * 1024),
+        available_bytes / (1024 * 1024)
+    )))
+}
+
+fn memory_check_disabled_by_env() -> bool {
+    std::env::var("OPENWISPR_LLM_SKIP_MEMORY_CHECK")
+        .ok()
+        .as_deref()
+        .map(|v| v == "1")
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piece_decoder_buffers_multibyte_char_split_across_pieces() {
+        let emoji = "😀".as_bytes();
+        let (first, second) = emoji.split_at(2);
+        let mut pieces = PieceDecoder::new();
+
+        assert_eq!(pieces.push(b"Hi "), "Hi ");
+        assert_eq!(pieces.push(first), "");
+        assert_eq!(pieces.push(second), "😀");
+        assert_eq!(pieces.finish(), "");
+    }
+
+    #[test]
+    fn piece_decoder_finish_releases_a_dangling_partial_character() {
+        let mut pieces = PieceDecoder::new();
+        assert_eq!(pieces.push(&"😀".as_bytes()[..2]), "");
+        assert_eq!(pieces.finish(), "\u{FFFD}");
+
+        assert_eq!(PieceDecoder::new().finish(), "");
+    }
+
+    #[test]
+    fn repeated_tokens_are_penalized_within_the_window() {
+        let token = LlamaToken::new;
+        let mut candidates = vec![
+            LlamaTokenData::new(token(1), 2.0, 0.0),
+            LlamaTokenData::new(token(2), 1.9, 0.0),
+            LlamaTokenData::new(token(3), -1.0, 0.0),
+        ];
+        let mut recent = RecentTokens::new(2);
+        for id in [2, 1, 3] {
+            recent.push(token(id));
+        }
+
+        // Token 2 fell out of the two-token window; 1 and 3 were just generated
+        assert_eq!(penalize_recent_tokens(&mut candidates, &recent, 1.1), 2);
+        assert!(candidates[0].logit() < candidates[1].logit());
+        assert_eq!(candidates[1].logit(), 1.9);
+        assert!((candidates[2].logit() + 1.1).abs() < 1e-6);
+
+        assert_eq!(penalize_recent_tokens(&mut candidates, &recent, 1.0), 0);
+        assert_eq!(
+            penalize_recent_tokens(&mut candidates, &RecentTokens::new(0), 1.1),
+            0
+        );
+    }
+
+    #[test]
+    fn output_is_cut_at_the_first_stop_sequence() {
+        let stops = vec!["\n\n".to_string(), "<|im_end|>".to_string()];
+        let text = "Meet at noon.<|im_end|>\n\nHere is the cleaned text:";
+        assert_eq!(find_stop(text, &stops), Some("Meet at noon.".len()));
+        assert_eq!(find_stop("One.\n\nTwo.<|im_end|>", &stops), Some(4));
+        assert_eq!(find_stop("\n\nMeet at noon.", &stops), None);
+        assert_eq!(find_stop("Meet at noon.", &[]), None);
+    }
+
+    #[test]
+    fn partial_stop_sequences_are_held_back_from_the_stream() {
+        let stops = vec!["\n\n".to_string(), "<|im_end|>".to_string()];
+        assert_eq!(pending_stop_len("Meet at noon.<|im", &stops), 4);
+        assert_eq!(pending_stop_len("Meet at noon.\n", &stops), 1);
+        assert_eq!(pending_stop_len("Meet at noon.", &stops), 0);
+        assert_eq!(pending_stop_len("Meet at noon.<", &[]), 0);
+    }
+
+    #[test]
+    fn reusable_slot_creates_once_until_cleared() {
+        let created = std::cell::Cell::new(0);
+        let create = || {
+            created.set(created.get() + 1);
+            Ok(Vec::<u32>::new())
+        };
+        let slot = Reusable::new();
+
+        for _ in 0..2 {
+            let mut guard = slot.acquire(create).unwrap();
+            guard.as_mut().unwrap().push(1);
+        }
+        assert_eq!(created.get(), 1);
+        assert_eq!(
+            slot.acquire(create).unwrap().as_ref().map(Vec::len),
+            Some(2)
+        );
+
+        slot.clear();
+        assert_eq!(
+            slot.acquire(create).unwrap().as_ref().map(Vec::len),
+            Some(0)
+        );
+        assert_eq!(created.get(), 2);
+    }
+
+    // Requires SmolLM2-135M-Instruct-Q4_K_M to be downloaded
+    #[test]
+    #[ignore]
+    fn second_prompt_reuses_the_context() {
+        let mut adapter = LlamaCppAdapter::new();
+        tokio_test::block_on(
+            adapter.initialize(LlmConfig::for_model("SmolLM2-135M-Instruct-Q4_K_M")),
+        )
+        .expect("model must be downloaded for this test");
+
+        for prompt in ["Say hello.", "Say goodbye."] {
+            tokio_test::block_on(adapter.run_prompt(prompt.to_string(), 8)).unwrap();
+        }
+        assert_eq!(adapter.contexts_created.load(Ordering::Relaxed), 1);
+    }
+
+    // Requires SmolLM2-135M-Instruct-Q4_K_M to be downloaded
+    #[test]
+    #[ignore]
+    fn same_seed_samples_the_same_output() {
+        let mut adapter = LlamaCppAdapter::new();
+        let config = LlmConfig {
+            temperature: 0.9,
+            seed: Some(42),
+            grammar: None,
+            ..LlmConfig::for_model("SmolLM2-135M-Instruct-Q4_K_M")
+        };
+        tokio_test::block_on(adapter.initialize(config))
+            .expect("model must be downloaded for this test");
+
+        let prompt = "Write one sentence about the sea.";
+        let first = tokio_test::block_on(adapter.run_prompt(prompt.to_string(), 24)).unwrap();
+        let second = tokio_test::block_on(adapter.run_prompt(prompt.to_string(), 24)).unwrap();
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+    }
+
+    /// Whether llama.cpp's grammar sampler lets `model` produce exactly `text` and then end.
+    fn grammar_accepts(model: &LlamaModel, grammar: &str, text: &str) -> bool {
+        let mut sampler = LlamaSampler::grammar(model, grammar, "root");
+        let mut allows = |token: LlamaToken| {
+            let mut candidates =
+                LlamaTokenDataArray::new(vec![LlamaTokenData::new(token, 0.0, 0.0)], false);
+            candidates.apply_sampler(&mut sampler);
+            candidates.data[0].logit().is_finite()
+        };
+        let tokens = model.str_to_token(text, AddBos::Never).unwrap();
+        for token in tokens {
+            if !allows(token) {
+                return false;
+            }
+            sampler.accept(token);
+        }
+        allows(model.token_eos())
+    }
+
+    // Requires SmolLM2-135M-Instruct-Q4_K_M to be downloaded
+    #[test]
+    #[ignore]
+    fn plain_prose_grammar_accepts_dictation_and_rejects_wrappers() {
+        let mut adapter = LlamaCppAdapter::new();
+        tokio_test::block_on(
+            adapter.initialize(LlmConfig::for_model("SmolLM2-135M-Instruct-Q4_K_M")),
+        )
+        .expect("model must be downloaded for this test");
+        let model = adapter.loaded_model().unwrap();
+        let grammar = prompts::PLAIN_PROSE_GRAMMAR;
+
+        // Spoken-punctuation output as text_processor::apply_spoken_punctuation produces it
+        for dictation in [
+            "first line\nSecond\n\nThird",
+            "\"hello\" she said.",
+            "(hello, world).",
+            "is it done? Yes!",
+            "he said: \"hi\"",
+            "wait - what...",
+            " The meeting is at 3:30. Bring notes: all of them.",
+        ] {
+            assert!(grammar_accepts(model, grammar, dictation), "rejects {dictation:?}");
+        }
+        for wrapped in [
+            "Output: hello",
+            "Fine.\n- item",
+            "1. foo\n2. bar",
+            "Here is the cleaned text:",
+            "# Heading",
+            "Use `code` here.",
+        ] {
+            assert!(!grammar_accepts(model, grammar, wrapped), "accepts {wrapped:?}");
+        }
+    }
+
+    #[test]
+    fn format_text_after_unload_reports_not_initialized() {
+        let mut adapter = LlamaCppAdapter::new();
+        tokio_test::block_on(adapter.unload());
+        assert_eq!(adapter.current_model(), None);
+
+        let err = tokio_test::block_on(adapter.format_text(TextFormattingRequest {
+            raw_text: "um hello there".to_string(),
+            format_type: FormattingType::SmartFormat,
+        }))
+        .unwrap_err();
+        assert!(
+            matches!(err, LlmError::ModelLoadError(ref message) if message.contains("not initialized"))
+        );
+    }
+
+    #[test]
+    fn token_counting_before_initialize_reports_not_initialized() {
+        let adapter = LlamaCppAdapter::new();
+        for err in [
+            adapter.count_tokens("hello there").unwrap_err(),
+            adapter.tokenize("hello there").unwrap_err(),
+            adapter.detokenize(&[1, 2]).unwrap_err(),
+        ] {
+            assert!(
+                matches!(err, LlmError::ModelLoadError(ref message) if message.contains("not initialized"))
+            );
+        }
+    }
+
+    #[test]
+    fn check_memory_budget_rejects_models_that_do_not_fit() {
+        const MB: u64 = 1024 * 1024;
+        assert!(check_memory_budget(1200 * MB, 8000 * MB).is_ok());
+
+        let err = check_memory_budget(1200 * MB, 1000 * MB).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Configuration error: model requires ~1500 MB, only 1000 MB available"
+        );
+    }
+}
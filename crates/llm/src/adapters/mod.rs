@@ -1,3 +1,5 @@
 pub mod llamacpp;
+pub mod ollama;
 
 pub use llamacpp::LlamaCppAdapter;
+pub use ollama::OllamaAdapter;
@@ -0,0 +1,242 @@
+use crate::{
+    prompts, FormattingType, LlmAdapter, LlmConfig, LlmError, Result, TextFormattingRequest,
+    TextFormattingResponse,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const GENERATE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Serialize)]
+struct GenerateRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+    stream: bool,
+    options: GenerateOptions,
+}
+
+#[derive(Serialize)]
+struct GenerateOptions {
+    num_predict: u32,
+    temperature: f32,
+    top_p: f32,
+    top_k: u32,
+    repeat_penalty: f32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+/// A model as listed by `/api/tags`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    pub size: u64,
+    pub digest: String,
+    pub details: Option<OllamaModelDetails>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OllamaModelDetails {
+    pub format: String,
+    pub family: String,
+    pub families: Option<Vec<String>>,
+    pub parameter_size: String,
+    pub quantization_level: String,
+}
+
+/// Body of `/api/tags`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OllamaTagsResponse {
+    pub models: Vec<OllamaModel>,
+}
+
+/// Adapter for a locally running Ollama server (`/api/generate`).
+pub struct OllamaAdapter {
+    base_url: String,
+    config: Option<LlmConfig>,
+}
+
+impl OllamaAdapter {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: normalize_base_url(base_url),
+            config: None,
+        }
+    }
+
+    fn ensure_initialized(&self) -> Result<&LlmConfig> {
+        self.config.as_ref().ok_or_else(|| {
+            LlmError::ModelLoadError("Adapter not initialized. Call initialize() first.".to_string())
+        })
+    }
+
+    async fn list_model_names(&self) -> Result<Vec<String>> {
+        let base_url = self.base_url.clone();
+        run_blocking(move || list_model_names(&base_url)).await
+    }
+
+    async fn generate_response(&self, prompt: String, max_tokens: u32) -> Result<String> {
+        let config = self.ensure_initialized()?.clone();
+        let base_url = self.base_url.clone();
+        run_blocking(move || generate_response(&base_url, &config, &prompt, max_tokens)).await
+    }
+}
+
+/// Runs a blocking `ureq` call on tokio's blocking pool so a slow generation does not stall
+/// an async worker thread.
+async fn run_blocking<T: Send + 'static>(
+    call: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(call)
+        .await
+        .map_err(|e| LlmError::InferenceFailed(format!("Ollama request task failed: {}", e)))?
+}
+
+fn list_model_names(base_url: &str) -> Result<Vec<String>> {
+    let url = format!("{}/api/tags", base_url);
+    let tags: OllamaTagsResponse = ureq::get(&url)
+        .timeout(CONNECT_TIMEOUT)
+        .call()
+        .map_err(|e| LlmError::ModelLoadError(format!("Failed to reach Ollama at {}: {}", url, e)))?
+        .into_json()
+        .map_err(|e| {
+            LlmError::ModelLoadError(format!("Failed to parse Ollama model list: {}", e))
+        })?;
+    Ok(tags.models.into_iter().map(|m| m.name).collect())
+}
+
+fn generate_response(
+    base_url: &str,
+    config: &LlmConfig,
+    prompt: &str,
+    max_tokens: u32,
+) -> Result<String> {
+    let url = format!("{}/api/generate", base_url);
+    let request = GenerateRequest {
+        model: &config.model_name,
+        prompt,
+        stream: false,
+        options: GenerateOptions {
+            num_predict: max_tokens,
+            temperature: config.temperature,
+            top_p: config.top_p,
+            top_k: config.top_k,
+            repeat_penalty: config.repeat_penalty,
+            stop: config.stop.clone(),
+        },
+    };
+
+    let response: GenerateResponse = ureq::post(&url)
+        .timeout(GENERATE_TIMEOUT)
+        .send_json(&request)
+        .map_err(|e| LlmError::InferenceFailed(format!("Ollama request failed: {}", e)))?
+        .into_json()
+        .map_err(|e| {
+            LlmError::InferenceFailed(format!("Failed to parse Ollama response: {}", e))
+        })?;
+
+    Ok(response.response.trim().to_string())
+}
+
+#[async_trait]
+impl LlmAdapter for OllamaAdapter {
+    async fn initialize(&mut self, config: LlmConfig) -> Result<()> {
+        tracing::info!(
+            "Initializing Ollama adapter at {} with model: {}",
+            self.base_url,
+            config.model_name
+        );
+
+        let models = self.list_model_names().await?;
+        if !models.iter().any(|name| model_matches(name, &config.model_name)) {
+            return Err(LlmError::ModelNotFound(format!(
+                "Ollama model '{}' is not pulled on {}",
+                config.model_name, self.base_url
+            )));
+        }
+
+        self.config = Some(config);
+        Ok(())
+    }
+
+    async fn format_text(&self, request: TextFormattingRequest) -> Result<TextFormattingResponse> {
+        let config = self.ensure_initialized()?;
+
+        let prompt = match request.format_type {
+            FormattingType::RemoveFillers => prompts::create_filler_removal_prompt(&request.raw_text),
+            FormattingType::AddPunctuation => prompts::create_punctuation_prompt(&request.raw_text),
+            FormattingType::FixCapitalization => prompts::create_capitalization_prompt(&request.raw_text),
+            FormattingType::CourseCorrection => prompts::create_course_correction_prompt(&request.raw_text),
+            FormattingType::SmartFormat => prompts::create_smart_format_prompt(&request.raw_text),
+        };
+
+        let formatted_text = self.generate_response(prompt, config.max_tokens).await?;
+
+        Ok(TextFormattingResponse {
+            formatted_text,
+            original_text: request.raw_text,
+        })
+    }
+
+    async fn run_prompt(&self, prompt: String, max_tokens: u32) -> Result<String> {
+        self.generate_response(prompt, max_tokens).await
+    }
+
+    async fn is_model_available(&self, model_name: &str) -> bool {
+        self.list_model_names()
+            .await
+            .map(|models| models.iter().any(|name| model_matches(name, model_name)))
+            .unwrap_or(false)
+    }
+
+    fn current_model(&self) -> Option<String> {
+        self.config.as_ref().map(|config| config.model_name.clone())
+    }
+}
+
+/// Adds a missing `http://` scheme and drops trailing slashes; blank means the default.
+pub fn normalize_base_url(base_url: &str) -> String {
+    let trimmed = base_url.trim();
+    let url = if trimmed.is_empty() {
+        DEFAULT_OLLAMA_BASE_URL.to_string()
+    } else if trimmed.starts_with("http") {
+        trimmed.to_string()
+    } else {
+        format!("http://{}", trimmed)
+    };
+    url.trim_end_matches('/').to_string()
+}
+
+/// Ollama reports tags as `name:tag`; a bare name refers to `:latest`.
+fn model_matches(available: &str, requested: &str) -> bool {
+    available == requested
+        || (!requested.contains(':') && available == format!("{}:latest", requested))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_base_url_adds_scheme_and_trims_slash() {
+        assert_eq!(normalize_base_url("localhost:11434/"), "http://localhost:11434");
+        assert_eq!(normalize_base_url("https://ollama.lan"), "https://ollama.lan");
+        assert_eq!(normalize_base_url("  "), DEFAULT_OLLAMA_BASE_URL);
+    }
+
+    #[test]
+    fn model_matches_resolves_latest_tag() {
+        assert!(model_matches("llama3.2:latest", "llama3.2"));
+        assert!(model_matches("llama3.2:3b", "llama3.2:3b"));
+        assert!(!model_matches("llama3.2:3b", "llama3.2"));
+    }
+}
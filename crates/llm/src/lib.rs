@@ -3,13 +3,18 @@
 
 use async_trait::async_trait;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 use thiserror::Error;
 
 pub mod adapters;
 pub mod models;
 pub mod prompts;
 
-pub use models::{LlmModelInfo, list_models, download_model, get_model_path, is_model_downloaded};
+pub use models::{
+    LlmModelInfo, SamplingPreset, list_models, download_model, get_model_path, stage_import,
+    is_model_downloaded, sampling_preset_for,
+};
 
 /// LLM-specific errors
 #[derive(Debug, Error)]
@@ -47,21 +52,49 @@ pub struct LlmConfig {
     pub max_tokens: u32,
     pub top_p: f32,
     pub top_k: u32,
+    pub repeat_penalty: f32,
+    /// How many of the most recently generated tokens `repeat_penalty` applies to.
+    pub repeat_last_n: usize,
+    /// Generation halts at the first of these and the result ends right before it.
+    pub stop: Vec<String>,
+    /// GBNF grammar, starting at rule `root`, that output must match, such as
+    /// [`prompts::PLAIN_PROSE_GRAMMAR`]. Only the llama.cpp backend enforces it.
+    pub grammar: Option<String>,
+    /// Seed for sampling when `temperature` is above zero. Fix it to make output
+    /// reproducible; `None` draws a fresh seed for every generation.
+    pub seed: Option<u32>,
+    /// Skip the free-memory check before loading (e.g. when GPU offload covers the model).
+    pub allow_oversized_model: bool,
 }
 
-impl Default for LlmConfig {
-    fn default() -> Self {
+impl LlmConfig {
+    /// Config for `model_name` with its [`models::sampling_preset_for`] sampling applied.
+    /// Set fields afterwards to override the preset.
+    pub fn for_model(model_name: &str) -> Self {
+        let preset = models::sampling_preset_for(model_name);
         Self {
-            model_name: "SmolLM2-135M-Instruct-Q4_K_M".to_string(),
+            model_name: model_name.to_string(),
             model_path: None,
-            temperature: 0.3, // Lower for more deterministic formatting
-            max_tokens: 512,  // Reasonable for text formatting
-            top_p: 0.9,
-            top_k: 40,
+            temperature: preset.temperature,
+            max_tokens: 512, // Reasonable for text formatting
+            top_p: preset.top_p,
+            top_k: preset.top_k,
+            repeat_penalty: preset.repeat_penalty,
+            repeat_last_n: 64,
+            stop: Vec::new(),
+            grammar: None,
+            seed: None,
+            allow_oversized_model: false,
         }
     }
 }
 
+impl Default for LlmConfig {
+    fn default() -> Self {
+        Self::for_model("SmolLM2-135M-Instruct-Q4_K_M")
+    }
+}
+
 /// Request for text formatting
 #[derive(Debug, Clone)]
 pub struct TextFormattingRequest {
@@ -86,18 +119,86 @@ pub struct TextFormattingResponse {
     pub original_text: String,
 }
 
+/// Text from [`LlmAdapter::run_prompt_with_deadline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptOutput {
+    pub text: String,
+    /// Generation stopped at the deadline, so `text` is only the beginning of the answer.
+    pub timed_out: bool,
+}
+
+/// Receives each decoded piece of output as soon as generation produces it.
+pub type TokenCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
 /// Core LLM adapter trait - implemented by different backends
 #[async_trait]
 pub trait LlmAdapter: Send + Sync {
     /// Initialize the adapter and load the model
     async fn initialize(&mut self, config: LlmConfig) -> Result<()>;
 
+    /// Free the loaded model. Later calls fail as uninitialized until `initialize` runs
+    /// again. Backends that hold nothing locally do nothing.
+    async fn unload(&mut self) {}
+
     /// Format text using the LLM
     async fn format_text(&self, request: TextFormattingRequest) -> Result<TextFormattingResponse>;
 
     /// Run a custom prompt
     async fn run_prompt(&self, prompt: String, max_tokens: u32) -> Result<String>;
 
+    /// Run a custom prompt, stopping generation at `deadline` and returning what was produced.
+    /// Backends that cannot interrupt decoding run to completion.
+    async fn run_prompt_with_deadline(
+        &self,
+        prompt: String,
+        max_tokens: u32,
+        deadline: Instant,
+    ) -> Result<PromptOutput> {
+        let _ = deadline;
+        let text = self.run_prompt(prompt, max_tokens).await?;
+        Ok(PromptOutput {
+            text,
+            timed_out: false,
+        })
+    }
+
+    /// Run a custom prompt, passing each decoded piece to `on_token` as it is generated, and
+    /// return the whole output as [`LlmAdapter::run_prompt`] would. Pieces are untrimmed.
+    /// Backends that cannot stream report the finished output as a single piece.
+    async fn run_prompt_stream(
+        &self,
+        prompt: String,
+        max_tokens: u32,
+        on_token: TokenCallback,
+    ) -> Result<String> {
+        let text = self.run_prompt(prompt, max_tokens).await?;
+        on_token(&text);
+        Ok(text)
+    }
+
+    /// Count tokens in `text` using the loaded model's tokenizer.
+    /// Backends without local tokenizer access fall back to a ~4 chars/token estimate.
+    fn count_tokens(&self, text: &str) -> Result<usize> {
+        Ok(text.chars().count().div_ceil(4))
+    }
+
+    /// Token ids for `text` in the loaded model's vocabulary, without a BOS token.
+    /// Backends without local tokenizer access return [`LlmError::InferenceFailed`].
+    fn tokenize(&self, text: &str) -> Result<Vec<i32>> {
+        let _ = text;
+        Err(LlmError::InferenceFailed(
+            "Tokenization is not available for this backend".to_string(),
+        ))
+    }
+
+    /// Text for token ids from [`LlmAdapter::tokenize`].
+    fn detokenize(&self, tokens: &[i32]) -> Result<String> {
+        let _ = tokens;
+        Err(LlmError::InferenceFailed(
+            "Tokenization is not available for this backend".to_string(),
+        ))
+    }
+
     /// Check if a model is available/downloaded
     async fn is_model_available(&self, model_name: &str) -> bool;
 
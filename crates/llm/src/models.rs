@@ -1,5 +1,12 @@
+use platform::catalog::{
+    file_has_magic, load_custom_models, CatalogModel, StagedImport, RUNTIME_LLAMA,
+};
+use platform::checksum::huggingface_sha256;
+pub use platform::download::ModelDownloadProgress;
+use platform::download::{self, DownloadError};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -12,6 +19,12 @@ pub enum ModelError {
     
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+
+    #[error("Invalid model file: {0}")]
+    InvalidModel(String),
+
+    #[error("Download cancelled")]
+    Cancelled,
 }
 
 pub type Result<T> = std::result::Result<T, ModelError>;
@@ -48,10 +61,67 @@ pub const AVAILABLE_MODELS: &[(&str, &str, &str, u64)] = &[
     ),
 ];
 
+/// Sampling parameters tuned for a particular model family.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingPreset {
+    pub temperature: f32,
+    pub top_p: f32,
+    pub top_k: u32,
+    pub repeat_penalty: f32,
+}
+
+/// Tuned sampling for known models; unknown models get the conservative formatting default.
+/// Fix per-model issues (e.g. a model that loops) here rather than at call sites.
+pub fn sampling_preset_for(model_name: &str) -> SamplingPreset {
+    let name = model_name.to_lowercase();
+    if name.contains("smollm2-135m") || name.contains("smollm2-360m") {
+        // Tiny models repeat themselves easily; keep them cold with a firmer penalty
+        SamplingPreset {
+            temperature: 0.2,
+            top_p: 0.9,
+            top_k: 40,
+            repeat_penalty: 1.15,
+        }
+    } else if name.contains("qwen") {
+        SamplingPreset {
+            temperature: 0.7,
+            top_p: 0.8,
+            top_k: 20,
+            repeat_penalty: 1.05,
+        }
+    } else {
+        SamplingPreset {
+            temperature: 0.3,
+            top_p: 0.9,
+            top_k: 40,
+            repeat_penalty: 1.1,
+        }
+    }
+}
+
+fn model_cache_dir_override_slot() -> &'static RwLock<Option<PathBuf>> {
+    static SLOT: OnceLock<RwLock<Option<PathBuf>>> = OnceLock::new();
+    SLOT.get_or_init(|| RwLock::new(None))
+}
+
+/// Stores LLM models in `dir` instead of the platform default. `OPENWISPR_LLM_MODEL_DIR`
+/// still takes precedence.
+pub fn set_model_cache_dir_override(dir: Option<PathBuf>) {
+    if let Ok(mut slot) = model_cache_dir_override_slot().write() {
+        *slot = dir;
+    }
+}
+
 /// Get the local model cache directory
 pub fn get_model_cache_dir() -> Result<PathBuf> {
+    let override_dir = model_cache_dir_override_slot()
+        .read()
+        .ok()
+        .and_then(|slot| slot.clone());
     let cache_dir = if let Ok(custom_dir) = std::env::var("OPENWISPR_LLM_MODEL_DIR") {
         PathBuf::from(custom_dir)
+    } else if let Some(override_dir) = override_dir {
+        override_dir
     } else {
         #[cfg(target_os = "macos")]
         {
@@ -76,17 +146,49 @@ pub fn get_model_cache_dir() -> Result<PathBuf> {
     Ok(cache_dir)
 }
 
+/// Built-in models followed by valid `llama.cpp` entries from `models.json` in the cache
+/// dir. Built-ins win on name clashes.
+pub fn catalog() -> Vec<CatalogModel> {
+    let mut models: Vec<CatalogModel> = AVAILABLE_MODELS
+        .iter()
+        .map(|(name, repo, filename, size_mb)| CatalogModel {
+            name: name.to_string(),
+            repo: repo.to_string(),
+            filename: filename.to_string(),
+            size_mb: *size_mb,
+            runtime: RUNTIME_LLAMA.to_string(),
+            sha256: None,
+        })
+        .collect();
+    if let Ok(dir) = get_model_cache_dir() {
+        for custom in load_custom_models(&dir, RUNTIME_LLAMA) {
+            if !models.iter().any(|model| model.name == custom.name) {
+                models.push(custom);
+            }
+        }
+    }
+    models
+}
+
+pub fn find_model(model_name: &str) -> Option<CatalogModel> {
+    catalog().into_iter().find(|model| model.name == model_name)
+}
+
+fn is_builtin_model(model_name: &str) -> bool {
+    AVAILABLE_MODELS
+        .iter()
+        .any(|(name, _, _, _)| *name == model_name)
+}
+
 /// Get the path to a specific model file
 pub fn get_model_path(model_name: &str) -> Result<PathBuf> {
     let cache_dir = get_model_cache_dir()?;
     
     // Find the model info
-    let model_info = AVAILABLE_MODELS
-        .iter()
-        .find(|(name, _, _, _)| *name == model_name)
+    let model_info = find_model(model_name)
         .ok_or_else(|| ModelError::NotFound(format!("Model '{}' not found", model_name)))?;
     
-    Ok(cache_dir.join(model_info.2))
+    Ok(cache_dir.join(model_info.filename))
 }
 
 /// Check if a model is downloaded
@@ -98,34 +200,41 @@ pub fn is_model_downloaded(model_name: &str) -> bool {
 
 /// List all available models with their status
 pub fn list_models() -> Vec<LlmModelInfo> {
-    AVAILABLE_MODELS
-        .iter()
-        .map(|(name, repo, filename, size_mb)| {
-            let downloaded = is_model_downloaded(name);
+    catalog()
+        .into_iter()
+        .map(|model| {
+            let downloaded = is_model_downloaded(&model.name);
+            let description = if is_builtin_model(&model.name) {
+                let size = model.name.split('-').nth(1).unwrap_or("");
+                format!("SmolLM2 {} quantized model", size)
+            } else if model.is_local() {
+                "Imported from a local file".to_string()
+            } else {
+                "Custom model from models.json".to_string()
+            };
             LlmModelInfo {
-                name: name.to_string(),
-                size_mb: *size_mb,
+                name: model.name,
+                size_mb: model.size_mb,
                 downloaded,
-                description: format!("SmolLM2 {} quantized model", name.split('-').nth(1).unwrap_or("")),
-                hf_repo: repo.to_string(),
-                filename: filename.to_string(),
+                description,
+                hf_repo: model.repo,
+                filename: model.filename,
             }
         })
         .collect()
 }
 
-/// Download a model from HuggingFace
+/// Download a model from HuggingFace, reporting `download`, `retry` and `verify` progress to
+/// `on_progress` and a final event with `error` set if it fails.
 pub async fn download_model(
     model_name: &str,
-    progress_callback: Option<impl Fn(u64, u64) + Send + Sync>,
+    on_progress: Option<impl Fn(ModelDownloadProgress) + Send + Sync>,
 ) -> Result<PathBuf> {
-    let model_info = AVAILABLE_MODELS
-        .iter()
-        .find(|(name, _, _, _)| *name == model_name)
+    let model_info = find_model(model_name)
         .ok_or_else(|| ModelError::NotFound(format!("Model '{}' not found", model_name)))?;
 
     let cache_dir = get_model_cache_dir()?;
-    let model_path = cache_dir.join(model_info.2);
+    let model_path = cache_dir.join(&model_info.filename);
 
     // Skip if already downloaded
     if model_path.exists() {
@@ -134,41 +243,196 @@ pub async fn download_model(
     }
 
     // Construct HuggingFace URL
-    let url = format!(
-        "https://huggingface.co/{}/resolve/main/{}",
-        model_info.1, model_info.2
-    );
+    let url = model_info.download_url().ok_or_else(|| {
+        ModelError::NotFound(format!(
+            "Model '{}' was imported from a local file; import it again to restore it",
+            model_name
+        ))
+    })?;
+
+    let expected_sha256 = model_info
+        .sha256
+        .clone()
+        .or_else(|| huggingface_sha256(&model_info.repo, &model_info.filename));
+    let estimated_bytes = Some(model_info.size_mb * 1024 * 1024).filter(|bytes| *bytes > 0);
 
     tracing::info!("Downloading {} from {}", model_name, url);
 
-    // Download with progress tracking
-    let response = ureq::get(&url)
-        .call()
-        .map_err(|e| ModelError::DownloadFailed(e.to_string()))?;
-
-    let total_size = response
-        .header("content-length")
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(0);
-
-    let mut reader = response.into_reader();
-    let mut file = std::fs::File::create(&model_path)?;
-    let mut downloaded = 0u64;
-    let mut buffer = [0; 8192];
-
-    loop {
-        let bytes_read = std::io::Read::read(&mut reader, &mut buffer)?;
-        if bytes_read == 0 {
-            break;
+    let report = |progress: ModelDownloadProgress| {
+        if let Some(callback) = &on_progress {
+            callback(progress);
         }
-        std::io::Write::write_all(&mut file, &buffer[..bytes_read])?;
-        downloaded += bytes_read as u64;
+    };
+    fetch_model_file(
+        model_name,
+        &url,
+        &model_path,
+        estimated_bytes,
+        expected_sha256.as_deref(),
+        &report,
+    )?;
 
-        if let Some(ref callback) = progress_callback {
-            callback(downloaded, total_size);
+    tracing::info!("Model {} downloaded to {:?}", model_name, model_path);
+    Ok(model_path)
+}
+
+/// Downloads `url` into `dest` through the shared model fetch and reports a final `done`
+/// event with `error` set when it fails, or a `cancelled` one when it was cancelled.
+fn fetch_model_file(
+    model_name: &str,
+    url: &str,
+    dest: &Path,
+    estimated_bytes: Option<u64>,
+    expected_sha256: Option<&str>,
+    report: &dyn Fn(ModelDownloadProgress),
+) -> Result<u64> {
+    download::fetch_model_file(
+        model_name,
+        url,
+        dest,
+        expected_sha256,
+        estimated_bytes,
+        "Downloading model",
+        report,
+    )
+    .map_err(|e| {
+        if matches!(e, DownloadError::Cancelled) {
+            report(ModelDownloadProgress {
+                model_name: model_name.to_string(),
+                stage: "cancelled".to_string(),
+                downloaded_bytes: 0,
+                total_bytes: None,
+                percent: None,
+                done: true,
+                error: None,
+                message: Some("Model download cancelled".to_string()),
+            });
+            return ModelError::Cancelled;
         }
+        let stage = match e {
+            DownloadError::Checksum { .. } => "verify",
+            _ => "download",
+        };
+        report(ModelDownloadProgress {
+            model_name: model_name.to_string(),
+            stage: stage.to_string(),
+            downloaded_bytes: 0,
+            total_bytes: None,
+            percent: None,
+            done: true,
+            error: Some(e.to_string()),
+            message: Some("Model download failed".to_string()),
+        });
+        ModelError::DownloadFailed(e.to_string())
+    })
+}
+
+/// Stages a local `.gguf` in the cache for validation; committing it registers it in
+/// `models.json` (built-in names just fill in the missing download).
+pub fn stage_import(source: &Path) -> Result<StagedImport> {
+    if source.extension().and_then(|ext| ext.to_str()) != Some("gguf")
+        || !file_has_magic(source, b"GGUF")
+    {
+        return Err(ModelError::InvalidModel(format!(
+            "{} is not a GGUF model",
+            source.display()
+        )));
     }
+    let name = source
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| !stem.is_empty())
+        .ok_or_else(|| ModelError::InvalidModel(format!("{} has no name", source.display())))?
+        .to_string();
 
-    tracing::info!("Model {} downloaded to {:?}", model_name, model_path);
-    Ok(model_path)
+    let cache_dir = get_model_cache_dir()?;
+    let builtin = find_model(&name).filter(|model| is_builtin_model(&model.name));
+    let entry = CatalogModel {
+        filename: builtin
+            .as_ref()
+            .map_or_else(|| format!("{name}.gguf"), |model| model.filename.clone()),
+        name,
+        repo: String::new(),
+        size_mb: 0,
+        runtime: RUNTIME_LLAMA.to_string(),
+        sha256: None,
+    };
+    let staged = StagedImport::stage(source, &cache_dir, entry, builtin.is_none())
+        .map_err(ModelError::InvalidModel)?;
+    tracing::info!("Staged {} from {:?}", staged.name, source);
+    Ok(staged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serves `body` to one request without a `Content-Length`, like some mirrors do.
+    fn serve_without_length(body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && !line.trim().is_empty() {
+                line.clear();
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            stream.write_all(body).unwrap();
+        });
+        format!("http://{addr}/model.gguf")
+    }
+
+    #[test]
+    fn download_writes_a_partial_file_and_renames_it_when_done() {
+        let body: &'static [u8] = b"GGUF model bytes";
+        let dir =
+            std::env::temp_dir().join(format!("openwispr-llm-download-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let dest = dir.join("model.gguf");
+        let events = RefCell::new(Vec::new());
+        let report = |progress: ModelDownloadProgress| {
+            if progress.stage == "download" {
+                assert!(!dest.exists(), "model visible before the download finished");
+            }
+            events.borrow_mut().push(progress);
+        };
+
+        let url = serve_without_length(body);
+        let bytes = fetch_model_file("tiny", &url, &dest, Some(32), None, &report).unwrap();
+        assert_eq!(bytes, body.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+        assert!(!platform::download::partial_path(&dest).exists());
+        let last = events.borrow().last().cloned().unwrap();
+        assert_eq!((last.total_bytes, last.percent), (Some(32), Some(50.0)));
+
+        let rejected = dir.join("rejected.gguf");
+        let url = serve_without_length(body);
+        let record = |progress: ModelDownloadProgress| events.borrow_mut().push(progress);
+        let err = fetch_model_file("tiny", &url, &rejected, None, Some("00"), &record).unwrap_err();
+        assert!(matches!(err, ModelError::DownloadFailed(_)));
+        assert!(!rejected.exists());
+        assert!(!platform::download::partial_path(&rejected).exists());
+        let failed = events.borrow().last().cloned().unwrap();
+        assert!(failed.done && failed.stage == "verify" && failed.error.is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sampling_preset_matches_model_family() {
+        let tiny = sampling_preset_for("SmolLM2-135M-Instruct-Q4_K_M");
+        assert!(tiny.repeat_penalty > sampling_preset_for("unknown-model").repeat_penalty);
+        assert_eq!(sampling_preset_for("Qwen2.5-1.5B-Instruct").top_k, 20);
+        assert_eq!(
+            crate::LlmConfig::for_model("SmolLM2-360M-Instruct-Q4_K_M").temperature,
+            0.2
+        );
+    }
 }
@@ -47,3 +47,15 @@ Only return the cleaned text, nothing else:
         text
     )
 }
+
+/// GBNF grammar for formatted dictation: plain prose. No line may open with markdown, a
+/// list marker or a numbered item, and there are no code fences. The first word cannot be a
+/// label ("Output: ..."), and a colon must be followed by a space or a digit (as in 3:30),
+/// so the text never ends on one introducing more. Dictated line breaks and quotes are
+/// allowed. The optional space admits tokenizers that put one before the first word.
+pub const PLAIN_PROSE_GRAMMAR: &str = r#"
+root  ::= " "? first ("\n"+ start body)*
+first ::= start [^` \t\n:]* (":" [0-9] [^` \t\n:]*)* (" " body)?
+start ::= [^-*+#>|`' \t\n:0-9] | [0-9]+ ([^-.)` \t\n:0-9] | " " | ":" [0-9])
+body  ::= ([^`:\n] | ":" [ 0-9])*
+"#;
@@ -0,0 +1,406 @@
+//! User-editable `models.json` that adds HuggingFace models to the built-in catalogs
+//! without a new release. Each crate reads the file from its own model cache dir and keeps
+//! the entries for its runtime.
+//!
+//! ```json
+//! [
+//!   { "name": "distil-large-v3", "repo": "distil-whisper/distil-large-v3-ggml",
+//!     "filename": "ggml-distil-large-v3.bin", "size_mb": 1520, "runtime": "whisper.cpp" }
+//! ]
+//! ```
+//!
+//! An optional `"sha256"` pins the expected digest; otherwise downloads are checked against
+//! the repo's LFS metadata.
+//!
+//! Models imported from a local file are registered here too, with an empty `repo`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+pub const CUSTOM_MODELS_FILE: &str = "models.json";
+
+pub const RUNTIME_WHISPER: &str = "whisper.cpp";
+pub const RUNTIME_LLAMA: &str = "llama.cpp";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogModel {
+    pub name: String,
+    /// HuggingFace repo, e.g. `bartowski/SmolLM2-360M-Instruct-GGUF`. Empty for models
+    /// imported from a local file, which cannot be re-downloaded.
+    #[serde(default)]
+    pub repo: String,
+    pub filename: String,
+    #[serde(default)]
+    pub size_mb: u64,
+    pub runtime: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+impl CatalogModel {
+    pub fn download_url(&self) -> Option<String> {
+        if self.is_local() {
+            return None;
+        }
+        Some(format!(
+            "https://huggingface.co/{}/resolve/main/{}",
+            self.repo, self.filename
+        ))
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.repo.trim().is_empty()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("empty name".to_string());
+        }
+        let repo_parts: Vec<&str> = self.repo.split('/').collect();
+        if !self.is_local()
+            && (repo_parts.len() != 2 || repo_parts.iter().any(|part| part.trim().is_empty()))
+        {
+            return Err(format!("repo '{}' is not owner/name", self.repo));
+        }
+        // The filename becomes a path inside the cache dir, so keep it a bare file name
+        if self.filename.trim().is_empty()
+            || self.filename.contains(['/', '\\'])
+            || self.filename.starts_with('.')
+        {
+            return Err(format!("filename '{}' is not a plain file name", self.filename));
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `models.json` array. Invalid entries are skipped with a warning; malformed
+/// JSON is an error so callers can fall back to their built-ins.
+pub fn parse_custom_models(json: &str) -> Result<Vec<CatalogModel>, String> {
+    let entries: Vec<CatalogModel> =
+        serde_json::from_str(json).map_err(|e| format!("invalid {CUSTOM_MODELS_FILE}: {e}"))?;
+    Ok(entries
+        .into_iter()
+        .filter(|entry| match entry.validate() {
+            Ok(()) => true,
+            Err(reason) => {
+                tracing::warn!("skipping custom model '{}': {reason}", entry.name);
+                false
+            }
+        })
+        .collect())
+}
+
+/// A parsed `models.json` and the file state it was parsed from.
+struct CachedCatalog {
+    modified: Option<SystemTime>,
+    len: u64,
+    entries: Vec<CatalogModel>,
+}
+
+fn catalog_cache() -> &'static Mutex<HashMap<PathBuf, CachedCatalog>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedCatalog>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Valid `runtime` entries from `<dir>/models.json`; empty when the file is missing or
+/// cannot be parsed. The parsed file is cached until its size or modification time changes.
+pub fn load_custom_models(dir: &Path, runtime: &str) -> Vec<CatalogModel> {
+    let path = dir.join(CUSTOM_MODELS_FILE);
+    let Ok(metadata) = fs::metadata(&path) else {
+        return Vec::new();
+    };
+    let (modified, len) = (metadata.modified().ok(), metadata.len());
+    let Ok(mut cache) = catalog_cache().lock() else {
+        return Vec::new();
+    };
+    let fresh = cache.get(&path).is_some_and(|cached| {
+        cached.modified.is_some() && cached.modified == modified && cached.len == len
+    });
+    if !fresh {
+        let entries = match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| parse_custom_models(&json))
+        {
+            Ok(entries) => entries,
+            Err(err) => {
+                tracing::warn!("ignoring {}: {err}", path.display());
+                Vec::new()
+            }
+        };
+        cache.insert(
+            path.clone(),
+            CachedCatalog {
+                modified,
+                len,
+                entries,
+            },
+        );
+    }
+    cache[&path]
+        .entries
+        .iter()
+        .filter(|entry| entry.runtime == runtime)
+        .cloned()
+        .collect()
+}
+
+fn forget_cached_catalog(path: &Path) {
+    if let Ok(mut cache) = catalog_cache().lock() {
+        cache.remove(path);
+    }
+}
+
+/// Adds or replaces (by name) an entry in `<dir>/models.json`. Other entries are kept
+/// verbatim, including ones this version cannot parse.
+pub fn register_custom_model(dir: &Path, model: &CatalogModel) -> Result<(), String> {
+    model.validate()?;
+    let path = dir.join(CUSTOM_MODELS_FILE);
+    let mut entries: Vec<serde_json::Value> = match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("refusing to overwrite invalid {}: {e}", path.display()))?,
+        Err(_) => Vec::new(),
+    };
+    entries.retain(|entry| entry.get("name").and_then(|name| name.as_str()) != Some(&model.name));
+    entries.push(serde_json::to_value(model).map_err(|e| e.to_string())?);
+
+    let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+    let written =
+        fs::write(&path, json).map_err(|e| format!("failed to write {}: {e}", path.display()));
+    // The rewrite can land within the filesystem's timestamp granularity
+    forget_cached_catalog(&path);
+    written
+}
+
+/// True when the file at `path` begins with `magic`, for cheap format checks on imports.
+pub fn file_has_magic(path: &Path, magic: &[u8]) -> bool {
+    let mut header = vec![0_u8; magic.len()];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map(|_| header == magic)
+        .unwrap_or(false)
+}
+
+/// Staging dir inside a model cache for imports that have not been validated yet.
+const IMPORT_STAGING_DIR: &str = ".import";
+
+/// A user-supplied model copied into `<cache>/.import/` to be validated before it is listed.
+/// [`StagedImport::commit`] moves it to its real name and registers it; dropping it
+/// uncommitted removes the staged copy, so a model that fails validation leaves no trace.
+#[derive(Debug)]
+pub struct StagedImport {
+    pub name: String,
+    staged: PathBuf,
+    dest: PathBuf,
+    /// `models.json` entry added on commit; `None` for a built-in's missing download.
+    entry: Option<CatalogModel>,
+}
+
+impl StagedImport {
+    /// Copies `source` into the staging dir of `cache_dir` for `entry.filename`. A built-in
+    /// (`register` false) may only fill in a missing download, never replace its file.
+    pub fn stage(
+        source: &Path,
+        cache_dir: &Path,
+        entry: CatalogModel,
+        register: bool,
+    ) -> Result<Self, String> {
+        if register {
+            entry.validate()?;
+        }
+        let dest = cache_dir.join(&entry.filename);
+        if !register && dest.exists() {
+            return Err(format!(
+                "{} is already downloaded; delete it before importing a replacement",
+                entry.name
+            ));
+        }
+        let staging_dir = cache_dir.join(IMPORT_STAGING_DIR);
+        fs::create_dir_all(&staging_dir)
+            .map_err(|e| format!("failed to create {}: {e}", staging_dir.display()))?;
+        let staged = staging_dir.join(&entry.filename);
+        let bytes = fs::copy(source, &staged).map_err(|e| {
+            let _ = fs::remove_file(&staged);
+            format!("failed to import {}: {e}", source.display())
+        })?;
+        let entry = CatalogModel {
+            size_mb: bytes / (1024 * 1024),
+            ..entry
+        };
+        Ok(Self {
+            name: entry.name.clone(),
+            staged,
+            dest,
+            entry: register.then_some(entry),
+        })
+    }
+
+    /// The staged copy, for validating the model before it is committed.
+    pub fn path(&self) -> &Path {
+        &self.staged
+    }
+
+    /// Moves the staged copy to its real name and registers it. Returns the model name.
+    pub fn commit(self) -> Result<String, String> {
+        if self.entry.is_none() && self.dest.exists() {
+            return Err(format!("{} was downloaded during the import", self.name));
+        }
+        fs::rename(&self.staged, &self.dest)
+            .map_err(|e| format!("failed to move {} into place: {e}", self.name))?;
+        if let Some(entry) = &self.entry {
+            let dir = self.dest.parent().unwrap_or(Path::new("."));
+            if let Err(err) = register_custom_model(dir, entry) {
+                let _ = fs::remove_file(&self.dest);
+                return Err(err);
+            }
+        }
+        Ok(self.name.clone())
+    }
+}
+
+impl Drop for StagedImport {
+    fn drop(&mut self) {
+        // Gone already after a successful commit
+        let _ = fs::remove_file(&self.staged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keeps_valid_entries_and_skips_invalid_ones() {
+        let json = r#"[
+            { "name": "distil-large-v3", "repo": "distil-whisper/distil-large-v3-ggml",
+              "filename": "ggml-distil-large-v3.bin", "size_mb": 1520, "runtime": "whisper.cpp" },
+            { "name": "escape", "repo": "a/b", "filename": "../../etc/passwd",
+              "runtime": "whisper.cpp" },
+            { "name": "no-owner", "repo": "repo", "filename": "x.gguf", "runtime": "llama.cpp" }
+        ]"#;
+
+        let entries = parse_custom_models(json).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].download_url().as_deref(),
+            Some("https://huggingface.co/distil-whisper/distil-large-v3-ggml/resolve/main/ggml-distil-large-v3.bin")
+        );
+    }
+
+    #[test]
+    fn malformed_json_is_an_error() {
+        assert!(parse_custom_models("{ not json").is_err());
+    }
+
+    #[test]
+    fn load_filters_by_runtime_and_tolerates_missing_file() {
+        let dir =
+            std::env::temp_dir().join(format!("openwispr-catalog-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(load_custom_models(&dir, RUNTIME_LLAMA).is_empty());
+
+        std::fs::write(
+            dir.join(CUSTOM_MODELS_FILE),
+            r#"[{ "name": "qwen", "repo": "Qwen/Qwen2.5-0.5B-Instruct-GGUF",
+                  "filename": "qwen2.5-0.5b-instruct-q4_k_m.gguf", "runtime": "llama.cpp" }]"#,
+        )
+        .unwrap();
+        assert_eq!(load_custom_models(&dir, RUNTIME_LLAMA).len(), 1);
+        assert!(load_custom_models(&dir, RUNTIME_WHISPER).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn load_rereads_the_file_once_it_changes() {
+        let dir =
+            std::env::temp_dir().join(format!("openwispr-catalog-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CUSTOM_MODELS_FILE);
+        std::fs::write(
+            &path,
+            r#"[{ "name": "one", "repo": "a/b", "filename": "one.bin", "runtime": "whisper.cpp" }]"#,
+        )
+        .unwrap();
+        assert_eq!(load_custom_models(&dir, RUNTIME_WHISPER).len(), 1);
+
+        std::fs::write(
+            &path,
+            r#"[{ "name": "one", "repo": "a/b", "filename": "one.bin", "runtime": "whisper.cpp" },
+                { "name": "two", "repo": "a/b", "filename": "two.bin", "runtime": "whisper.cpp" }]"#,
+        )
+        .unwrap();
+        assert_eq!(load_custom_models(&dir, RUNTIME_WHISPER).len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn register_replaces_by_name_and_keeps_other_entries() {
+        let dir = std::env::temp_dir()
+            .join(format!("openwispr-catalog-register-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(CUSTOM_MODELS_FILE),
+            r#"[{ "name": "keep", "repo": "a/b", "filename": "keep.gguf", "runtime": "llama.cpp" },
+                { "name": "mine", "repo": "a/b", "filename": "old.bin", "runtime": "whisper.cpp" }]"#,
+        )
+        .unwrap();
+
+        let imported = CatalogModel {
+            name: "mine".to_string(),
+            repo: String::new(),
+            filename: "ggml-mine.bin".to_string(),
+            size_mb: 42,
+            runtime: RUNTIME_WHISPER.to_string(),
+            sha256: None,
+        };
+        register_custom_model(&dir, &imported).unwrap();
+
+        let whisper = load_custom_models(&dir, RUNTIME_WHISPER);
+        assert_eq!(whisper, vec![imported]);
+        assert!(whisper[0].download_url().is_none());
+        assert_eq!(load_custom_models(&dir, RUNTIME_LLAMA).len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn staged_import_is_listed_only_after_commit() {
+        let dir =
+            std::env::temp_dir().join(format!("openwispr-catalog-stage-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.bin");
+        std::fs::write(&source, b"model").unwrap();
+        let entry = |name: &str| CatalogModel {
+            name: name.to_string(),
+            repo: String::new(),
+            filename: format!("ggml-{name}.bin"),
+            size_mb: 0,
+            runtime: RUNTIME_WHISPER.to_string(),
+            sha256: None,
+        };
+
+        // Dropped without commit: nothing is left or registered
+        let rejected = StagedImport::stage(&source, &dir, entry("bad"), true).unwrap();
+        let staged_path = rejected.path().to_path_buf();
+        assert!(staged_path.exists());
+        drop(rejected);
+        assert!(!staged_path.exists());
+        assert!(!dir.join("ggml-bad.bin").exists());
+        assert!(load_custom_models(&dir, RUNTIME_WHISPER).is_empty());
+
+        let accepted = StagedImport::stage(&source, &dir, entry("good"), true).unwrap();
+        assert_eq!(accepted.commit().unwrap(), "good");
+        assert_eq!(std::fs::read(dir.join("ggml-good.bin")).unwrap(), b"model");
+        assert_eq!(load_custom_models(&dir, RUNTIME_WHISPER).len(), 1);
+
+        // A built-in that is already downloaded is never replaced
+        std::fs::write(dir.join("ggml-base.bin"), b"working").unwrap();
+        assert!(StagedImport::stage(&source, &dir, entry("base"), false).is_err());
+        assert_eq!(std::fs::read(dir.join("ggml-base.bin")).unwrap(), b"working");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
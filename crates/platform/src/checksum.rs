@@ -0,0 +1,170 @@
+//! SHA-256 verification for downloaded models.
+//!
+//! Built-in models are checked against [`BUILTIN_SHA256`], so they are verified even when
+//! the hosting service's API cannot be reached. Anything else gets its digest from that
+//! service's own metadata (HuggingFace LFS object ids, GitHub release asset digests) or
+//! from a `sha256` pinned in `models.json`.
+
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::download::http_agent;
+use crate::mirror;
+
+/// Hashes the file at `path`, reporting `(hashed_bytes, total_bytes)` as it goes.
+pub fn sha256_file(path: &Path, mut on_progress: impl FnMut(u64, u64)) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let total = file.metadata()?.len();
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0_u8; 1024 * 1024];
+    let mut hashed = 0_u64;
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        hashed += n as u64;
+        on_progress(hashed, total);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Compares the file's digest with `expected` (hex, case-insensitive).
+pub fn verify_sha256(
+    path: &Path,
+    expected: &str,
+    on_progress: impl FnMut(u64, u64),
+) -> Result<(), String> {
+    let actual = sha256_file(path, on_progress).map_err(|e| e.to_string())?;
+    if actual.eq_ignore_ascii_case(expected.trim()) {
+        Ok(())
+    } else {
+        Err(format!("expected sha256 {expected}, got {actual}"))
+    }
+}
+
+/// Digests of the built-in models' files as `(repo, filename, sha256)`, copied from the
+/// repo's LFS metadata or release asset digest when a model joins a built-in catalog.
+/// Upstream files are pinned by these, so replacing one upstream means updating its entry.
+const BUILTIN_SHA256: &[(&str, &str, &str)] = &[];
+
+fn builtin_sha256(table: &[(&str, &str, &str)], repo: &str, filename: &str) -> Option<String> {
+    table
+        .iter()
+        .find(|(known_repo, known_file, _)| *known_repo == repo && *known_file == filename)
+        .map(|(_, _, sha256)| sha256.to_string())
+}
+
+/// SHA-256 of `filename` in a HuggingFace repo: the built-in digest when there is one,
+/// otherwise the repo's LFS metadata. `None` when neither knows the file.
+pub fn huggingface_sha256(repo: &str, filename: &str) -> Option<String> {
+    if let Some(sha256) = builtin_sha256(BUILTIN_SHA256, repo, filename) {
+        return Some(sha256);
+    }
+    let url = format!("https://huggingface.co/api/models/{repo}/tree/main");
+    mirror::mirrored_url(&url)
+        .into_iter()
+        .chain(std::iter::once(url))
+        .find_map(|url| {
+            let json = http_agent().get(&url).call().ok()?.into_string().ok()?;
+            parse_huggingface_tree(&json, filename)
+        })
+}
+
+/// SHA-256 of a GitHub release asset: the built-in digest when there is one, otherwise the
+/// digest GitHub records on upload. `None` when neither knows the asset.
+pub fn github_release_sha256(repo: &str, tag: &str, asset: &str) -> Option<String> {
+    if let Some(sha256) = builtin_sha256(BUILTIN_SHA256, repo, asset) {
+        return Some(sha256);
+    }
+    let url = format!("https://api.github.com/repos/{repo}/releases/tags/{tag}");
+    let json = http_agent().get(&url).call().ok()?.into_string().ok()?;
+    parse_github_release(&json, asset)
+}
+
+fn parse_huggingface_tree(json: &str, filename: &str) -> Option<String> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(json).ok()?;
+    entries
+        .iter()
+        .find(|entry| entry.get("path").and_then(|path| path.as_str()) == Some(filename))?
+        .get("lfs")?
+        .get("oid")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn parse_github_release(json: &str, asset: &str) -> Option<String> {
+    let release: serde_json::Value = serde_json::from_str(json).ok()?;
+    release
+        .get("assets")?
+        .as_array()?
+        .iter()
+        .find(|entry| entry.get("name").and_then(|name| name.as_str()) == Some(asset))?
+        .get("digest")?
+        .as_str()?
+        .strip_prefix("sha256:")
+        .map(str::to_string)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn verify_sha256_accepts_match_and_rejects_mismatch() {
+        let path = std::env::temp_dir().join(format!("openwispr-sha-{}", std::process::id()));
+        std::fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(sha256_file(&path, |_, _| {}).unwrap(), ABC_SHA256);
+        assert!(verify_sha256(&path, &ABC_SHA256.to_uppercase(), |_, _| {}).is_ok());
+        assert!(verify_sha256(&path, &"0".repeat(64), |_, _| {}).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn builtin_digests_are_found_by_repo_and_file() {
+        let table = [("ggerganov/whisper.cpp", "ggml-base.bin", ABC_SHA256)];
+        assert_eq!(
+            builtin_sha256(&table, "ggerganov/whisper.cpp", "ggml-base.bin").as_deref(),
+            Some(ABC_SHA256)
+        );
+        assert_eq!(builtin_sha256(&table, "other/repo", "ggml-base.bin"), None);
+        assert_eq!(
+            builtin_sha256(&table, "ggerganov/whisper.cpp", "ggml-tiny.bin"),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_digests_from_hosting_metadata() {
+        let tree = r#"[
+            { "type": "file", "path": "README.md", "oid": "1234" },
+            { "type": "file", "path": "ggml-base.en.bin", "oid": "5678",
+              "lfs": { "oid": "abcd", "size": 147964211 } }
+        ]"#;
+        assert_eq!(
+            parse_huggingface_tree(tree, "ggml-base.en.bin").as_deref(),
+            Some("abcd")
+        );
+        assert_eq!(parse_huggingface_tree(tree, "README.md"), None);
+
+        let release = r#"{ "assets": [
+            { "name": "model.tar.bz2", "digest": "sha256:ef01" },
+            { "name": "old.tar.bz2", "digest": null }
+        ] }"#;
+        assert_eq!(
+            parse_github_release(release, "model.tar.bz2").as_deref(),
+            Some("ef01")
+        );
+        assert_eq!(parse_github_release(release, "old.tar.bz2"), None);
+    }
+}
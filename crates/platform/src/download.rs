@@ -0,0 +1,674 @@
+//! Shared HTTP download loop used by the STT and LLM model downloaders.
+//!
+//! Data is streamed into `<dest>.download` and renamed into place only once the transfer
+//! (and optional checksum hook) succeeds, so a crashed or interrupted download is resumed
+//! with a `Range` request on the next attempt instead of starting from zero.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+use crate::checksum::verify_sha256;
+use crate::mirror;
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("failed to download {url}: {message}")]
+    Request { url: String, message: String },
+
+    #[error("{url} not found")]
+    NotFound { url: String },
+
+    #[error("I/O error on {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("checksum mismatch for {}: {message}", path.display())]
+    Checksum { path: PathBuf, message: String },
+
+    #[error("download cancelled")]
+    Cancelled,
+}
+
+pub type Result<T> = std::result::Result<T, DownloadError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+}
+
+impl DownloadProgress {
+    pub fn percent(&self) -> Option<f32> {
+        self.total_bytes
+            .map(|total| ((self.downloaded_bytes as f32 / total as f32) * 100.0).min(100.0))
+    }
+}
+
+/// One step of a model download as reported to the UI, shared by the STT and LLM
+/// downloaders. `stage` is e.g. `download`, `retry`, `verify` or `ready`; `done` marks the
+/// last event, with `error` set when it failed.
+#[derive(Debug, Clone)]
+pub struct ModelDownloadProgress {
+    pub model_name: String,
+    pub stage: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub percent: Option<f32>,
+    pub done: bool,
+    pub error: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Verifies the completed file before it is moved into place; `Err` carries the reason.
+pub type ChecksumHook<'a> = &'a dyn Fn(&Path) -> std::result::Result<(), String>;
+
+/// Called before each retry with the retry number (from 1), the retry budget and the failure.
+pub type RetryHook<'a> = &'a dyn Fn(u32, u32, &DownloadError);
+
+/// Polled between reads; `true` stops the download, keeping the partial file for a resume.
+pub type CancelHook<'a> = &'a dyn Fn() -> bool;
+
+/// First retry delay; each further retry doubles it.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// How long to wait for a TCP/TLS connection before the attempt fails.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long a single read may stall; a dead connection fails the attempt instead of
+/// hanging the download forever, and the retry resumes from the partial file.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// HTTP agent with connect and read timeouts, used for every request this crate makes.
+pub(crate) fn http_agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .timeout_read(READ_TIMEOUT)
+        .build()
+}
+
+pub struct FetchOptions<'a> {
+    /// Extra attempts after a transport or stream failure. Each retry resumes the partial file.
+    pub retries: u32,
+    /// Minimum number of new bytes between progress callbacks.
+    pub progress_interval_bytes: u64,
+    pub verify: Option<ChecksumHook<'a>>,
+    pub on_retry: Option<RetryHook<'a>>,
+    pub cancelled: Option<CancelHook<'a>>,
+}
+
+impl Default for FetchOptions<'_> {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            progress_interval_bytes: 256 * 1024,
+            verify: None,
+            on_retry: None,
+            cancelled: None,
+        }
+    }
+}
+
+/// Downloads `url` to `dest` with the default [`FetchOptions`]. Returns the file size.
+pub fn fetch_to_file(
+    url: &str,
+    dest: &Path,
+    on_progress: impl FnMut(DownloadProgress),
+) -> Result<u64> {
+    fetch_to_file_with(url, dest, &FetchOptions::default(), on_progress)
+}
+
+/// Like [`fetch_to_file`]. HuggingFace URLs go through the configured
+/// [mirror](crate::mirror) first, falling back to huggingface.co when the mirror lacks the file.
+pub fn fetch_to_file_with(
+    url: &str,
+    dest: &Path,
+    options: &FetchOptions<'_>,
+    mut on_progress: impl FnMut(DownloadProgress),
+) -> Result<u64> {
+    let partial = partial_path(dest);
+    let downloaded_bytes = match mirror::mirrored_url(url) {
+        Some(mirrored) => {
+            match fetch_with_retries(&mirrored, &partial, options, &mut on_progress) {
+                Err(DownloadError::NotFound { .. }) => {
+                    tracing::warn!("{mirrored} is missing on the mirror, falling back to {url}");
+                    fetch_with_retries(url, &partial, options, &mut on_progress)?
+                }
+                result => result?,
+            }
+        }
+        None => fetch_with_retries(url, &partial, options, &mut on_progress)?,
+    };
+
+    if let Some(verify) = options.verify {
+        if let Err(message) = verify(&partial) {
+            // A corrupt partial must not be resumed on the next try
+            let _ = fs::remove_file(&partial);
+            return Err(DownloadError::Checksum {
+                path: dest.to_path_buf(),
+                message,
+            });
+        }
+    }
+
+    fs::rename(&partial, dest).map_err(|source| DownloadError::Io {
+        path: dest.to_path_buf(),
+        source,
+    })?;
+    Ok(downloaded_bytes)
+}
+
+/// Bumped by [`cancel_model_downloads`]; each model download remembers the value it started at.
+static CANCEL_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Stops every [`fetch_model_file`] in flight with [`DownloadError::Cancelled`]. Their
+/// partial files are kept, so downloading the model again resumes; downloads started
+/// afterwards are unaffected.
+pub fn cancel_model_downloads() {
+    CANCEL_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Downloads one model file for `model_name` with [`fetch_to_file_with`], verifying it
+/// against `expected_sha256` when one is published. Reports `download` (tagged with
+/// `message`), `retry` and `verify` progress to `report`; callers report the final outcome.
+/// Servers that omit `Content-Length` get their percentages from `estimated_bytes`.
+/// [`cancel_model_downloads`] stops it.
+pub fn fetch_model_file(
+    model_name: &str,
+    url: &str,
+    dest: &Path,
+    expected_sha256: Option<&str>,
+    estimated_bytes: Option<u64>,
+    message: &str,
+    report: &dyn Fn(ModelDownloadProgress),
+) -> Result<u64> {
+    let event = |stage: &str, downloaded_bytes: u64, total_bytes: Option<u64>, message: String| {
+        ModelDownloadProgress {
+            model_name: model_name.to_string(),
+            stage: stage.to_string(),
+            downloaded_bytes,
+            total_bytes,
+            percent: total_bytes.map(|total| percent_of(downloaded_bytes, total)),
+            done: false,
+            error: None,
+            message: Some(message),
+        }
+    };
+    let verify = |path: &Path| -> std::result::Result<(), String> {
+        let Some(expected) = expected_sha256 else {
+            tracing::warn!("no published sha256 for {model_name}; skipping checksum verification");
+            return Ok(());
+        };
+        let mut last_percent = None;
+        verify_sha256(path, expected, |hashed, total| {
+            let progress = event(
+                "verify",
+                hashed,
+                Some(total),
+                "Verifying model checksum".into(),
+            );
+            let percent = progress.percent.map(f32::floor);
+            if percent != last_percent {
+                last_percent = percent;
+                report(progress);
+            }
+        })
+    };
+    let on_retry = |retry: u32, retries: u32, _: &DownloadError| {
+        report(event(
+            "retry",
+            0,
+            None,
+            format!("Retrying ({retry}/{retries})…"),
+        ));
+    };
+    let generation = CANCEL_GENERATION.load(Ordering::SeqCst);
+    let cancelled = || CANCEL_GENERATION.load(Ordering::SeqCst) != generation;
+    let options = FetchOptions {
+        verify: Some(&verify),
+        on_retry: Some(&on_retry),
+        cancelled: Some(&cancelled),
+        ..Default::default()
+    };
+    fetch_to_file_with(url, dest, &options, |progress| {
+        report(event(
+            "download",
+            progress.downloaded_bytes,
+            progress.total_bytes.or(estimated_bytes),
+            message.to_string(),
+        ));
+    })
+}
+
+/// `done` as a percentage of `total`, capped at 100; an empty total counts as complete.
+fn percent_of(done: u64, total: u64) -> f32 {
+    if total == 0 {
+        100.0
+    } else {
+        (done as f32 / total as f32 * 100.0).min(100.0)
+    }
+}
+
+fn fetch_with_retries(
+    url: &str,
+    partial: &Path,
+    options: &FetchOptions<'_>,
+    on_progress: &mut impl FnMut(DownloadProgress),
+) -> Result<u64> {
+    let mut attempt = 0;
+    loop {
+        match fetch_attempt(url, partial, options, on_progress) {
+            Ok(bytes) => return Ok(bytes),
+            Err(Attempt::Retryable(err)) if attempt < options.retries => {
+                if options.cancelled.is_some_and(|cancelled| cancelled()) {
+                    return Err(DownloadError::Cancelled);
+                }
+                attempt += 1;
+                tracing::warn!("download attempt {attempt} for {url} failed, retrying: {err}");
+                if let Some(on_retry) = options.on_retry {
+                    on_retry(attempt, options.retries, &err);
+                }
+                std::thread::sleep(retry_delay(attempt));
+            }
+            Err(Attempt::Retryable(err)) | Err(Attempt::Fatal(err)) => return Err(err),
+        }
+    }
+}
+
+/// `<dest>.download`, where in-flight bytes live until the download completes.
+pub fn partial_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".download");
+    dest.with_file_name(name)
+}
+
+/// Removes `*.download` files directly inside `dir` that have not been written to for
+/// `older_than`, so downloads abandoned long ago stop holding disk space while a recent one
+/// can still resume. Returns the removed paths; a missing `dir` sweeps nothing.
+pub fn sweep_partial_downloads(dir: &Path, older_than: Duration) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "download"))
+        .filter(|path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= older_than)
+        })
+        .filter(|path| match fs::remove_file(path) {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::warn!("failed to remove stale download {}: {err}", path.display());
+                false
+            }
+        })
+        .collect()
+}
+
+/// Exponential backoff plus up to half as much again of jitter, so downloads that failed
+/// together do not retry in lockstep.
+fn retry_delay(attempt: u32) -> Duration {
+    let base = RETRY_BASE_DELAY * 2_u32.pow(attempt.saturating_sub(1).min(6));
+    let jitter = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| f64::from(now.subsec_nanos() % 1000) / 2000.0)
+        .unwrap_or(0.0);
+    base + base.mul_f64(jitter)
+}
+
+enum Attempt {
+    Retryable(DownloadError),
+    Fatal(DownloadError),
+}
+
+fn fetch_attempt(
+    url: &str,
+    partial: &Path,
+    options: &FetchOptions<'_>,
+    on_progress: &mut impl FnMut(DownloadProgress),
+) -> std::result::Result<u64, Attempt> {
+    let io_error = |source| {
+        Attempt::Fatal(DownloadError::Io {
+            path: partial.to_path_buf(),
+            source,
+        })
+    };
+    let request_error = |message: String| DownloadError::Request {
+        url: url.to_string(),
+        message,
+    };
+
+    let resume_from = fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+    let mut request = http_agent().get(url);
+    if resume_from > 0 {
+        request = request.set("Range", &format!("bytes={resume_from}-"));
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(416, response)) if resume_from > 0 => {
+            // Nothing left to fetch: the previous run finished the transfer but never
+            // got to verify and rename the partial
+            if content_range_total(&response) == Some(resume_from) {
+                on_progress(DownloadProgress {
+                    downloaded_bytes: resume_from,
+                    total_bytes: Some(resume_from),
+                });
+                return Ok(resume_from);
+            }
+            // Otherwise the partial is stale (e.g. the file changed upstream), so start over
+            let _ = fs::remove_file(partial);
+            return Err(Attempt::Retryable(request_error(
+                "server rejected resume range".to_string(),
+            )));
+        }
+        Err(ureq::Error::Status(404, _)) => {
+            return Err(Attempt::Fatal(DownloadError::NotFound {
+                url: url.to_string(),
+            }));
+        }
+        Err(ureq::Error::Status(code, _)) if code < 500 => {
+            return Err(Attempt::Fatal(request_error(format!("HTTP status {code}"))));
+        }
+        Err(err) => return Err(Attempt::Retryable(request_error(err.to_string()))),
+    };
+
+    // A server that ignores `Range` answers 200 with the whole file, which overwrites the
+    // partial from the start
+    let resumed = resume_from > 0 && response.status() == 206;
+    let offset = if resumed { resume_from } else { 0 };
+    let total_bytes = response
+        .header("Content-Length")
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|v| *v > 0)
+        .map(|len| len + offset);
+
+    let file = if resumed {
+        OpenOptions::new().append(true).open(partial)
+    } else {
+        File::create(partial)
+    }
+    .map_err(io_error)?;
+    let mut writer = BufWriter::new(file);
+
+    let mut downloaded_bytes = offset;
+    let mut last_emitted = downloaded_bytes;
+    on_progress(DownloadProgress {
+        downloaded_bytes,
+        total_bytes,
+    });
+
+    let mut reader = response.into_reader();
+    let mut buffer = [0_u8; 64 * 1024];
+    loop {
+        if options.cancelled.is_some_and(|cancelled| cancelled()) {
+            let _ = writer.flush();
+            return Err(Attempt::Fatal(DownloadError::Cancelled));
+        }
+        let n = match reader.read(&mut buffer) {
+            Ok(n) => n,
+            Err(err) => {
+                // Keep what we have so the retry resumes from here
+                let _ = writer.flush();
+                return Err(Attempt::Retryable(request_error(format!(
+                    "stream interrupted after {downloaded_bytes} bytes: {err}"
+                ))));
+            }
+        };
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..n]).map_err(io_error)?;
+        downloaded_bytes += n as u64;
+
+        if downloaded_bytes.saturating_sub(last_emitted) >= options.progress_interval_bytes
+            || total_bytes.is_some_and(|total| downloaded_bytes >= total)
+        {
+            on_progress(DownloadProgress {
+                downloaded_bytes,
+                total_bytes,
+            });
+            last_emitted = downloaded_bytes;
+        }
+    }
+    writer.flush().map_err(io_error)?;
+
+    if total_bytes.is_some_and(|total| downloaded_bytes < total) {
+        return Err(Attempt::Retryable(request_error(format!(
+            "connection closed after {downloaded_bytes} of {} bytes",
+            total_bytes.unwrap_or_default()
+        ))));
+    }
+    Ok(downloaded_bytes)
+}
+
+/// Full resource size from a `Content-Range: bytes */<size>` header.
+fn content_range_total(response: &ureq::Response) -> Option<u64> {
+    response
+        .header("Content-Range")?
+        .rsplit('/')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// Serves `body` once. With `honour_range`, a `Range: bytes=N-` header gets a 206 (or a
+    /// 416 past the end); otherwise the whole body is sent with a 200.
+    fn serve_once(body: &'static [u8], honour_range: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut start = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(range) = line.to_lowercase().strip_prefix("range: bytes=") {
+                    start = range.trim().trim_end_matches('-').parse().unwrap();
+                }
+            }
+            if !honour_range {
+                start = 0;
+            }
+            if start >= body.len() && start > 0 {
+                write!(
+                    stream,
+                    "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                return;
+            }
+            let status = if start > 0 { "206 Partial Content" } else { "200 OK" };
+            let chunk = &body[start..];
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                chunk.len()
+            )
+            .unwrap();
+            stream.write_all(chunk).unwrap();
+        });
+        format!("http://{addr}/model.bin")
+    }
+
+    fn temp_dest(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("openwispr-download-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn retry_delay_doubles_with_bounded_jitter() {
+        for (attempt, base_ms) in [(1, 500), (2, 1000), (3, 2000)] {
+            let delay = retry_delay(attempt).as_millis();
+            assert!(
+                (base_ms..=base_ms * 3 / 2).contains(&delay),
+                "{attempt}: {delay}"
+            );
+        }
+    }
+
+    #[test]
+    fn partial_path_appends_suffix() {
+        assert_eq!(
+            partial_path(Path::new("/models/ggml-base.bin")),
+            PathBuf::from("/models/ggml-base.bin.download")
+        );
+    }
+
+    #[test]
+    fn sweep_removes_only_stale_partials() {
+        let dir = temp_dest("sweep").with_extension("d");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let partial = partial_path(&dir.join("ggml-base.bin"));
+        fs::write(&partial, b"half").unwrap();
+        fs::write(dir.join("ggml-tiny.bin"), b"model").unwrap();
+
+        assert!(sweep_partial_downloads(&dir, Duration::from_secs(3600)).is_empty());
+        assert_eq!(
+            sweep_partial_downloads(&dir, Duration::ZERO),
+            vec![partial.clone()]
+        );
+        assert!(!partial.exists());
+        assert!(dir.join("ggml-tiny.bin").exists());
+        assert!(sweep_partial_downloads(&dir.join("missing"), Duration::ZERO).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn fetch_resumes_from_partial_file() {
+        let body: &'static [u8] = b"0123456789abcdef";
+        let dest = temp_dest("resume.bin");
+        fs::write(partial_path(&dest), &body[..6]).unwrap();
+
+        let mut first = None;
+        let mut last = None;
+        let bytes = fetch_to_file(&serve_once(body, true), &dest, |p| {
+            first.get_or_insert(p);
+            last = Some(p);
+        })
+        .unwrap();
+
+        assert_eq!(bytes, body.len() as u64);
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        assert!(!partial_path(&dest).exists());
+        assert_eq!(first.map(|p| p.downloaded_bytes), Some(6));
+        assert_eq!(last.and_then(|p| p.percent()), Some(100.0));
+        let _ = fs::remove_file(&dest);
+    }
+
+    #[test]
+    fn cancelled_fetch_keeps_the_partial_file() {
+        let body: &'static [u8] = b"0123456789abcdef";
+        let dest = temp_dest("cancel.bin");
+        let _ = fs::remove_file(partial_path(&dest));
+        let options = FetchOptions {
+            cancelled: Some(&|| true),
+            ..Default::default()
+        };
+
+        let err = fetch_to_file_with(&serve_once(body, true), &dest, &options, |_| {}).unwrap_err();
+
+        assert!(matches!(err, DownloadError::Cancelled), "{err}");
+        assert!(!dest.exists());
+        assert!(partial_path(&dest).exists());
+        let _ = fs::remove_file(partial_path(&dest));
+    }
+
+    #[test]
+    fn fetch_restarts_when_server_ignores_range() {
+        let body: &'static [u8] = b"0123456789abcdef";
+        let dest = temp_dest("ignored-range.bin");
+        fs::write(partial_path(&dest), b"stale!").unwrap();
+
+        let bytes = fetch_to_file(&serve_once(body, false), &dest, |_| {}).unwrap();
+
+        assert_eq!(bytes, body.len() as u64);
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        let _ = fs::remove_file(&dest);
+    }
+
+    #[test]
+    fn fetch_finishes_already_complete_partial() {
+        let body: &'static [u8] = b"0123456789abcdef";
+        let dest = temp_dest("complete.bin");
+        fs::write(partial_path(&dest), body).unwrap();
+
+        let bytes = fetch_to_file(&serve_once(body, true), &dest, |_| {}).unwrap();
+
+        assert_eq!(bytes, body.len() as u64);
+        assert_eq!(fs::read(&dest).unwrap(), body);
+        assert!(!partial_path(&dest).exists());
+        let _ = fs::remove_file(&dest);
+    }
+
+    #[test]
+    fn model_fetch_reports_download_and_verify_progress() {
+        let body: &'static [u8] = b"payload";
+        let dest = temp_dest("model-fetch.bin");
+        let events = std::cell::RefCell::new(Vec::new());
+        let record = |progress: ModelDownloadProgress| events.borrow_mut().push(progress);
+
+        let bytes = fetch_model_file(
+            "tiny",
+            &serve_once(body, true),
+            &dest,
+            Some("239f59ed55e737c77147cf55ad0c1b030b6d7ee748a7426952f9b852d5a935e5"),
+            None,
+            "Downloading model",
+            &record,
+        )
+        .unwrap();
+
+        assert_eq!(bytes, body.len() as u64);
+        let events = events.into_inner();
+        assert!(events.iter().any(|e| e.stage == "download"));
+        let last = events.last().unwrap();
+        assert_eq!((last.stage.as_str(), last.percent), ("verify", Some(100.0)));
+        assert!(events.iter().all(|e| !e.done && e.model_name == "tiny"));
+        let _ = fs::remove_file(&dest);
+    }
+
+    #[test]
+    fn failed_checksum_discards_download() {
+        let dest = temp_dest("checksum.bin");
+        let reject = |_: &Path| -> std::result::Result<(), String> {
+            Err("digest differs".to_string())
+        };
+        let options = FetchOptions {
+            verify: Some(&reject),
+            ..Default::default()
+        };
+
+        let err =
+            fetch_to_file_with(&serve_once(b"payload", true), &dest, &options, |_| {}).unwrap_err();
+
+        assert!(matches!(err, DownloadError::Checksum { .. }));
+        assert!(!dest.exists());
+        assert!(!partial_path(&dest).exists());
+    }
+}
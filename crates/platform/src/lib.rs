@@ -1,5 +1,11 @@
 // Platform abstraction crate. Do NOT put OS checks here — keep platform specifics in `platform-*` crates.
 
+pub mod catalog;
+pub mod checksum;
+pub mod download;
+pub mod mirror;
+pub mod relocate;
+
 pub trait PlatformImpl {
     fn name() -> &'static str;
 }
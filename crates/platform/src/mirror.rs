@@ -0,0 +1,100 @@
+//! Optional HuggingFace mirror (e.g. `hf-mirror.com`) for regions where huggingface.co is
+//! blocked or slow. Mirrors serve the same `<repo>/resolve/main/<file>` layout, so only the
+//! host changes; files missing on the mirror are fetched from the canonical host instead.
+
+use std::sync::{OnceLock, RwLock};
+
+const HUGGINGFACE_HOST: &str = "huggingface.co";
+
+fn mirror_slot() -> &'static RwLock<Option<String>> {
+    static SLOT: OnceLock<RwLock<Option<String>>> = OnceLock::new();
+    SLOT.get_or_init(|| RwLock::new(None))
+}
+
+/// Routes HuggingFace downloads through `mirror` (a host, optionally with scheme).
+/// `OPENWISPR_HF_MIRROR` still takes precedence.
+pub fn set_huggingface_mirror(mirror: Option<String>) {
+    if let Ok(mut slot) = mirror_slot().write() {
+        *slot = mirror;
+    }
+}
+
+/// Mirror base URL from `OPENWISPR_HF_MIRROR` or [`set_huggingface_mirror`], if any.
+pub fn huggingface_mirror() -> Option<String> {
+    let configured = std::env::var("OPENWISPR_HF_MIRROR")
+        .ok()
+        .filter(|mirror| !mirror.trim().is_empty())
+        .or_else(|| mirror_slot().read().ok().and_then(|slot| slot.clone()))?;
+    mirror_base(&configured)
+}
+
+/// `url` with its huggingface.co host replaced by the configured mirror. `None` when no
+/// mirror is set or `url` is not a HuggingFace URL.
+pub fn mirrored_url(url: &str) -> Option<String> {
+    rewrite_host(url, &huggingface_mirror()?)
+}
+
+/// Normalizes `hf-mirror.com`, `https://hf-mirror.com/` etc. to `https://hf-mirror.com`.
+fn mirror_base(mirror: &str) -> Option<String> {
+    let mirror = mirror.trim().trim_end_matches('/');
+    let host = mirror
+        .strip_prefix("https://")
+        .or_else(|| mirror.strip_prefix("http://"))
+        .unwrap_or(mirror);
+    if host.is_empty() || host.contains('/') || host == HUGGINGFACE_HOST {
+        return None;
+    }
+    let scheme = if mirror.starts_with("http://") {
+        "http"
+    } else {
+        "https"
+    };
+    Some(format!("{scheme}://{host}"))
+}
+
+fn rewrite_host(url: &str, mirror_base: &str) -> Option<String> {
+    let path = url.strip_prefix(&format!("https://{HUGGINGFACE_HOST}/"))?;
+    Some(format!("{mirror_base}/{path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_mirror_hosts() {
+        assert_eq!(
+            mirror_base("hf-mirror.com").as_deref(),
+            Some("https://hf-mirror.com")
+        );
+        assert_eq!(
+            mirror_base(" https://hf-mirror.com/ ").as_deref(),
+            Some("https://hf-mirror.com")
+        );
+        assert_eq!(
+            mirror_base("http://10.0.0.2:8080").as_deref(),
+            Some("http://10.0.0.2:8080")
+        );
+        assert_eq!(mirror_base("huggingface.co"), None);
+        assert_eq!(mirror_base("https://example.com/hf"), None);
+    }
+
+    #[test]
+    fn rewrites_only_huggingface_urls() {
+        assert_eq!(
+            rewrite_host(
+                "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
+                "https://hf-mirror.com"
+            )
+            .as_deref(),
+            Some("https://hf-mirror.com/ggerganov/whisper.cpp/resolve/main/ggml-base.bin")
+        );
+        assert_eq!(
+            rewrite_host(
+                "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/x.tar.bz2",
+                "https://hf-mirror.com"
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Moving a model cache to another directory, possibly on another drive.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How one entry reached its destination, so a failed move can be undone.
+enum Moved {
+    Renamed,
+    Copied,
+}
+
+/// Entries of one directory that now also exist in another. Nothing has been deleted from
+/// the source yet except entries moved by rename, so the move can still be undone or
+/// completed once the new location has been recorded.
+pub struct Relocation {
+    src: PathBuf,
+    moved: Vec<(PathBuf, PathBuf, Moved)>,
+}
+
+impl Relocation {
+    /// Puts renamed entries back and deletes the copies.
+    pub fn undo(self) {
+        for (from, to, how) in self.moved.into_iter().rev() {
+            match how {
+                Moved::Renamed => {
+                    let _ = fs::rename(&to, &from);
+                }
+                Moved::Copied => {
+                    let _ = remove_tree(&to);
+                }
+            }
+        }
+    }
+
+    /// Deletes the source of every copied entry, then `src` itself if it is empty. Returns
+    /// the sources that could not be deleted: the new copies are complete, so these are
+    /// only old copies left behind.
+    pub fn remove_sources(self) -> Vec<PathBuf> {
+        let mut left_behind = Vec::new();
+        for (from, _, how) in self.moved {
+            if matches!(how, Moved::Copied) && remove_tree(&from).is_err() {
+                left_behind.push(from);
+            }
+        }
+        let _ = fs::remove_dir(&self.src);
+        left_behind
+    }
+}
+
+/// Brings every entry of `src` into `dest`, creating `dest` if needed. Entries are renamed
+/// when possible and copied otherwise (`fs::rename` fails across filesystems); copies are
+/// checked against the source. On error everything already brought over is put back.
+/// Existing entries in `dest` are never overwritten.
+pub fn copy_dir_contents(src: &Path, dest: &Path) -> io::Result<Relocation> {
+    let mut relocation = Relocation {
+        src: src.to_path_buf(),
+        moved: Vec::new(),
+    };
+    if !src.exists() || src == dest {
+        return Ok(relocation);
+    }
+    if dest.starts_with(src) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is inside {}", dest.display(), src.display()),
+        ));
+    }
+    fs::create_dir_all(dest)?;
+
+    let moved = &mut relocation.moved;
+    let result = fs::read_dir(src).and_then(|entries| {
+        for entry in entries {
+            let from = entry?.path();
+            let Some(name) = from.file_name() else {
+                continue;
+            };
+            let to = dest.join(name);
+            if to.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", to.display()),
+                ));
+            }
+            if fs::rename(&from, &to).is_ok() {
+                moved.push((from, to, Moved::Renamed));
+                continue;
+            }
+            let copied = copy_tree(&from, &to).and_then(|_| verify_tree(&from, &to));
+            if let Err(err) = copied {
+                let _ = remove_tree(&to);
+                return Err(err);
+            }
+            moved.push((from, to, Moved::Copied));
+        }
+        Ok(())
+    });
+
+    if let Err(err) = result {
+        relocation.undo();
+        return Err(err);
+    }
+    Ok(relocation)
+}
+
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+/// Every file under `from` exists under `to` with the same size.
+fn verify_tree(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_dir() {
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            verify_tree(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+    let expected = fs::metadata(from)?.len();
+    let actual = fs::metadata(to)?.len();
+    if expected != actual {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "copy of {} has {actual} bytes, expected {expected}",
+                from.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn remove_tree(path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else if path.exists() {
+        fs::remove_file(path)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "openwispr-relocate-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn moves_nested_entries_and_removes_source() {
+        let root = scratch("move");
+        let src = root.join("old");
+        let dest = root.join("new");
+        fs::create_dir_all(src.join("sherpa-onnx")).unwrap();
+        fs::write(src.join("ggml-base.en.bin"), b"model").unwrap();
+        fs::write(src.join("sherpa-onnx").join("tokens.txt"), b"tokens").unwrap();
+
+        let relocation = copy_dir_contents(&src, &dest).unwrap();
+        assert!(relocation.remove_sources().is_empty());
+
+        assert!(!src.exists());
+        assert_eq!(fs::read(dest.join("ggml-base.en.bin")).unwrap(), b"model");
+        assert_eq!(
+            fs::read(dest.join("sherpa-onnx").join("tokens.txt")).unwrap(),
+            b"tokens"
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn undo_puts_entries_back() {
+        let root = scratch("undo");
+        let src = root.join("old");
+        let dest = root.join("new");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.bin"), b"a").unwrap();
+
+        copy_dir_contents(&src, &dest).unwrap().undo();
+
+        assert_eq!(fs::read(src.join("a.bin")).unwrap(), b"a");
+        assert!(!dest.join("a.bin").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn conflict_restores_already_moved_entries() {
+        let root = scratch("conflict");
+        let src = root.join("old");
+        let dest = root.join("new");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(src.join("a.bin"), b"a").unwrap();
+        fs::write(src.join("b.bin"), b"b").unwrap();
+        fs::write(dest.join("b.bin"), b"existing").unwrap();
+
+        assert!(copy_dir_contents(&src, &dest).is_err());
+
+        assert_eq!(fs::read(src.join("a.bin")).unwrap(), b"a");
+        assert_eq!(fs::read(src.join("b.bin")).unwrap(), b"b");
+        assert_eq!(fs::read(dest.join("b.bin")).unwrap(), b"existing");
+        assert!(!dest.join("a.bin").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn rejects_destination_inside_source() {
+        let root = scratch("nested");
+        fs::create_dir_all(&root).unwrap();
+        assert!(copy_dir_contents(&root, &root.join("inner")).is_err());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
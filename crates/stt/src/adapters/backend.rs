@@ -1,25 +1,75 @@
 use crate::{
-    emit_model_download_progress, AudioFormat, ModelDownloadProgress, Result, SttConfig, SttError,
-    TranscriptSegment, Transcription, TranscriptionTask,
+    current_progress_scope, emit_model_download_progress, verbose_logs_enabled,
+    with_download_scope, AudioFormat, ComputeBackend, DecodeScores, ModelDescriptor,
+    ModelDownloadProgress, NormalizationTuning, Result, SttConfig, SttError, SttStream,
+    TranscriptSegment, Transcription, TranscriptionTask, WhisperParam, Word,
 };
-use std::fs::File;
-use std::io::{BufWriter, Read, Write};
+use std::collections::HashMap;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use platform::catalog::{
+    file_has_magic, load_custom_models, CatalogModel, StagedImport, RUNTIME_WHISPER,
+};
+use platform::checksum::huggingface_sha256;
+use platform::download::{fetch_model_file, DownloadError};
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
+
+use super::stream::{ChunkDecoder, WindowedStream};
+use crate::vad::{contains_speech, signal_stats, trim_silence};
 use whisper_rs::{
     get_lang_str, install_logging_hooks, FullParams, SamplingStrategy, WhisperContext,
-    WhisperContextParameters,
+    WhisperContextParameters, WhisperState,
 };
 
 pub(crate) const TARGET_SAMPLE_RATE: u32 = 16_000;
 
+/// Built-in ggml models with their approximate download size in MB.
+const BUILTIN_MODELS: &[(&str, u64)] = &[
+    ("tiny", 75),
+    ("tiny.en", 75),
+    ("base", 142),
+    ("base.en", 142),
+    ("small", 466),
+    ("small.en", 466),
+    ("medium", 1_500),
+    ("medium.en", 1_500),
+    ("large-v3-turbo", 1_620),
+    ("large-v3", 3_100),
+];
+
+/// Best accuracy per second on current hardware; the default we point users at.
+const RECOMMENDED_MODEL: &str = "large-v3-turbo";
+
+/// Default GPU memory floor relative to the model file: weights plus KV cache and buffers.
+const GPU_MEMORY_HEADROOM_FACTOR: f64 = 1.5;
+
+/// Zero crossings of the resampling kernel kept on each side of an output sample.
+const RESAMPLE_ZERO_CROSSINGS: f64 = 16.0;
+/// Resampling cutoff as a fraction of the lower Nyquist frequency, leaving room for the
+/// filter's transition band so it is fully attenuated by Nyquist.
+const RESAMPLE_CUTOFF: f64 = 0.92;
+
+/// whisper.cpp rejects clips shorter than this; stream tails are padded up to it.
+const MIN_DECODE_SAMPLES: usize = TARGET_SAMPLE_RATE as usize;
+/// Tail of the committed stream transcript passed as the prompt for the next window.
+const STREAM_PROMPT_CHARS: usize = 200;
+
+/// GGML container magic (`0x67676d6c`) as it appears on disk.
+const GGML_MAGIC: [u8; 4] = *b"lmgg";
+
 #[derive(Default)]
 struct SharedState {
     config: Option<SttConfig>,
     model_path: Option<PathBuf>,
-    context: Option<Arc<WhisperContext>>,
+    context: Option<Arc<LoadedContext>>,
+    context_on_gpu: bool,
 }
 
 /// Shared whisper.cpp backend used by both macOS and Windows adapters.
@@ -28,14 +78,6 @@ pub(crate) struct SharedWhisperAdapter {
     state: Arc<RwLock<SharedState>>,
 }
 
-fn verbose_logs_enabled() -> bool {
-    std::env::var("OPENWISPR_VERBOSE_LOGS")
-        .ok()
-        .as_deref()
-        .map(|v| v == "1")
-        .unwrap_or(false)
-}
-
 impl SharedWhisperAdapter {
     pub(crate) fn new(runtime_name: &'static str) -> Self {
         Self {
@@ -50,15 +92,16 @@ impl SharedWhisperAdapter {
         install_logging_hooks();
 
         let runtime_name = self.runtime_name;
+        let scope = current_progress_scope();
         let model_path = tokio::task::spawn_blocking({
             let config = config.clone();
-            move || resolve_model_path(&config)
+            move || with_download_scope(scope, || resolve_model_path(&config))
         })
         .await
         .map_err(|e| SttError::ModelLoadError(format!("model path task failed: {e}")))??;
 
         let model_path_for_ctx = model_path.clone();
-        let (prefer_gpu, preferred_backend) = preferred_backend();
+        let (prefer_gpu, preferred_backend) = choose_backend(&config, &model_path)?;
         let context = tokio::task::spawn_blocking(move || {
             let model_path_str = model_path_for_ctx.to_str().ok_or_else(|| {
                 SttError::ModelLoadError(format!(
@@ -76,7 +119,7 @@ impl SharedWhisperAdapter {
                         preferred_backend,
                         prefer_gpu
                     );
-                    Ok(ctx)
+                    Ok((ctx, prefer_gpu))
                 }
                 Err(gpu_err) if prefer_gpu => {
                     warn!(
@@ -87,14 +130,16 @@ impl SharedWhisperAdapter {
 
                     let mut cpu_params = WhisperContextParameters::default();
                     cpu_params.use_gpu(false);
-                    WhisperContext::new_with_params(model_path_str, cpu_params).map_err(|cpu_err| {
-                        SttError::ModelLoadError(format!(
-                            "failed to load whisper model from {} with GPU ({}) and CPU fallback (cpu): {}",
-                            model_path_for_ctx.display(),
-                            preferred_backend,
-                            cpu_err
-                        ))
-                    })
+                    WhisperContext::new_with_params(model_path_str, cpu_params)
+                        .map(|ctx| (ctx, false))
+                        .map_err(|cpu_err| {
+                            SttError::ModelLoadError(format!(
+                                "failed to load whisper model from {} with GPU ({}) and CPU fallback (cpu): {}",
+                                model_path_for_ctx.display(),
+                                preferred_backend,
+                                cpu_err
+                            ))
+                        })
                 }
                 Err(err) => Err(SttError::ModelLoadError(format!(
                     "failed to load whisper model from {}: {err}",
@@ -105,10 +150,12 @@ impl SharedWhisperAdapter {
         .await
         .map_err(|e| SttError::ModelLoadError(format!("context task failed: {e}")))??;
 
+        let (context, context_on_gpu) = context;
         let mut state = self.state.write().await;
         state.config = Some(config);
         state.model_path = Some(model_path.clone());
-        state.context = Some(Arc::new(context));
+        state.context = Some(Arc::new(LoadedContext::new(context)));
+        state.context_on_gpu = context_on_gpu;
 
         info!(
             "{} initialized with model {}",
@@ -118,12 +165,18 @@ impl SharedWhisperAdapter {
         Ok(())
     }
 
+    /// Drops the whisper context and its pooled decode states.
+    pub(crate) async fn unload(&self) {
+        *self.state.write().await = SharedState::default();
+        info!("{} unloaded", self.runtime_name);
+    }
+
     pub(crate) async fn transcribe(
         &self,
         audio_data: &[f32],
         format: AudioFormat,
     ) -> Result<Transcription> {
-        let (config, context) = {
+        let (config, context, cpu_fallback_model) = {
             let state = self.state.read().await;
             let config = state
                 .config
@@ -132,10 +185,14 @@ impl SharedWhisperAdapter {
             let context = state.context.clone().ok_or_else(|| {
                 SttError::TranscriptionFailed("model context not initialized".into())
             })?;
-            (config, context)
+            let cpu_fallback_model = state
+                .model_path
+                .clone()
+                .filter(|_| state.context_on_gpu && config.cpu_fallback_on_empty);
+            (config, context, cpu_fallback_model)
         };
 
-        let prepared_audio = prepare_audio(audio_data, &format);
+        let prepared_audio = prepare_audio(audio_data, &format, &config);
         if prepared_audio.is_empty() {
             return Err(SttError::AudioError(
                 "no audio samples available after preprocessing".into(),
@@ -167,70 +224,260 @@ impl SharedWhisperAdapter {
             prepared_audio.len()
         );
 
-        let language_override = config.language.clone();
-        let task = config.task.clone();
         tokio::task::spawn_blocking(move || {
-            run_whisper_transcription(context, prepared_audio, language_override, task)
+            let mut transcription = run_whisper_transcription(
+                context,
+                &prepared_audio,
+                &config,
+                cpu_fallback_model.as_deref(),
+            )?;
+            // Score the mono 16 kHz signal whisper actually heard, not the raw capture
+            transcription.quality = estimate_quality(&prepared_audio, &transcription.text);
+            Ok(transcription)
         })
         .await
         .map_err(|e| SttError::TranscriptionFailed(format!("transcription task failed: {e}")))?
     }
 
+    /// One primary decode of silence. Compiles the GPU kernels and leaves a decode state in
+    /// the pool for the next transcription; skips the empty-result retries and CPU fallback
+    /// that silence would otherwise trigger.
+    pub(crate) async fn warm_up(&self) -> Result<()> {
+        let (config, context) = {
+            let state = self.state.read().await;
+            let config = state
+                .config
+                .clone()
+                .ok_or_else(|| SttError::TranscriptionFailed("adapter not initialized".into()))?;
+            let context = state.context.clone().ok_or_else(|| {
+                SttError::TranscriptionFailed("model context not initialized".into())
+            })?;
+            (config, context)
+        };
+
+        let started = Instant::now();
+        tokio::task::spawn_blocking(move || {
+            let language = decode_language(&config);
+            decode_once(
+                &context,
+                &vec![0.0; MIN_DECODE_SAMPLES],
+                Some(&language),
+                DecodeProfile::Primary,
+                &config,
+            )
+        })
+        .await
+        .map_err(|e| SttError::TranscriptionFailed(format!("warm-up task failed: {e}")))??;
+        info!(
+            "{} warmed up in {} ms",
+            self.runtime_name,
+            started.elapsed().as_millis()
+        );
+        Ok(())
+    }
+
+    /// Fetches the model file without creating a whisper context.
+    pub(crate) async fn download_only(&self, model_name: &str) -> Result<()> {
+        let config = SttConfig {
+            model_name: model_name.to_string(),
+            ..Default::default()
+        };
+        let scope = current_progress_scope();
+        tokio::task::spawn_blocking(move || {
+            with_download_scope(scope, || resolve_model_path(&config))
+        })
+        .await
+        .map_err(|e| SttError::ModelLoadError(format!("model download task failed: {e}")))??;
+        Ok(())
+    }
+
     pub(crate) async fn is_model_available(&self, model_name: &str) -> bool {
         if looks_like_model_path(model_name) {
             return Path::new(model_name).exists();
         }
 
         model_cache_dir()
-            .map(|dir| dir.join(model_filename(model_name)).exists())
+            .map(|dir| dir.join(cached_model_filename(model_name)).exists())
             .unwrap_or(false)
     }
 
-    pub(crate) fn available_models(&self) -> Vec<String> {
-        vec![
-            "tiny".to_string(),
-            "tiny.en".to_string(),
-            "base".to_string(),
-            "base.en".to_string(),
-            "small".to_string(),
-            "small.en".to_string(),
-            "medium".to_string(),
-            "medium.en".to_string(),
-            "large-v3-turbo".to_string(),
-            "large-v3".to_string(),
-        ]
+    /// Built-in whisper models followed by any added through `models.json`.
+    pub(crate) fn model_descriptors(&self) -> Vec<ModelDescriptor> {
+        let mut models: Vec<ModelDescriptor> = BUILTIN_MODELS
+            .iter()
+            .map(|(name, size_mb)| whisper_descriptor(name, *size_mb, None))
+            .collect();
+        for custom in custom_whisper_models() {
+            if !models.iter().any(|model| model.name == custom.name) {
+                models.push(whisper_descriptor(
+                    &custom.name,
+                    custom.size_mb,
+                    Some("Custom model".to_string()),
+                ));
+            }
+        }
+        models
     }
 
+    pub(crate) fn create_stream(&self, format: AudioFormat) -> Result<Box<dyn SttStream>> {
+        let state = self
+            .state
+            .try_read()
+            .map_err(|_| SttError::TranscriptionFailed("whisper adapter is busy loading".into()))?;
+        let config = state
+            .config
+            .clone()
+            .ok_or_else(|| SttError::TranscriptionFailed("adapter not initialized".into()))?;
+        let context = state.context.clone().ok_or_else(|| {
+            SttError::TranscriptionFailed("model context not initialized".into())
+        })?;
+        let language = decode_language(&config);
+        let normalization = config.normalization;
+        Ok(Box::new(WindowedStream::new(
+            WhisperDecoder {
+                context,
+                config,
+                language: language.clone(),
+            },
+            format,
+            Some(language),
+            normalization,
+        )))
+    }
 }
 
-fn preferred_backend() -> (bool, &'static str) {
-    #[cfg(target_os = "macos")]
+/// The GPU backend whisper is built with on this platform, if any.
+fn platform_gpu_backend() -> Option<ComputeBackend> {
+    if cfg!(target_os = "macos") && std::env::consts::ARCH == "aarch64" {
+        Some(ComputeBackend::Metal)
+    } else if cfg!(target_os = "windows") || (cfg!(target_os = "linux") && cfg!(feature = "vulkan"))
     {
-        let is_apple_silicon = std::env::consts::ARCH == "aarch64";
-        if is_apple_silicon {
-            return (true, "metal");
-        }
-        return (false, "cpu");
+        Some(ComputeBackend::Vulkan)
+    } else {
+        None
     }
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        return (true, "vulkan");
+fn preferred_backend(requested: ComputeBackend) -> Result<(bool, &'static str)> {
+    match requested {
+        ComputeBackend::Auto => Ok(match platform_gpu_backend() {
+            Some(gpu) => (true, gpu.as_str()),
+            None => (false, "cpu"),
+        }),
+        ComputeBackend::Cpu => Ok((false, "cpu")),
+        gpu if platform_gpu_backend() == Some(gpu) => Ok((true, gpu.as_str())),
+        _ => Err(SttError::UnsupportedPlatform),
     }
+}
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    {
-        (false, "cpu")
+/// [`preferred_backend`] for the configured (or `OPENWISPR_STT_BACKEND`) choice, downgraded
+/// to CPU up front when the GPU lacks the free memory to hold the model, instead of failing
+/// (or crawling) on the GPU and retrying.
+fn choose_backend(config: &SttConfig, model_path: &Path) -> Result<(bool, &'static str)> {
+    let requested = ComputeBackend::from_env().unwrap_or(config.compute_backend);
+    let (prefer_gpu, backend) = preferred_backend(requested).inspect_err(|_| {
+        warn!("{} is not available in this build", requested.as_str());
+    })?;
+    if !prefer_gpu {
+        return Ok((prefer_gpu, backend));
+    }
+
+    let model_bytes = std::fs::metadata(model_path).map(|m| m.len()).unwrap_or(0);
+    let floor = gpu_memory_floor_bytes(config.min_gpu_memory_mb, model_bytes);
+    match available_gpu_memory_bytes() {
+        Some(available) if available < floor => {
+            warn!(
+                "{} MB of GPU memory free, below the {} MB floor for {}; loading on CPU",
+                available / (1024 * 1024),
+                floor / (1024 * 1024),
+                backend
+            );
+            Ok((false, "cpu"))
+        }
+        Some(available) => {
+            debug!(
+                "{} MB of GPU memory free (floor {} MB); using {}",
+                available / (1024 * 1024),
+                floor / (1024 * 1024),
+                backend
+            );
+            Ok((prefer_gpu, backend))
+        }
+        None => {
+            debug!("could not query GPU memory; trying {}", backend);
+            Ok((prefer_gpu, backend))
+        }
     }
 }
 
+/// Config value, then `OPENWISPR_MIN_GPU_MEMORY_MB`, then a multiple of the model size.
+fn gpu_memory_floor_bytes(configured_mb: Option<u64>, model_bytes: u64) -> u64 {
+    configured_mb
+        .or_else(|| {
+            std::env::var("OPENWISPR_MIN_GPU_MEMORY_MB")
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+        })
+        .map(|mb| mb * 1024 * 1024)
+        .unwrap_or((model_bytes as f64 * GPU_MEMORY_HEADROOM_FACTOR) as u64)
+}
+
+/// Apple silicon GPUs share unified memory with the CPU, so free system memory is the budget.
+#[cfg(target_os = "macos")]
+fn available_gpu_memory_bytes() -> Option<u64> {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    Some(system.available_memory())
+}
+
+/// Largest remaining DXGI local-memory budget across hardware adapters.
+#[cfg(target_os = "windows")]
+fn available_gpu_memory_bytes() -> Option<u64> {
+    use windows::core::Interface;
+    use windows::Win32::Graphics::Dxgi::{
+        CreateDXGIFactory1, IDXGIAdapter3, IDXGIFactory1, DXGI_ADAPTER_FLAG_SOFTWARE,
+        DXGI_MEMORY_SEGMENT_GROUP_LOCAL, DXGI_QUERY_VIDEO_MEMORY_INFO,
+    };
+
+    let factory: IDXGIFactory1 = unsafe { CreateDXGIFactory1() }.ok()?;
+    let mut best: Option<u64> = None;
+    let mut index = 0;
+    while let Ok(adapter) = unsafe { factory.EnumAdapters1(index) } {
+        index += 1;
+        let Ok(desc) = (unsafe { adapter.GetDesc1() }) else {
+            continue;
+        };
+        if desc.Flags & DXGI_ADAPTER_FLAG_SOFTWARE.0 as u32 != 0 {
+            continue;
+        }
+        let Ok(adapter) = adapter.cast::<IDXGIAdapter3>() else {
+            continue;
+        };
+        let mut info = DXGI_QUERY_VIDEO_MEMORY_INFO::default();
+        if unsafe { adapter.QueryVideoMemoryInfo(0, DXGI_MEMORY_SEGMENT_GROUP_LOCAL, &mut info) }
+            .is_ok()
+        {
+            let free = info.Budget.saturating_sub(info.CurrentUsage);
+            best = Some(best.map_or(free, |current| current.max(free)));
+        }
+    }
+    best
+}
+
+/// Vulkan does not expose free device memory portably; let whisper try and fall back to CPU.
+#[cfg(target_os = "linux")]
+fn available_gpu_memory_bytes() -> Option<u64> {
+    None
+}
+
 fn run_whisper_transcription(
-    context: Arc<WhisperContext>,
-    audio_data: Vec<f32>,
-    language_override: Option<String>,
-    task: TranscriptionTask,
+    context: Arc<LoadedContext>,
+    audio_data: &[f32],
+    config: &SttConfig,
+    cpu_fallback_model: Option<&Path>,
 ) -> Result<Transcription> {
-    let requested_language = language_override
+    let requested_language = config
+        .language
         .as_deref()
         .map(str::trim)
         .filter(|lang| !lang.is_empty())
@@ -241,10 +488,10 @@ fn run_whisper_transcription(
 
     let primary_attempt = decode_once(
         &context,
-        &audio_data,
+        audio_data,
         preferred_language.as_deref(),
-        &task,
         DecodeProfile::Primary,
+        config,
     )?;
     println!(
         "[stt] primary decode chars={} segments={} lang={}",
@@ -260,13 +507,7 @@ fn run_whisper_transcription(
         if verbose_logs_enabled() {
             println!("[stt] primary decode empty, retrying with auto language detection");
         }
-        let auto_attempt = decode_once(
-            &context,
-            &audio_data,
-            None,
-            &task,
-            DecodeProfile::Primary,
-        )?;
+        let auto_attempt = decode_once(&context, audio_data, None, DecodeProfile::Primary, config)?;
         if verbose_logs_enabled() {
             println!(
                 "[stt] auto-language decode chars={} segments={}",
@@ -284,10 +525,10 @@ fn run_whisper_transcription(
     }
     let permissive_attempt = decode_once(
         &context,
-        &audio_data,
+        audio_data,
         preferred_language.as_deref(),
-        &task,
         DecodeProfile::PermissiveFallback,
+        config,
     )?;
     if verbose_logs_enabled() {
         println!(
@@ -298,31 +539,359 @@ fn run_whisper_transcription(
     }
 
     if permissive_attempt.text.trim().is_empty() && permissive_attempt.segments.is_empty() {
+        // Some Vulkan/Metal drivers decode speech to nothing on a healthy context; retry once
+        // on CPU. Silence decodes to nothing anyway and is not worth a second model.
+        let cpu_fallback_model = cpu_fallback_model
+            .filter(|_| contains_speech(audio_data, TARGET_SAMPLE_RATE));
+        if let Some(model_path) = cpu_fallback_model {
+            match decode_on_cpu(
+                &context,
+                model_path,
+                audio_data,
+                preferred_language.as_deref(),
+                config,
+            ) {
+                Ok(cpu_attempt)
+                    if !cpu_attempt.text.trim().is_empty() || !cpu_attempt.segments.is_empty() =>
+                {
+                    info!("CPU fallback recovered a transcription after empty GPU decodes");
+                    if verbose_logs_enabled() {
+                        println!(
+                            "[stt] cpu fallback decode chars={} segments={}",
+                            cpu_attempt.text.chars().count(),
+                            cpu_attempt.segments.len()
+                        );
+                    }
+                    return Ok(cpu_attempt);
+                }
+                Ok(_) => {}
+                Err(err) => warn!("CPU fallback decode failed: {}", err),
+            }
+        }
         warn!("whisper returned empty transcription result after all decode attempts");
     }
     Ok(permissive_attempt)
 }
 
+/// Decodes on the CPU twin of `context`, loaded on first use and kept with it afterwards.
+fn decode_on_cpu(
+    context: &LoadedContext,
+    model_path: &Path,
+    audio_data: &[f32],
+    language_option: Option<&str>,
+    config: &SttConfig,
+) -> Result<Transcription> {
+    decode_once(
+        &context.cpu_fallback(model_path)?,
+        audio_data,
+        language_option,
+        DecodeProfile::Primary,
+        config,
+    )
+}
+
+fn load_cpu_context(model_path: &Path) -> Result<WhisperContext> {
+    let model_path_str = model_path.to_str().ok_or_else(|| {
+        SttError::ModelLoadError(format!("non-utf8 model path: {}", model_path.display()))
+    })?;
+    let mut params = WhisperContextParameters::default();
+    params.use_gpu(false);
+    WhisperContext::new_with_params(model_path_str, params).map_err(|err| {
+        SttError::ModelLoadError(format!(
+            "failed to load CPU whisper context from {}: {err}",
+            model_path.display()
+        ))
+    })
+}
+
 enum DecodeProfile {
     Primary,
     PermissiveFallback,
 }
 
+/// Applies [`SttConfig::whisper_overrides`] on top of the profile defaults. Returns how many
+/// were applied; unknown keys and mismatched value types are skipped with a warning.
+fn apply_whisper_overrides(
+    params: &mut FullParams,
+    overrides: &HashMap<String, WhisperParam>,
+) -> usize {
+    use WhisperParam::{Number, Text};
+
+    let mut applied = 0;
+    for (key, value) in overrides {
+        let known = match (key.as_str(), value) {
+            ("max_len", Number(v)) => {
+                // max_len is only honoured with token timestamps enabled.
+                params.set_token_timestamps(true);
+                params.set_max_len(*v as i32);
+                true
+            }
+            ("split_on_word", Number(v)) => {
+                params.set_split_on_word(*v != 0.0);
+                true
+            }
+            ("single_segment", Number(v)) => {
+                params.set_single_segment(*v != 0.0);
+                true
+            }
+            ("no_context", Number(v)) => {
+                params.set_no_context(*v != 0.0);
+                true
+            }
+            ("suppress_blank", Number(v)) => {
+                params.set_suppress_blank(*v != 0.0);
+                true
+            }
+            ("suppress_nst", Number(v)) => {
+                params.set_suppress_nst(*v != 0.0);
+                true
+            }
+            ("max_tokens", Number(v)) => {
+                params.set_max_tokens(*v as i32);
+                true
+            }
+            ("audio_ctx", Number(v)) => {
+                params.set_audio_ctx(*v as i32);
+                true
+            }
+            ("n_max_text_ctx", Number(v)) => {
+                params.set_n_max_text_ctx(*v as i32);
+                true
+            }
+            ("length_penalty", Number(v)) => {
+                params.set_length_penalty(*v);
+                true
+            }
+            ("temperature", Number(v)) => {
+                params.set_temperature(*v);
+                true
+            }
+            ("temperature_inc", Number(v)) => {
+                params.set_temperature_inc(*v);
+                true
+            }
+            ("entropy_thold", Number(v)) => {
+                params.set_entropy_thold(*v);
+                true
+            }
+            ("logprob_thold", Number(v)) => {
+                params.set_logprob_thold(*v);
+                true
+            }
+            ("no_speech_thold", Number(v)) => {
+                params.set_no_speech_thold(*v);
+                true
+            }
+            ("max_initial_ts", Number(v)) => {
+                params.set_max_initial_ts(*v);
+                true
+            }
+            ("initial_prompt", Text(v)) => {
+                params.set_initial_prompt(v);
+                true
+            }
+            _ => false,
+        };
+        if known {
+            applied += 1;
+        } else {
+            warn!("ignoring whisper override {key}={value:?}: unknown key or wrong value type");
+        }
+    }
+    applied
+}
+
+/// The configured language, English when unset.
+fn decode_language(config: &SttConfig) -> String {
+    config
+        .language
+        .as_deref()
+        .map(str::trim)
+        .filter(|lang| !lang.is_empty())
+        .unwrap_or("en")
+        .to_string()
+}
+
+/// Streams decode each window with the primary profile, prompted with the text before it.
+struct WhisperDecoder {
+    context: Arc<LoadedContext>,
+    config: SttConfig,
+    language: String,
+}
+
+impl ChunkDecoder for WhisperDecoder {
+    fn decode(&mut self, samples: &[f32], committed: &str) -> String {
+        let mut clip = samples.to_vec();
+        if clip.len() < MIN_DECODE_SAMPLES {
+            clip.resize(MIN_DECODE_SAMPLES, 0.0);
+        }
+        let mut config = self.config.clone();
+        let prompt_start = committed
+            .char_indices()
+            .rev()
+            .nth(STREAM_PROMPT_CHARS)
+            .map_or(0, |(i, _)| i);
+        let prompt = committed[prompt_start..].trim();
+        if !prompt.is_empty() {
+            config
+                .whisper_overrides
+                .entry("initial_prompt".to_string())
+                .or_insert_with(|| prompt.into());
+        }
+        match decode_once(
+            &self.context,
+            &clip,
+            Some(&self.language),
+            DecodeProfile::Primary,
+            &config,
+        ) {
+            Ok(transcription) => transcription.text,
+            Err(err) => {
+                warn!("stream window decode failed: {err}");
+                String::new()
+            }
+        }
+    }
+}
+
+/// A loaded model together with the decode states allocated for it.
+struct LoadedContext {
+    context: WhisperContext,
+    states: StatePool<WhisperState>,
+    /// The same model on the CPU, for GPU contexts whose decodes come back empty.
+    cpu_fallback: Mutex<Option<Arc<LoadedContext>>>,
+}
+
+impl LoadedContext {
+    fn new(context: WhisperContext) -> Self {
+        Self {
+            context,
+            states: StatePool::new(),
+            cpu_fallback: Mutex::new(None),
+        }
+    }
+
+    /// The CPU context for `model_path`, loading it the first time it is needed.
+    fn cpu_fallback(&self, model_path: &Path) -> Result<Arc<LoadedContext>> {
+        let mut slot = self
+            .cpu_fallback
+            .lock()
+            .map_err(|_| SttError::ModelLoadError("CPU fallback lock poisoned".into()))?;
+        if let Some(context) = slot.as_ref() {
+            return Ok(context.clone());
+        }
+        let context = Arc::new(LoadedContext::new(load_cpu_context(model_path)?));
+        *slot = Some(context.clone());
+        Ok(context)
+    }
+
+    /// An idle state, or a fresh one when every existing state is busy in another decode.
+    fn checkout_state(&self) -> Result<PooledState<'_, WhisperState>> {
+        self.states.checkout(|| {
+            self.context.create_state().map_err(|e| {
+                SttError::TranscriptionFailed(format!("failed to create whisper state: {e}"))
+            })
+        })
+    }
+}
+
+/// Decode states kept for reuse. Allocating one sets up the KV caches and compute buffers,
+/// which is noticeable on large models, while `whisper_full` starts each run by clearing
+/// the previous segments, so a state can serve any number of decodes. The text of earlier
+/// decodes is kept as the next prompt unless `no_context` is set, which [`decode_once`]
+/// always does. Concurrent decodes each check out their own.
+struct StatePool<S> {
+    idle: Mutex<Vec<S>>,
+    created: AtomicUsize,
+}
+
+impl<S> StatePool<S> {
+    fn new() -> Self {
+        Self {
+            idle: Mutex::new(Vec::new()),
+            created: AtomicUsize::new(0),
+        }
+    }
+
+    fn checkout(&self, create: impl FnOnce() -> Result<S>) -> Result<PooledState<'_, S>> {
+        let idle = self.idle.lock().ok().and_then(|mut idle| idle.pop());
+        let state = match idle {
+            Some(state) => state,
+            None => {
+                let state = create()?;
+                let created = self.created.fetch_add(1, Ordering::Relaxed) + 1;
+                debug!("allocated whisper state #{created}");
+                state
+            }
+        };
+        Ok(PooledState {
+            pool: self,
+            state: Some(state),
+        })
+    }
+}
+
+/// A checked-out state; returns to its pool when dropped.
+struct PooledState<'a, S> {
+    pool: &'a StatePool<S>,
+    state: Option<S>,
+}
+
+impl<S> Deref for PooledState<'_, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.state
+            .as_ref()
+            .expect("pooled state is only taken on drop")
+    }
+}
+
+impl<S> DerefMut for PooledState<'_, S> {
+    fn deref_mut(&mut self) -> &mut S {
+        self.state
+            .as_mut()
+            .expect("pooled state is only taken on drop")
+    }
+}
+
+impl<S> Drop for PooledState<'_, S> {
+    fn drop(&mut self) {
+        if let (Some(state), Ok(mut idle)) = (self.state.take(), self.pool.idle.lock()) {
+            idle.push(state);
+        }
+    }
+}
+
+/// Whisper's most likely language for `audio_data` and its probability, from one encoder
+/// pass over the start of the audio.
+fn detect_language(state: &mut WhisperState, audio_data: &[f32]) -> Option<(&'static str, f32)> {
+    let threads = optimal_threads() as usize;
+    state.pcm_to_mel(audio_data, threads).ok()?;
+    let (lang_id, probs) = state.lang_detect(0, threads).ok()?;
+    let probability = probs.get(usize::try_from(lang_id).ok()?).copied()?;
+    Some((get_lang_str(lang_id)?, probability))
+}
+
 fn decode_once(
-    context: &Arc<WhisperContext>,
+    context: &Arc<LoadedContext>,
     audio_data: &[f32],
     language_option: Option<&str>,
-    task: &TranscriptionTask,
     profile: DecodeProfile,
+    config: &SttConfig,
 ) -> Result<Transcription> {
-    let mut state = context.create_state().map_err(|e| {
-        SttError::TranscriptionFailed(format!("failed to create whisper state: {e}"))
-    })?;
+    let mut state = context.checkout_state()?;
 
+    let tuning = &config.decode_tuning;
     let mut params = match profile {
-        DecodeProfile::Primary => FullParams::new(SamplingStrategy::BeamSearch {
-            beam_size: 5,
-            patience: -1.0,
+        DecodeProfile::Primary if tuning.beam_size > 1 => {
+            FullParams::new(SamplingStrategy::BeamSearch {
+                beam_size: tuning.beam_size as i32,
+                patience: -1.0,
+            })
+        }
+        DecodeProfile::Primary => FullParams::new(SamplingStrategy::Greedy {
+            best_of: tuning.best_of.max(1) as i32,
         }),
         DecodeProfile::PermissiveFallback => {
             FullParams::new(SamplingStrategy::Greedy { best_of: 1 })
@@ -334,13 +903,25 @@ fn decode_once(
     params.set_print_realtime(false);
     params.set_print_timestamps(false);
     params.set_no_timestamps(false);
-    params.set_single_segment(false);
-    params.set_no_context(false);
-    params.set_translate(matches!(task, TranscriptionTask::Translate));
-    params.set_temperature(0.2);
-    params.set_temperature_inc(0.2);
+    params.set_single_segment(config.single_segment);
+    if config.word_timestamps {
+        params.set_token_timestamps(true);
+    }
+    if let Some(max_len) = config.max_len {
+        // max_len is only honoured with token timestamps enabled.
+        params.set_token_timestamps(true);
+        params.set_max_len(max_len as i32);
+        params.set_split_on_word(config.split_on_word);
+    }
+    // A pooled state keeps the previous decode's text as the prompt unless this is set; one
+    // decode still carries context across its own 30 s windows, and streams pass theirs in
+    // through `initial_prompt`
+    params.set_no_context(true);
+    params.set_translate(matches!(config.task, TranscriptionTask::Translate));
+    params.set_temperature(tuning.temperature);
+    params.set_temperature_inc(tuning.temperature_inc);
     params.set_max_initial_ts(1.0);
-    params.set_entropy_thold(2.4);
+    params.set_entropy_thold(tuning.entropy_thold);
     params.set_initial_prompt("");
 
     match profile {
@@ -348,8 +929,8 @@ fn decode_once(
             // Mirrors voicetypr defaults for stable dictation output.
             params.set_suppress_blank(true);
             params.set_suppress_nst(true);
-            params.set_no_speech_thold(0.6);
-            params.set_logprob_thold(-1.0);
+            params.set_no_speech_thold(tuning.no_speech_thold);
+            params.set_logprob_thold(tuning.logprob_thold);
         }
         DecodeProfile::PermissiveFallback => {
             // Reduce filtering when the primary profile yields empty text.
@@ -360,12 +941,23 @@ fn decode_once(
         }
     }
 
-    if let Some(lang) = language_option {
-        params.set_language(Some(lang));
-        params.set_detect_language(false);
-    } else {
-        params.set_language(None);
-        params.set_detect_language(true);
+    apply_whisper_overrides(&mut params, &config.whisper_overrides);
+
+    // Detecting the language here, once, lets `full` skip its own detection pass and still
+    // tells us how sure whisper was
+    let detected = match language_option {
+        Some(_) => None,
+        None => detect_language(&mut state, audio_data),
+    };
+    match language_option.or(detected.map(|(lang, _)| lang)) {
+        Some(lang) => {
+            params.set_language(Some(lang));
+            params.set_detect_language(false);
+        }
+        None => {
+            params.set_language(None);
+            params.set_detect_language(true);
+        }
     }
 
     state
@@ -375,16 +967,56 @@ fn decode_once(
     let n_segments = state.full_n_segments();
     let mut text = String::new();
     let mut segments = Vec::new();
+    let mut logprob_sum = 0.0f32;
+    let mut token_count = 0usize;
+    let mut no_speech_sum = 0.0f32;
+    let mut scored_segments = 0usize;
+    // Ids from end-of-text upward are special/timestamp tokens; they say nothing about the words
+    let first_special_token = context.context.token_eot();
+    let mut text_token_probs = Vec::new();
     for i in 0..n_segments {
         let Some(segment) = state.get_segment(i) else {
             continue;
         };
+        no_speech_sum += segment.no_speech_probability();
+        scored_segments += 1;
+        let mut segment_probs = Vec::new();
+        let mut timed_tokens = Vec::new();
+        for t in 0..segment.n_tokens() {
+            if let Some(token) = segment.get_token(t) {
+                let data = token.token_data();
+                logprob_sum += data.plog;
+                token_count += 1;
+                if data.id < first_special_token {
+                    segment_probs.push(data.p);
+                    if config.word_timestamps {
+                        if let Ok(text) = token.to_str_lossy() {
+                            timed_tokens.push(TimedToken {
+                                text: text.into_owned(),
+                                start: data.t0 as f64 / 100.0,
+                                end: data.t1 as f64 / 100.0,
+                                prob: data.p,
+                            });
+                        }
+                    }
+                }
+            }
+        }
         let segment_text = segment
             .to_str_lossy()
             .map_err(|e| {
                 SttError::TranscriptionFailed(format!("failed to read segment text: {e}"))
             })?
             .into_owned();
+        let segment_text = if config.strip_special_tokens {
+            // Keep the leading space whisper uses to separate consecutive segments.
+            match strip_whisper_special_tokens(&segment_text) {
+                stripped if stripped.is_empty() => stripped,
+                stripped => format!(" {stripped}"),
+            }
+        } else {
+            segment_text
+        };
         text.push_str(&segment_text);
 
         let cleaned = segment_text.trim().to_string();
@@ -393,24 +1025,166 @@ fn decode_once(
                 text: cleaned,
                 start: segment.start_timestamp() as f64 / 100.0,
                 end: segment.end_timestamp() as f64 / 100.0,
+                confidence: mean_probability(&segment_probs),
+                words: group_words(&timed_tokens, config.strip_special_tokens),
             });
+            text_token_probs.extend(segment_probs);
         }
     }
 
-    let language = if let Some(lang) = language_option {
-        Some(lang.to_string())
-    } else {
-        get_lang_str(state.full_lang_id_from_state()).map(str::to_string)
+    let (language, language_confidence) = match (language_option, detected) {
+        (Some(lang), _) => (Some(lang.to_string()), Some(1.0)),
+        (None, Some((lang, probability))) => (Some(lang.to_string()), Some(probability)),
+        // Detection up front failed, so `full` detected the language without odds
+        (None, None) => (
+            get_lang_str(state.full_lang_id_from_state()).map(str::to_string),
+            None,
+        ),
     };
 
+    let text = text.trim().to_string();
+    let scores = (scored_segments > 0).then(|| DecodeScores {
+        avg_logprob: if token_count > 0 {
+            logprob_sum / token_count as f32
+        } else {
+            0.0
+        },
+        no_speech_prob: no_speech_sum / scored_segments as f32,
+        compression_ratio: compression_ratio(&text),
+    });
+
     Ok(Transcription {
-        text: text.trim().to_string(),
+        text,
         language,
-        confidence: None,
+        language_confidence,
+        confidence: mean_probability(&text_token_probs),
+        quality: None,
+        scores,
         segments,
     })
 }
 
+/// A text token with its timestamps (seconds) and probability.
+struct TimedToken {
+    text: String,
+    start: f64,
+    end: f64,
+    prob: f32,
+}
+
+/// Joins BPE tokens into words: a token starting with a space begins a new word, anything
+/// else (word pieces, trailing punctuation) extends the current one.
+fn group_words(tokens: &[TimedToken], strip_special_tokens: bool) -> Vec<Word> {
+    let mut groups: Vec<Vec<&TimedToken>> = Vec::new();
+    for token in tokens {
+        match groups.last_mut() {
+            Some(group) if !token.text.starts_with(' ') => group.push(token),
+            _ => groups.push(vec![token]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            let text: String = group.iter().map(|token| token.text.as_str()).collect();
+            let text = if strip_special_tokens {
+                strip_whisper_special_tokens(&text)
+            } else {
+                text.trim().to_string()
+            };
+            if text.is_empty() {
+                return None;
+            }
+            let probs: Vec<f32> = group.iter().map(|token| token.prob).collect();
+            Some(Word {
+                text,
+                start: group[0].start,
+                end: group[group.len() - 1].end,
+                confidence: mean_probability(&probs),
+            })
+        })
+        .collect()
+}
+
+/// Mean token probability, or `None` when there are no tokens to score.
+fn mean_probability(probs: &[f32]) -> Option<f32> {
+    (!probs.is_empty()).then(|| probs.iter().sum::<f32>() / probs.len() as f32)
+}
+
+/// Whisper's repetition heuristic: UTF-8 length over zlib-compressed length.
+pub(crate) fn compression_ratio(text: &str) -> f32 {
+    if text.is_empty() {
+        return 0.0;
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    if encoder.write_all(text.as_bytes()).is_err() {
+        return 0.0;
+    }
+    match encoder.finish() {
+        Ok(compressed) if !compressed.is_empty() => text.len() as f32 / compressed.len() as f32,
+        _ => 0.0,
+    }
+}
+
+/// Bracketed annotations whisper emits for non-speech audio instead of words.
+const NON_SPEECH_ANNOTATIONS: &[&str] = &[
+    "music",
+    "applause",
+    "laughter",
+    "laughs",
+    "blank_audio",
+    "silence",
+    "noise",
+    "background noise",
+    "inaudible",
+    "no speech",
+];
+
+/// Removes whisper control tokens (`[_BEG_]`, `[_TT_42]`, `<|en|>`), musical-note
+/// symbols and known non-speech annotations. Other bracketed text is kept as-is.
+fn strip_whisper_special_tokens(text: &str) -> String {
+    let mut cleaned = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['[', '(', '<']) {
+        let (before, tail) = rest.split_at(start);
+        cleaned.push_str(before);
+
+        let close = match tail.as_bytes()[0] {
+            b'[' => "]",
+            b'(' => ")",
+            _ => "|>",
+        };
+        let token = tail.find(close).map(|end| &tail[..end + close.len()]);
+        match token {
+            Some(token) if is_special_token(token) => rest = &tail[token.len()..],
+            // Keep just the opener and look for a token again from the next one, so a
+            // stray '<' or '[' cannot swallow the special token that follows it
+            _ => {
+                cleaned.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    cleaned.push_str(rest);
+
+    cleaned
+        .replace(['♪', '♫', '♬'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_special_token(token: &str) -> bool {
+    if token.starts_with("<|") {
+        return true;
+    }
+    if token.starts_with("[_") {
+        return true;
+    }
+    let inner = token[1..token.len() - 1].trim().to_lowercase();
+    NON_SPEECH_ANNOTATIONS.contains(&inner.as_str())
+}
+
 fn optimal_threads() -> i32 {
     std::thread::available_parallelism()
         .map(|n| n.get().min(8))
@@ -447,9 +1221,35 @@ fn resolve_model_path(config: &SttConfig) -> Result<PathBuf> {
         ))
     })?;
 
-    let model_path = cache_dir.join(model_filename(&config.model_name));
-    if model_path.exists() {
-        return Ok(model_path);
+    let model_path = cache_dir.join(cached_model_filename(&config.model_name));
+    if let Ok(metadata) = std::fs::metadata(&model_path) {
+        let expected_mb = expected_model_size_mb(&config.model_name);
+        if !is_truncated_model(metadata.len(), expected_mb) {
+            return Ok(model_path);
+        }
+        warn!(
+            "model {} at {} is {} bytes, expected about {} MB; downloading it again",
+            config.model_name,
+            model_path.display(),
+            metadata.len(),
+            expected_mb.unwrap_or(0)
+        );
+        emit_model_download_progress(ModelDownloadProgress {
+            model_name: config.model_name.clone(),
+            stage: "repair".to_string(),
+            downloaded_bytes: 0,
+            total_bytes: None,
+            percent: None,
+            done: false,
+            error: None,
+            message: Some("Model file is incomplete, downloading it again".to_string()),
+        });
+        std::fs::remove_file(&model_path).map_err(|e| {
+            SttError::ModelLoadError(format!(
+                "failed to remove corrupt model {}: {e}",
+                model_path.display()
+            ))
+        })?;
     }
 
     info!(
@@ -461,11 +1261,26 @@ fn resolve_model_path(config: &SttConfig) -> Result<PathBuf> {
     Ok(model_path)
 }
 
-fn model_cache_dir() -> Result<PathBuf> {
-    if let Ok(override_dir) = std::env::var("OPENWISPR_MODEL_DIR") {
-        if !override_dir.trim().is_empty() {
-            return Ok(PathBuf::from(override_dir));
-        }
+/// Approximate size of a cached model from the built-in table or `models.json`.
+fn expected_model_size_mb(model_name: &str) -> Option<u64> {
+    BUILTIN_MODELS
+        .iter()
+        .find(|(name, _)| *name == model_name)
+        .map(|(_, size_mb)| *size_mb)
+        .or_else(|| custom_whisper_model(model_name).map(|custom| custom.size_mb))
+        .filter(|size_mb| *size_mb > 0)
+}
+
+/// True for an empty file or one under half its expected size, which only an interrupted
+/// copy or a download from before partial files leaves behind. The table sizes are rounded,
+/// so a complete file is never that far off.
+fn is_truncated_model(len: u64, expected_mb: Option<u64>) -> bool {
+    len == 0 || expected_mb.is_some_and(|mb| len < mb * 1024 * 1024 / 2)
+}
+
+pub(crate) fn model_cache_dir() -> Result<PathBuf> {
+    if let Some(override_dir) = crate::model_dir_override() {
+        return Ok(override_dir);
     }
 
     #[cfg(target_os = "windows")]
@@ -479,6 +1294,15 @@ fn model_cache_dir() -> Result<PathBuf> {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(cache_home) = std::env::var("XDG_CACHE_HOME") {
+            if !cache_home.trim().is_empty() {
+                return Ok(PathBuf::from(cache_home).join("openwispr").join("models"));
+            }
+        }
+    }
+
     #[cfg(not(target_os = "windows"))]
     {
         if let Ok(home) = std::env::var("HOME") {
@@ -496,150 +1320,58 @@ fn model_cache_dir() -> Result<PathBuf> {
     ))
 }
 
-fn download_model(model_name: &str, output_path: &Path) -> Result<()> {
-    let filename = model_filename(model_name);
-    let url = format!("https://huggingface.co/ggerganov/whisper.cpp/resolve/main/{filename}");
-
-    let response = ureq::get(&url)
-        .call()
-        .map_err(|e| {
-            let message = format!("failed to download {url}: {e}");
-            emit_model_download_progress(ModelDownloadProgress {
-                model_name: model_name.to_string(),
-                stage: "download".to_string(),
-                downloaded_bytes: 0,
-                total_bytes: None,
-                percent: None,
-                done: true,
-                error: Some(message.clone()),
-                message: Some("Download request failed".to_string()),
-            });
-            SttError::ModelLoadError(message)
-        })?;
-    let total_bytes = response
-        .header("Content-Length")
-        .and_then(|v| v.parse::<u64>().ok())
-        .filter(|v| *v > 0);
-
-    emit_model_download_progress(ModelDownloadProgress {
-        model_name: model_name.to_string(),
-        stage: "download".to_string(),
-        downloaded_bytes: 0,
-        total_bytes,
-        percent: Some(0.0),
-        done: false,
-        error: None,
-        message: Some("Starting model download".to_string()),
-    });
+/// HuggingFace repo hosting whisper.cpp's own ggml conversions.
+const WHISPER_MODEL_REPO: &str = "ggerganov/whisper.cpp";
 
-    let mut reader = response.into_reader();
+fn download_model(model_name: &str, output_path: &Path) -> Result<()> {
+    let custom = custom_whisper_model(model_name);
+    let url = match &custom {
+        Some(custom) => custom.download_url().ok_or_else(|| {
+            SttError::ModelNotFound(format!(
+                "{model_name} was imported from a local file; import it again to restore it"
+            ))
+        })?,
+        None => format!(
+            "https://huggingface.co/{WHISPER_MODEL_REPO}/resolve/main/{}",
+            model_filename(model_name)
+        ),
+    };
+    let expected_sha256 = match &custom {
+        Some(custom) => custom
+            .sha256
+            .clone()
+            .or_else(|| huggingface_sha256(&custom.repo, &custom.filename)),
+        None => huggingface_sha256(WHISPER_MODEL_REPO, &model_filename(model_name)),
+    };
 
-    let tmp_path = output_path.with_extension("download");
-    let file = File::create(&tmp_path).map_err(|e| {
-        let message = format!(
-            "failed to create temporary model file {}: {e}",
-            tmp_path.display()
-        );
+    let downloaded_bytes = fetch_model_file(
+        model_name,
+        &url,
+        output_path,
+        expected_sha256.as_deref(),
+        None,
+        "Downloading model",
+        &emit_model_download_progress,
+    )
+    .map_err(|e| {
+        if matches!(e, DownloadError::Cancelled) {
+            emit_download_cancelled(model_name);
+            return SttError::Cancelled;
+        }
+        let message = e.to_string();
+        let stage = match e {
+            DownloadError::Checksum { .. } => "verify",
+            _ => "download",
+        };
         emit_model_download_progress(ModelDownloadProgress {
             model_name: model_name.to_string(),
-            stage: "download".to_string(),
+            stage: stage.to_string(),
             downloaded_bytes: 0,
-            total_bytes,
+            total_bytes: None,
             percent: None,
             done: true,
             error: Some(message.clone()),
-            message: Some("Failed to create temporary file".to_string()),
-        });
-        SttError::ModelLoadError(message)
-    })?;
-    let mut writer = BufWriter::new(file);
-
-    let mut downloaded_bytes = 0_u64;
-    let mut last_emitted = 0_u64;
-    let mut buffer = [0_u8; 64 * 1024];
-    loop {
-        let n = reader.read(&mut buffer).map_err(|e| {
-            let message = format!("failed while reading model stream {}: {e}", output_path.display());
-            emit_model_download_progress(ModelDownloadProgress {
-                model_name: model_name.to_string(),
-                stage: "download".to_string(),
-                downloaded_bytes,
-                total_bytes,
-                percent: total_bytes.map(|t| ((downloaded_bytes as f32 / t as f32) * 100.0).min(100.0)),
-                done: true,
-                error: Some(message.clone()),
-                message: Some("Download stream read failed".to_string()),
-            });
-            SttError::ModelLoadError(message)
-        })?;
-        if n == 0 {
-            break;
-        }
-        writer.write_all(&buffer[..n]).map_err(|e| {
-            let message = format!("failed while writing model {}: {e}", output_path.display());
-            emit_model_download_progress(ModelDownloadProgress {
-                model_name: model_name.to_string(),
-                stage: "download".to_string(),
-                downloaded_bytes,
-                total_bytes,
-                percent: total_bytes.map(|t| ((downloaded_bytes as f32 / t as f32) * 100.0).min(100.0)),
-                done: true,
-                error: Some(message.clone()),
-                message: Some("Write to temporary file failed".to_string()),
-            });
-            SttError::ModelLoadError(message)
-        })?;
-        downloaded_bytes += n as u64;
-
-        if downloaded_bytes.saturating_sub(last_emitted) >= 256 * 1024
-            || total_bytes.is_some_and(|total| downloaded_bytes >= total)
-        {
-            emit_model_download_progress(ModelDownloadProgress {
-                model_name: model_name.to_string(),
-                stage: "download".to_string(),
-                downloaded_bytes,
-                total_bytes,
-                percent: total_bytes.map(|t| ((downloaded_bytes as f32 / t as f32) * 100.0).min(100.0)),
-                done: false,
-                error: None,
-                message: Some("Downloading model".to_string()),
-            });
-            last_emitted = downloaded_bytes;
-        }
-    }
-
-    writer.flush().map_err(|e| {
-        let message = format!(
-            "failed to flush downloaded model {}: {e}",
-            output_path.display()
-        );
-        emit_model_download_progress(ModelDownloadProgress {
-            model_name: model_name.to_string(),
-            stage: "download".to_string(),
-            downloaded_bytes,
-            total_bytes,
-            percent: total_bytes.map(|t| ((downloaded_bytes as f32 / t as f32) * 100.0).min(100.0)),
-            done: true,
-            error: Some(message.clone()),
-            message: Some("Failed to flush temporary file".to_string()),
-        });
-        SttError::ModelLoadError(message)
-    })?;
-
-    std::fs::rename(&tmp_path, output_path).map_err(|e| {
-        let message = format!(
-            "failed to finalize downloaded model {}: {e}",
-            output_path.display()
-        );
-        emit_model_download_progress(ModelDownloadProgress {
-            model_name: model_name.to_string(),
-            stage: "download".to_string(),
-            downloaded_bytes,
-            total_bytes,
-            percent: total_bytes.map(|t| ((downloaded_bytes as f32 / t as f32) * 100.0).min(100.0)),
-            done: true,
-            error: Some(message.clone()),
-            message: Some("Failed to finalize model file".to_string()),
+            message: Some("Model download failed".to_string()),
         });
         SttError::ModelLoadError(message)
     })?;
@@ -648,7 +1380,7 @@ fn download_model(model_name: &str, output_path: &Path) -> Result<()> {
         model_name: model_name.to_string(),
         stage: "ready".to_string(),
         downloaded_bytes,
-        total_bytes,
+        total_bytes: Some(downloaded_bytes),
         percent: Some(100.0),
         done: true,
         error: None,
@@ -658,6 +1390,38 @@ fn download_model(model_name: &str, output_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Final event of a download stopped by [`platform::download::cancel_model_downloads`].
+pub(crate) fn emit_download_cancelled(model_name: &str) {
+    emit_model_download_progress(ModelDownloadProgress {
+        model_name: model_name.to_string(),
+        stage: "cancelled".to_string(),
+        downloaded_bytes: 0,
+        total_bytes: None,
+        percent: None,
+        done: true,
+        error: None,
+        message: Some("Model download cancelled".to_string()),
+    });
+}
+
+/// `.en` checkpoints are English-only; every other ggml model is multilingual.
+fn whisper_descriptor(name: &str, approx_size_mb: u64, note: Option<String>) -> ModelDescriptor {
+    let english_only = name.ends_with(".en");
+    ModelDescriptor {
+        name: name.to_string(),
+        runtime: RUNTIME_WHISPER.to_string(),
+        approx_size_mb,
+        languages: if english_only {
+            vec!["en".to_string()]
+        } else {
+            Vec::new()
+        },
+        multilingual: !english_only,
+        recommended: name == RECOMMENDED_MODEL,
+        note,
+    }
+}
+
 fn looks_like_model_path(model_name: &str) -> bool {
     model_name.contains('/')
         || model_name.contains('\\')
@@ -665,6 +1429,79 @@ fn looks_like_model_path(model_name: &str) -> bool {
         || model_name.ends_with(".gguf")
 }
 
+fn custom_whisper_models() -> Vec<CatalogModel> {
+    model_cache_dir()
+        .map(|dir| load_custom_models(&dir, RUNTIME_WHISPER))
+        .unwrap_or_default()
+}
+
+fn is_builtin_model(model_name: &str) -> bool {
+    BUILTIN_MODELS.iter().any(|(name, _)| *name == model_name)
+}
+
+/// The `models.json` entry for `model_name`. Built-ins always win, so an entry reusing a
+/// built-in's name cannot change where it downloads from or which file it loads.
+fn custom_whisper_model(model_name: &str) -> Option<CatalogModel> {
+    if is_builtin_model(model_name) {
+        return None;
+    }
+    custom_whisper_models()
+        .into_iter()
+        .find(|custom| custom.name == model_name)
+}
+
+/// Where `model_name` is (or would be) stored in the cache.
+pub(crate) fn cached_model_path(model_name: &str) -> Result<PathBuf> {
+    Ok(model_cache_dir()?.join(cached_model_filename(model_name)))
+}
+
+/// File name inside the cache dir; custom models use the one from `models.json`.
+fn cached_model_filename(model_name: &str) -> String {
+    custom_whisper_model(model_name)
+        .map(|custom| custom.filename)
+        .unwrap_or_else(|| model_filename(model_name))
+}
+
+/// Stages a local `ggml-*.bin` in the cache under its canonical file name for validation.
+/// Committing it registers it in `models.json`, unless it is a built-in's missing download,
+/// so it shows up in `available_models`.
+pub(crate) fn stage_import(source: &Path) -> Result<StagedImport> {
+    if source.extension().and_then(|ext| ext.to_str()) != Some("bin")
+        || !file_has_magic(source, &GGML_MAGIC)
+    {
+        return Err(SttError::ModelLoadError(format!(
+            "{} is not a GGML whisper model",
+            source.display()
+        )));
+    }
+    let stem = source
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let name = stem.strip_prefix("ggml-").unwrap_or(stem).to_string();
+    if name.is_empty() {
+        return Err(SttError::ModelLoadError(format!(
+            "cannot derive a model name from {}",
+            source.display()
+        )));
+    }
+
+    let cache_dir = model_cache_dir()?;
+    let builtin = is_builtin_model(&name);
+    let entry = CatalogModel {
+        filename: model_filename(&name),
+        name,
+        repo: String::new(),
+        size_mb: 0,
+        runtime: RUNTIME_WHISPER.to_string(),
+        sha256: None,
+    };
+    let staged = StagedImport::stage(source, &cache_dir, entry, !builtin)
+        .map_err(SttError::ModelLoadError)?;
+    info!("staged whisper model {} from {}", staged.name, source.display());
+    Ok(staged)
+}
+
 pub(crate) fn model_filename(model_name: &str) -> String {
     if model_name.ends_with(".bin") {
         return model_name.to_string();
@@ -675,7 +1512,46 @@ pub(crate) fn model_filename(model_name: &str) -> String {
     format!("ggml-{model_name}.bin")
 }
 
-pub(crate) fn prepare_audio(audio_data: &[f32], format: &AudioFormat) -> Vec<f32> {
+/// 16 kHz mono, cleaned up as `config` asks: DC offset removed, silence trimmed and quiet
+/// captures lifted.
+pub(crate) fn prepare_audio(
+    audio_data: &[f32],
+    format: &AudioFormat,
+    config: &SttConfig,
+) -> Vec<f32> {
+    let mut prepared = to_model_rate(audio_data, format);
+    if config.remove_dc_offset {
+        // Before trimming: a constant offset reads as signal to the energy VAD
+        remove_dc_offset(&mut prepared);
+    }
+    if config.trim_silence {
+        prepared = trim_silence(&prepared, TARGET_SAMPLE_RATE);
+    }
+    normalize_for_asr(&mut prepared, &config.normalization);
+    prepared
+}
+
+const DC_BLOCKER_POLE: f32 = 0.995;
+
+/// First-order DC blocker, `y[n] = x[n] - x[n-1] + 0.995 * y[n-1]`. Seeded with the first
+/// sample so a constant offset never produces a step at the start of the clip.
+fn remove_dc_offset(samples: &mut [f32]) {
+    let Some(&first) = samples.first() else {
+        return;
+    };
+    let mut prev_in = first;
+    let mut prev_out = 0.0_f32;
+    for sample in samples.iter_mut() {
+        let input = *sample;
+        prev_out = input - prev_in + DC_BLOCKER_POLE * prev_out;
+        prev_in = input;
+        *sample = prev_out;
+    }
+}
+
+/// Downmixes and resamples to 16 kHz mono without normalizing, so streams can convert
+/// chunk by chunk and normalize each decode window as a whole.
+fn to_model_rate(audio_data: &[f32], format: &AudioFormat) -> Vec<f32> {
     if audio_data.is_empty() || format.sample_rate == 0 || format.channels == 0 {
         return Vec::new();
     }
@@ -686,14 +1562,11 @@ pub(crate) fn prepare_audio(audio_data: &[f32], format: &AudioFormat) -> Vec<f32
         downmix_to_mono(audio_data, format.channels as usize)
     };
 
-    let mut prepared = if format.sample_rate == TARGET_SAMPLE_RATE {
+    if format.sample_rate == TARGET_SAMPLE_RATE {
         mono
     } else {
-        resample_linear(&mono, format.sample_rate, TARGET_SAMPLE_RATE)
-    };
-
-    normalize_for_asr(&mut prepared);
-    prepared
+        resample_sinc(&mono, format.sample_rate, TARGET_SAMPLE_RATE)
+    }
 }
 
 fn downmix_to_mono(audio_data: &[f32], channels: usize) -> Vec<f32> {
@@ -709,7 +1582,9 @@ fn downmix_to_mono(audio_data: &[f32], channels: usize) -> Vec<f32> {
     mono
 }
 
-fn resample_linear(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+/// Windowed-sinc resampling with a polyphase kernel table: one precomputed kernel per
+/// fractional input position, so the inner loop is a plain dot product.
+fn resample_sinc(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
     if samples.is_empty() || from_rate == 0 || to_rate == 0 {
         return Vec::new();
     }
@@ -717,24 +1592,192 @@ fn resample_linear(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
         return samples.to_vec();
     }
 
-    let ratio = from_rate as f64 / to_rate as f64;
-    let out_len = ((samples.len() as f64) / ratio).max(1.0).round() as usize;
+    let kernel = SincKernel::new(from_rate, to_rate);
+    let out_len = kernel.output_len(samples.len());
+    (0..out_len)
+        .map(|i| kernel.sample_at(samples, 0, i * kernel.step))
+        .collect()
+}
+
+/// Polyphase kernel table for one pair of rates. Positions are counted in `1 / phases` of
+/// an input sample, so output `i` sits exactly at `i * step` with no accumulated drift.
+struct SincKernel {
+    phases: usize,
+    step: usize,
+    ratio: f64,
+    radius: usize,
+    taps: usize,
+    kernels: Vec<f32>,
+}
+
+impl SincKernel {
+    fn new(from_rate: u32, to_rate: u32) -> Self {
+        let divisor = gcd(from_rate, to_rate);
+        let phases = (to_rate / divisor) as usize;
+        let step = (from_rate / divisor) as usize;
+        let ratio = from_rate as f64 / to_rate as f64;
+        // Cycles per input sample; downsampling lowers it to the output Nyquist
+        let cutoff = 0.5 * RESAMPLE_CUTOFF * (1.0 / ratio).min(1.0);
+        let half_width = RESAMPLE_ZERO_CROSSINGS / (2.0 * cutoff);
+        let radius = half_width.ceil() as usize;
+        let taps = 2 * radius + 1;
+
+        let mut kernels = Vec::with_capacity(phases * taps);
+        for phase in 0..phases {
+            let frac = phase as f64 / phases as f64;
+            kernels.extend((0..taps).map(|tap| {
+                let x = tap as f64 - radius as f64 - frac;
+                if x.abs() >= half_width {
+                    0.0
+                } else {
+                    (sinc(2.0 * cutoff * x) * blackman(x / half_width)) as f32
+                }
+            }));
+        }
+        Self {
+            phases,
+            step,
+            ratio,
+            radius,
+            taps,
+            kernels,
+        }
+    }
+
+    /// Output samples for `input_len` input samples.
+    fn output_len(&self, input_len: usize) -> usize {
+        ((input_len as f64) / self.ratio).max(1.0).round() as usize
+    }
+
+    /// The output sample at `position`, from `samples` holding the input starting at index
+    /// `offset`. The kernel is clipped where it runs past either end of `samples`.
+    fn sample_at(&self, samples: &[f32], offset: usize, position: usize) -> f32 {
+        let base = position / self.phases;
+        let radius = self.radius;
+        let kernel = &self.kernels[(position % self.phases) * self.taps..][..self.taps];
+        let first = base.saturating_sub(radius).max(offset);
+        let last = (base + radius).min(offset + samples.len() - 1);
+        let kernel = &kernel[first + radius - base..=last + radius - base];
+        let (mut acc, mut weight_sum) = (0.0_f32, 0.0_f32);
+        for (sample, weight) in samples[first - offset..=last - offset].iter().zip(kernel) {
+            acc += sample * weight;
+            weight_sum += weight;
+        }
+        // Normalizing keeps DC exact, including at the edges where the kernel is cut off
+        if weight_sum.abs() > f32::EPSILON {
+            acc / weight_sum
+        } else {
+            0.0
+        }
+    }
+}
+
+/// [`to_model_rate`] for audio arriving in chunks. The input still under the kernel and the
+/// position of the next output carry over between chunks, so the result matches converting
+/// the whole recording at once instead of having seams at every chunk boundary.
+pub(crate) struct StreamResampler {
+    channels: usize,
+    /// `None` when the input is already at the target rate (or unusable).
+    kernel: Option<SincKernel>,
+    /// Mono input from index `offset` on; earlier samples are no longer under the kernel.
+    history: Vec<f32>,
+    offset: usize,
+    consumed: usize,
+    produced: usize,
+}
+
+impl StreamResampler {
+    pub(crate) fn new(format: &AudioFormat) -> Self {
+        let kernel = (format.sample_rate != 0 && format.sample_rate != TARGET_SAMPLE_RATE)
+            .then(|| SincKernel::new(format.sample_rate, TARGET_SAMPLE_RATE));
+        Self {
+            channels: format.channels as usize,
+            kernel,
+            history: Vec::new(),
+            offset: 0,
+            consumed: 0,
+            produced: 0,
+        }
+    }
+
+    /// Converts the next chunk. Outputs whose kernel reaches past the input seen so far are
+    /// held back until more arrives or [`Self::flush`] is called.
+    pub(crate) fn push(&mut self, samples: &[f32]) -> Vec<f32> {
+        if samples.is_empty() || self.channels == 0 {
+            return Vec::new();
+        }
+        let mono = if self.channels == 1 {
+            samples.to_vec()
+        } else {
+            downmix_to_mono(samples, self.channels)
+        };
+        let Some(kernel) = &self.kernel else {
+            return mono;
+        };
+        self.history.extend_from_slice(&mono);
+        self.consumed += mono.len();
+
+        let mut out = Vec::new();
+        loop {
+            let position = self.produced * kernel.step;
+            if position / kernel.phases + kernel.radius >= self.consumed {
+                break;
+            }
+            out.push(kernel.sample_at(&self.history, self.offset, position));
+            self.produced += 1;
+        }
+
+        let next_base = self.produced * kernel.step / kernel.phases;
+        let needed_from = next_base.saturating_sub(kernel.radius);
+        if needed_from > self.offset {
+            let drop = (needed_from - self.offset).min(self.history.len());
+            self.history.drain(..drop);
+            self.offset += drop;
+        }
+        out
+    }
+
+    /// Emits the outputs held back at the end of the input; call once the stream is done.
+    pub(crate) fn flush(&mut self) -> Vec<f32> {
+        let Some(kernel) = &self.kernel else {
+            return Vec::new();
+        };
+        if self.history.is_empty() {
+            return Vec::new();
+        }
+        let out: Vec<f32> = (self.produced..kernel.output_len(self.consumed))
+            .map(|i| kernel.sample_at(&self.history, self.offset, i * kernel.step))
+            .collect();
+        self.produced += out.len();
+        out
+    }
+}
 
-    let mut out = Vec::with_capacity(out_len);
-    for i in 0..out_len {
-        let src_pos = i as f64 * ratio;
-        let idx = src_pos.floor() as usize;
-        let frac = (src_pos - idx as f64) as f32;
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
 
-        let a = samples[idx.min(samples.len() - 1)];
-        let b = samples[(idx + 1).min(samples.len() - 1)];
-        out.push(a + (b - a) * frac);
+/// Normalized sinc, `sin(pi x) / (pi x)`.
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        return 1.0;
     }
-    out
+    let phase = std::f64::consts::PI * x;
+    phase.sin() / phase
+}
+
+/// Blackman window over `t` in [-1, 1].
+fn blackman(t: f64) -> f64 {
+    let phase = std::f64::consts::PI * t;
+    0.42 + 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos()
 }
 
-fn normalize_for_asr(samples: &mut [f32]) {
-    if samples.is_empty() {
+pub(crate) fn normalize_for_asr(samples: &mut [f32], tuning: &NormalizationTuning) {
+    if !tuning.enabled || samples.is_empty() {
         return;
     }
 
@@ -747,12 +1790,11 @@ fn normalize_for_asr(samples: &mut [f32]) {
     }
 
     // Leave normal/loud captures untouched; only lift very quiet push-to-talk clips.
-    if peak >= 0.20 {
+    if peak >= tuning.activation_peak {
         return;
     }
 
-    let target_peak = 0.35_f32;
-    let gain = (target_peak / peak).clamp(1.0, 80.0);
+    let gain = (tuning.target_peak / peak).min(tuning.max_gain).max(1.0);
     if (gain - 1.0).abs() < 0.01 {
         return;
     }
@@ -762,45 +1804,119 @@ fn normalize_for_asr(samples: &mut [f32]) {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
-struct SignalStats {
-    peak: f32,
-    rms: f32,
-    zero_crossing_rate: f32,
-}
+const QUALITY_SILENCE_RMS: f32 = 0.002;
+const QUALITY_NOISE_ZCR: f32 = 0.35;
+const QUALITY_SHORT_TEXT_WORDS: usize = 3;
 
-fn signal_stats(samples: &[f32]) -> SignalStats {
-    if samples.is_empty() {
-        return SignalStats::default();
+/// Heuristic stand-in for decoder confidence, derived from the prepared mono 16 kHz
+/// audio and the decoded text. Text produced from near-silence or from broadband noise
+/// is most often a hallucination, so those cases score low.
+pub(crate) fn estimate_quality(audio: &[f32], text: &str) -> Option<f32> {
+    if audio.is_empty() {
+        return None;
     }
 
-    let peak = samples
-        .iter()
-        .map(|s| s.abs())
-        .fold(0.0_f32, |acc, v| acc.max(v));
-    let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
-
-    let mut crossings = 0usize;
-    for pair in samples.windows(2) {
-        let a = pair[0];
-        let b = pair[1];
-        if (a >= 0.0 && b < 0.0) || (a < 0.0 && b >= 0.0) {
-            crossings += 1;
-        }
+    let word_count = text.split_whitespace().count();
+    if word_count == 0 {
+        return Some(0.0);
     }
-    let zcr = crossings as f32 / samples.len() as f32;
 
-    SignalStats {
-        peak,
-        rms,
-        zero_crossing_rate: zcr,
+    let stats = signal_stats(audio);
+    let mut quality = 1.0_f32;
+    if stats.rms < QUALITY_SILENCE_RMS {
+        quality = quality.min(0.2);
     }
+    if stats.zero_crossing_rate > QUALITY_NOISE_ZCR && word_count <= QUALITY_SHORT_TEXT_WORDS {
+        quality = quality.min(0.3);
+    }
+    Some(quality)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn whisper_descriptors_mark_english_only_models() {
+        let english = whisper_descriptor("small.en", 466, None);
+        assert_eq!(english.languages, vec!["en".to_string()]);
+        assert!(!english.multilingual);
+
+        let turbo = whisper_descriptor(RECOMMENDED_MODEL, 1_620, None);
+        assert!(turbo.multilingual && turbo.recommended);
+        assert!(BUILTIN_MODELS
+            .iter()
+            .any(|(name, _)| *name == RECOMMENDED_MODEL));
+    }
+
+    #[test]
+    fn whisper_overrides_skip_unknown_keys_and_wrong_types() {
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        let overrides = HashMap::from([
+            ("length_penalty".to_string(), WhisperParam::from(1.0)),
+            ("initial_prompt".to_string(), WhisperParam::from("OpenWispr")),
+            ("max_len".to_string(), WhisperParam::from("wide")),
+            ("made_up".to_string(), WhisperParam::from(2.0)),
+        ]);
+        assert_eq!(apply_whisper_overrides(&mut params, &overrides), 2);
+    }
+
+    #[test]
+    fn truncated_models_are_detected_against_expected_size() {
+        assert_eq!(expected_model_size_mb("base.en"), Some(142));
+        assert!(is_truncated_model(0, None));
+        assert!(is_truncated_model(0, Some(142)));
+        assert!(is_truncated_model(10 * 1024 * 1024, Some(142)));
+        assert!(!is_truncated_model(141 * 1024 * 1024, Some(142)));
+        assert!(!is_truncated_model(1024, None));
+    }
+
+    #[test]
+    fn configured_gpu_memory_floor_wins_over_model_size() {
+        assert_eq!(gpu_memory_floor_bytes(Some(2048), 100), 2048 * 1024 * 1024);
+        assert_eq!(gpu_memory_floor_bytes(Some(0), 3_000_000_000), 0);
+    }
+
+    #[test]
+    fn compression_ratio_flags_repetition() {
+        assert_eq!(compression_ratio(""), 0.0);
+        let normal = compression_ratio("Please send the quarterly report to the finance team.");
+        let looping = compression_ratio(&"thank you ".repeat(40));
+        assert!(normal < 2.4, "normal speech ratio {normal}");
+        assert!(looping > 2.4, "looping ratio {looping}");
+    }
+
+    fn timed(text: &str, start: f64, end: f64) -> TimedToken {
+        TimedToken {
+            text: text.to_string(),
+            start,
+            end,
+            prob: 0.5,
+        }
+    }
+
+    #[test]
+    fn group_words_joins_word_pieces_and_punctuation() {
+        let tokens = [
+            timed(" Hel", 0.0, 0.2),
+            timed("lo", 0.2, 0.4),
+            timed(",", 0.4, 0.4),
+            timed(" [Music]", 0.5, 1.0),
+            timed(" world", 1.0, 1.5),
+        ];
+        let words = group_words(&tokens, true);
+        let texts: Vec<&str> = words.iter().map(|word| word.text.as_str()).collect();
+        assert_eq!(texts, ["Hello,", "world"]);
+        assert_eq!((words[0].start, words[0].end), (0.0, 0.4));
+        assert_eq!(words[1].confidence, Some(0.5));
+    }
+
+    #[test]
+    fn mean_probability_is_none_without_tokens() {
+        assert_eq!(mean_probability(&[]), None);
+        assert_eq!(mean_probability(&[0.5, 1.0]), Some(0.75));
+    }
+
     #[test]
     fn model_filename_maps_named_models() {
         assert_eq!(model_filename("base"), "ggml-base.bin");
@@ -813,18 +1929,150 @@ mod tests {
         assert_eq!(model_filename("ggml-custom.bin"), "ggml-custom.bin");
     }
 
+    #[test]
+    fn transcribe_after_unload_reports_not_initialized() {
+        let adapter = SharedWhisperAdapter::new("test whisper backend");
+        let format = AudioFormat {
+            sample_rate: TARGET_SAMPLE_RATE,
+            channels: 1,
+            bits_per_sample: 16,
+        };
+        let err = tokio_test::block_on(async {
+            adapter.unload().await;
+            adapter.transcribe(&[0.0; 1_600], format).await
+        })
+        .unwrap_err();
+        assert!(
+            matches!(&err, SttError::TranscriptionFailed(msg) if msg == "adapter not initialized"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn state_pool_reuses_states_between_decodes() {
+        let pool = StatePool::new();
+        let allocate = || Ok(vec![0.0_f32; 1024]);
+        for _ in 0..3 {
+            let mut state = pool.checkout(allocate).unwrap();
+            state[0] += 1.0;
+        }
+        assert_eq!(pool.created.load(Ordering::Relaxed), 1);
+
+        // Overlapping decodes never share a state
+        let first = pool.checkout(allocate).unwrap();
+        let second = pool.checkout(allocate).unwrap();
+        assert_eq!(first[0], 3.0);
+        assert_eq!(second[0], 0.0);
+        drop((first, second));
+        assert_eq!(pool.created.load(Ordering::Relaxed), 2);
+        assert_eq!(pool.idle.lock().unwrap().len(), 2);
+    }
+
+    /// Resampling and quiet-clip normalization only.
+    fn raw_config() -> SttConfig {
+        SttConfig {
+            remove_dc_offset: false,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn prepare_audio_downmixes_stereo_and_resamples_to_16k() {
-        let input = vec![0.2, 0.6, 0.4, 0.8, 0.6, 1.0];
+        let input = [0.2, 0.6].repeat(480);
         let format = AudioFormat {
             sample_rate: 48_000,
             channels: 2,
             bits_per_sample: 16,
         };
 
-        let out = prepare_audio(&input, &format);
-        assert_eq!(out.len(), 1);
-        assert!((out[0] - 0.4).abs() < 0.001);
+        let out = prepare_audio(&input, &format, &raw_config());
+        assert_eq!(out.len(), 160);
+        assert!(out.iter().all(|s| (s - 0.4).abs() < 0.001));
+    }
+
+    /// The interpolating resampler this replaced, kept as the aliasing baseline.
+    fn resample_linear(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+        if samples.is_empty() || from_rate == 0 || to_rate == 0 {
+            return Vec::new();
+        }
+        if from_rate == to_rate {
+            return samples.to_vec();
+        }
+
+        let ratio = from_rate as f64 / to_rate as f64;
+        let out_len = ((samples.len() as f64) / ratio).max(1.0).round() as usize;
+
+        let mut out = Vec::with_capacity(out_len);
+        for i in 0..out_len {
+            let src_pos = i as f64 * ratio;
+            let idx = src_pos.floor() as usize;
+            let frac = (src_pos - idx as f64) as f32;
+
+            let a = samples[idx.min(samples.len() - 1)];
+            let b = samples[(idx + 1).min(samples.len() - 1)];
+            out.push(a + (b - a) * frac);
+        }
+        out
+    }
+
+    /// Linear chirp at half amplitude.
+    fn sweep(rate: u32, from_hz: f64, to_hz: f64) -> Vec<f32> {
+        let mut phase = 0.0_f64;
+        (0..rate)
+            .map(|i| {
+                let hz = from_hz + (to_hz - from_hz) * f64::from(i) / f64::from(rate);
+                phase += 2.0 * std::f64::consts::PI * hz / f64::from(rate);
+                (phase.sin() * 0.5) as f32
+            })
+            .collect()
+    }
+
+    /// RMS away from the clip edges.
+    fn inner_rms(samples: &[f32]) -> f32 {
+        let inner = &samples[200..samples.len() - 200];
+        (inner.iter().map(|s| s * s).sum::<f32>() / inner.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn sinc_resampler_rejects_content_above_nyquist() {
+        for rate in [48_000, 44_100] {
+            // Everything above the 8 kHz output Nyquist should vanish, not fold back down
+            let above = sweep(rate, 9_000.0, 20_000.0);
+            let linear = inner_rms(&resample_linear(&above, rate, TARGET_SAMPLE_RATE));
+            let sinc = inner_rms(&resample_sinc(&above, rate, TARGET_SAMPLE_RATE));
+            assert!(linear > 0.1, "{rate}: linear {linear}");
+            assert!(sinc < 0.001, "{rate}: sinc {sinc}");
+
+            let speech_band = sweep(rate, 200.0, 6_000.0);
+            let kept = inner_rms(&resample_sinc(&speech_band, rate, TARGET_SAMPLE_RATE));
+            assert!(
+                (kept - inner_rms(&speech_band)).abs() < 0.01,
+                "{rate}: kept {kept}"
+            );
+        }
+    }
+
+    #[test]
+    fn stream_resampler_matches_converting_the_whole_recording() {
+        let stereo: Vec<f32> = sweep(44_100, 200.0, 6_000.0)
+            .into_iter()
+            .flat_map(|s| [s, -0.5 * s])
+            .collect();
+        let format = AudioFormat {
+            sample_rate: 44_100,
+            channels: 2,
+            bits_per_sample: 16,
+        };
+        let whole = to_model_rate(&stereo, &format);
+
+        let mut resampler = StreamResampler::new(&format);
+        let mut chunked = Vec::new();
+        // An odd frame count, so chunk edges land at many fractional positions
+        for chunk in stereo.chunks(2 * 479) {
+            chunked.extend(resampler.push(chunk));
+        }
+        chunked.extend(resampler.flush());
+        assert_eq!(chunked, whole);
     }
 
     #[test]
@@ -835,10 +2083,57 @@ mod tests {
             channels: 1,
             bits_per_sample: 16,
         };
-        let out = prepare_audio(&input, &format);
+        let out = prepare_audio(&input, &format, &raw_config());
         assert_eq!(out, input);
     }
 
+    #[test]
+    fn disabled_normalization_leaves_quiet_samples_identical() {
+        let input = vec![0.001, -0.0015, 0.002];
+        let mut out = input.clone();
+        let tuning = NormalizationTuning {
+            enabled: false,
+            ..Default::default()
+        };
+        normalize_for_asr(&mut out, &tuning);
+        assert_eq!(
+            out.iter().map(|s| s.to_bits()).collect::<Vec<_>>(),
+            input.iter().map(|s| s.to_bits()).collect::<Vec<_>>()
+        );
+
+        let capped = NormalizationTuning {
+            max_gain: 2.0,
+            ..Default::default()
+        };
+        normalize_for_asr(&mut out, &capped);
+        assert_eq!(out, vec![0.002, -0.003, 0.004]);
+    }
+
+    #[test]
+    fn prepare_audio_removes_dc_offset() {
+        let rate = TARGET_SAMPLE_RATE as f32;
+        let tone: Vec<f32> = (0..TARGET_SAMPLE_RATE)
+            .map(|i| 0.3 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / rate).sin())
+            .collect();
+        let input: Vec<f32> = tone.iter().map(|s| s + 0.1).collect();
+        let format = AudioFormat {
+            sample_rate: TARGET_SAMPLE_RATE,
+            channels: 1,
+            bits_per_sample: 16,
+        };
+
+        let config = SttConfig {
+            remove_dc_offset: true,
+            ..raw_config()
+        };
+        let out = prepare_audio(&input, &format, &config);
+        assert_eq!(out.len(), input.len());
+        let mean = out.iter().sum::<f32>() / out.len() as f32;
+        assert!(mean.abs() < 0.005, "mean {mean}");
+        let (kept, original) = (inner_rms(&out), inner_rms(&tone));
+        assert!((kept - original).abs() < 0.01, "kept {kept}, original {original}");
+    }
+
     #[test]
     fn prepare_audio_normalizes_very_quiet_input() {
         let input = vec![0.001, -0.0015, 0.002];
@@ -848,7 +2143,7 @@ mod tests {
             bits_per_sample: 16,
         };
 
-        let out = prepare_audio(&input, &format);
+        let out = prepare_audio(&input, &format, &raw_config());
         let max_amp = out
             .iter()
             .map(|s| s.abs())
@@ -860,11 +2155,50 @@ mod tests {
     }
 
     #[test]
-    fn signal_stats_reports_peak_and_rms() {
-        let input = vec![0.5, -0.5, 0.5, -0.5];
-        let stats = signal_stats(&input);
-        assert!((stats.peak - 0.5).abs() < 0.0001);
-        assert!((stats.rms - 0.5).abs() < 0.0001);
-        assert!(stats.zero_crossing_rate > 0.0);
+    fn estimate_quality_flags_text_from_silence() {
+        let silence = vec![0.0005, -0.0005, 0.0004, -0.0004];
+        assert_eq!(estimate_quality(&silence, "thank you for watching"), Some(0.2));
+    }
+
+    #[test]
+    fn estimate_quality_flags_short_text_from_noise() {
+        let noise = vec![0.3, -0.3, 0.3, -0.3, 0.3, -0.3];
+        assert_eq!(estimate_quality(&noise, "you"), Some(0.3));
+    }
+
+    #[test]
+    fn estimate_quality_trusts_normal_speech_levels() {
+        let speech: Vec<f32> = (0..1600).map(|i| (i as f32 * 0.05).sin() * 0.2).collect();
+        assert_eq!(estimate_quality(&speech, "schedule the meeting"), Some(1.0));
+        assert_eq!(estimate_quality(&speech, ""), Some(0.0));
+        assert_eq!(estimate_quality(&[], "anything"), None);
+    }
+
+    #[test]
+    fn strip_whisper_special_tokens_removes_non_speech_annotations() {
+        assert_eq!(
+            strip_whisper_special_tokens(" [Music] ♪ Hello there [_BEG_] world <|en|>"),
+            "Hello there world"
+        );
+        assert_eq!(strip_whisper_special_tokens("(applause) thanks"), "thanks");
+        assert_eq!(strip_whisper_special_tokens("[BLANK_AUDIO]"), "");
+    }
+
+    #[test]
+    fn strip_whisper_special_tokens_keeps_legitimate_brackets() {
+        assert_eq!(
+            strip_whisper_special_tokens("call f(x) with [index] and a < b"),
+            "call f(x) with [index] and a < b"
+        );
+    }
+
+    #[test]
+    fn strip_whisper_special_tokens_restarts_at_each_opener() {
+        assert_eq!(strip_whisper_special_tokens("a <<|en|>b"), "a <b");
+        assert_eq!(strip_whisper_special_tokens("a < b <|en|>"), "a < b");
+        assert_eq!(
+            strip_whisper_special_tokens("[see [Music] here]"),
+            "[see here]"
+        );
     }
 }
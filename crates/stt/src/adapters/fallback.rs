@@ -1,7 +1,7 @@
 //! Fallback STT adapter for when no backend is available.
 //! Provides a no-op implementation to maintain compilation.
 
-use crate::{AudioFormat, Result, SttAdapter, SttConfig, Transcription, SttError};
+use crate::{AudioFormat, ModelDescriptor, Result, SttAdapter, SttConfig, SttError, Transcription};
 use async_trait::async_trait;
 use tracing::warn;
 
@@ -21,15 +21,23 @@ impl SttAdapter for FallbackAdapter {
         Ok(())
     }
 
+    async fn unload(&mut self) -> Result<()> {
+        Ok(())
+    }
+
     async fn transcribe(&self, _audio_data: &[f32], _format: AudioFormat) -> Result<Transcription> {
         Err(SttError::TranscriptionFailed("No STT backend available on this system".to_string()))
     }
 
+    async fn download_only(&self, _model_name: &str) -> Result<()> {
+        Err(SttError::ModelLoadError("No STT backend available on this system".to_string()))
+    }
+
     async fn is_model_available(&self, _model_name: &str) -> bool {
         false
     }
 
-    fn available_models(&self) -> Vec<String> {
+    fn available_model_descriptors(&self) -> Vec<ModelDescriptor> {
         vec![]
     }
 
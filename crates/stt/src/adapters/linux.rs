@@ -0,0 +1,93 @@
+//! Linux STT adapter.
+//! whisper.cpp only: CPU by default, Vulkan when built with the `vulkan` feature.
+
+use crate::{
+    is_mlx_model_name, is_sherpa_model_name, AudioFormat, ModelDescriptor, Result, SttAdapter,
+    SttConfig, SttError, SttStream, Transcription,
+};
+use async_trait::async_trait;
+use tracing::info;
+
+use super::backend::SharedWhisperAdapter;
+use std::sync::{Arc, Mutex};
+
+pub struct LinuxWhisperAdapter {
+    whisper: SharedWhisperAdapter,
+    current_model: Arc<Mutex<Option<String>>>,
+}
+
+impl LinuxWhisperAdapter {
+    pub fn new() -> Self {
+        info!("Initializing Linux whisper adapter");
+        Self {
+            whisper: SharedWhisperAdapter::new("Linux whisper backend"),
+            current_model: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+/// Sherpa and MLX runtimes are not built for Linux.
+fn ensure_whisper_model(model_name: &str) -> Result<()> {
+    if is_sherpa_model_name(model_name) || is_mlx_model_name(model_name) {
+        return Err(SttError::UnsupportedPlatform);
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl SttAdapter for LinuxWhisperAdapter {
+    async fn initialize(&mut self, config: SttConfig) -> Result<()> {
+        ensure_whisper_model(&config.model_name)?;
+        let model_name = config.model_name.clone();
+        self.whisper.initialize(config).await?;
+
+        if let Ok(mut slot) = self.current_model.lock() {
+            *slot = Some(model_name);
+        }
+        Ok(())
+    }
+
+    async fn unload(&mut self) -> Result<()> {
+        self.whisper.unload().await;
+        if let Ok(mut slot) = self.current_model.lock() {
+            *slot = None;
+        }
+        Ok(())
+    }
+
+    async fn transcribe(&self, audio_data: &[f32], format: AudioFormat) -> Result<Transcription> {
+        self.whisper.transcribe(audio_data, format).await
+    }
+
+    async fn download_only(&self, model_name: &str) -> Result<()> {
+        ensure_whisper_model(model_name)?;
+        self.whisper.download_only(model_name).await
+    }
+
+    async fn is_model_available(&self, model_name: &str) -> bool {
+        ensure_whisper_model(model_name).is_ok()
+            && self.whisper.is_model_available(model_name).await
+    }
+
+    fn available_model_descriptors(&self) -> Vec<ModelDescriptor> {
+        self.whisper.model_descriptors()
+    }
+
+    async fn warm_up(&self) -> Result<()> {
+        self.whisper.warm_up().await
+    }
+
+    fn current_model(&self) -> Option<String> {
+        self.current_model.lock().ok().and_then(|slot| slot.clone())
+    }
+
+    fn create_stream(&self, format: AudioFormat) -> Result<Box<dyn SttStream>> {
+        self.whisper.create_stream(format)
+    }
+}
+
+impl Default for LinuxWhisperAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,8 +2,8 @@
 //! Routes between whisper.cpp, Sherpa ONNX and MLX Parakeet based on selected model.
 
 use crate::{
-    is_mlx_model_name, is_sherpa_model_name, AudioFormat, Result, SttAdapter, SttConfig,
-    Transcription,
+    is_mlx_model_name, is_sherpa_model_name, mlx_supported, warm_up_clip, AudioFormat,
+    ModelDescriptor, ProgressCallback, Result, SttAdapter, SttConfig, SttStream, Transcription,
 };
 use async_trait::async_trait;
 use tracing::{info, warn};
@@ -64,6 +64,16 @@ impl SttAdapter for MlxAdapter {
         Ok(())
     }
 
+    async fn unload(&mut self) -> Result<()> {
+        self.whisper.unload().await;
+        self.sherpa.unload().await;
+        self.mlx_parakeet.unload().await;
+        if let Ok(mut slot) = self.current_model.lock() {
+            *slot = None;
+        }
+        Ok(())
+    }
+
     async fn transcribe(&self, audio_data: &[f32], format: AudioFormat) -> Result<Transcription> {
         let model_name = self
             .current_model
@@ -81,6 +91,32 @@ impl SttAdapter for MlxAdapter {
         }
     }
 
+    async fn transcribe_with_progress(
+        &self,
+        audio_data: &[f32],
+        format: AudioFormat,
+        on_progress: ProgressCallback,
+    ) -> Result<Transcription> {
+        let model_name = self.current_model().unwrap_or_default();
+        if is_mlx_model_name(&model_name) {
+            self.mlx_parakeet
+                .transcribe_with_progress(audio_data, format, on_progress)
+                .await
+        } else {
+            self.transcribe(audio_data, format).await
+        }
+    }
+
+    async fn download_only(&self, model_name: &str) -> Result<()> {
+        if is_sherpa_model_name(model_name) {
+            self.sherpa.download_only(model_name).await
+        } else if is_mlx_model_name(model_name) {
+            self.mlx_parakeet.download_only(model_name).await
+        } else {
+            self.whisper.download_only(model_name).await
+        }
+    }
+
     async fn is_model_available(&self, model_name: &str) -> bool {
         if is_sherpa_model_name(model_name) {
             self.sherpa.is_model_available(model_name).await
@@ -91,13 +127,50 @@ impl SttAdapter for MlxAdapter {
         }
     }
 
-    fn available_models(&self) -> Vec<String> {
-        self.whisper.available_models()
+    fn available_model_descriptors(&self) -> Vec<ModelDescriptor> {
+        let mut models = self.whisper.model_descriptors();
+        models.push(self.sherpa.model_descriptor());
+        if mlx_supported() {
+            models.push(self.mlx_parakeet.model_descriptor());
+        }
+        models
+    }
+
+    async fn warm_up(&self) -> Result<()> {
+        let model_name = self.current_model().ok_or_else(|| {
+            crate::SttError::TranscriptionFailed("adapter not initialized".into())
+        })?;
+        if is_sherpa_model_name(&model_name) {
+            let (clip, format) = warm_up_clip();
+            self.sherpa.transcribe(&clip, format).await.map(|_| ())
+        } else if is_mlx_model_name(&model_name) {
+            let (clip, format) = warm_up_clip();
+            self.mlx_parakeet
+                .transcribe(&clip, format)
+                .await
+                .map(|_| ())
+        } else {
+            self.whisper.warm_up().await
+        }
     }
 
     fn current_model(&self) -> Option<String> {
         self.current_model.lock().ok().and_then(|slot| slot.clone())
     }
+
+    fn create_stream(&self, format: AudioFormat) -> Result<Box<dyn SttStream>> {
+        let model_name = self.current_model().ok_or_else(|| {
+            crate::SttError::TranscriptionFailed("adapter not initialized".into())
+        })?;
+        if is_sherpa_model_name(&model_name) {
+            self.sherpa.create_stream(format)
+        } else if is_mlx_model_name(&model_name) {
+            // Each MLX decode round-trips a wav through the python worker; too slow per window
+            Err(crate::SttError::StreamingUnsupported)
+        } else {
+            self.whisper.create_stream(format)
+        }
+    }
 }
 
 impl Default for MlxAdapter {
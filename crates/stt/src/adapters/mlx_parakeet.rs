@@ -1,23 +1,38 @@
 use crate::{
-    emit_model_download_progress, is_mlx_model_name, AudioFormat, ModelDownloadProgress, Result,
-    SttConfig, SttError, TranscriptSegment, Transcription,
+    current_progress_scope, emit_model_download_progress, is_mlx_model_name, with_download_scope,
+    AudioFormat, ModelDescriptor, ModelDownloadProgress, ProgressCallback, Result, SttConfig,
+    SttError, TranscriptSegment, Transcription, TranscriptionTask, Word, MLX_PARAKEET_V2_MODEL,
 };
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 use tokio::sync::RwLock;
 
-use std::sync::Arc;
-
-use super::backend::{prepare_audio, TARGET_SAMPLE_RATE};
+use super::backend::{estimate_quality, prepare_audio, TARGET_SAMPLE_RATE};
+use super::mlx_worker::MlxWorker;
 
 const PYTHON_BIN: &str = "python3";
+// mlx wheels are not published for older interpreters
+const MIN_PYTHON_VERSION: (u32, u32) = (3, 9);
 const MLX_VENV_DIR: &str = ".venv";
+// Installed versions of the pinned packages, as a JSON object keyed by package name
+const MLX_INSTALLED_MARKER: &str = "versions.json";
+// Known-good versions; bump together after verifying transcription end-to-end.
+const PARAKEET_MLX_VERSION: &str = "0.3.5";
+const MLX_VERSION: &str = "0.26.3";
+const MLX_REQUIREMENTS: &[(&str, &str)] =
+    &[("parakeet-mlx", PARAKEET_MLX_VERSION), ("mlx", MLX_VERSION)];
 
 #[derive(Default)]
 struct MlxState {
     config: Option<SttConfig>,
-    model_ref: Option<String>,
+    /// Python process holding the loaded model; shared with in-flight decodes.
+    worker: Option<Arc<Mutex<MlxWorker>>>,
 }
 
 pub(crate) struct SharedMlxParakeetAdapter {
@@ -31,75 +46,79 @@ impl SharedMlxParakeetAdapter {
         }
     }
 
+    /// Size excludes the python virtualenv, built on first use.
+    pub(crate) fn model_descriptor(&self) -> ModelDescriptor {
+        ModelDescriptor {
+            name: MLX_PARAKEET_V2_MODEL.to_string(),
+            runtime: "mlx-parakeet".to_string(),
+            approx_size_mb: 2_470,
+            languages: vec!["en".to_string()],
+            multilingual: false,
+            recommended: false,
+            note: Some("Parakeet MLX community model".to_string()),
+        }
+    }
+
     pub(crate) async fn initialize(&self, config: SttConfig) -> Result<()> {
-        let model_ref = resolve_model_ref(&config)?;
+        // Parakeet is an English-only transcriber
+        if matches!(config.task, TranscriptionTask::Translate) {
+            return Err(SttError::TranslationUnsupported(config.model_name));
+        }
+        let model_ref = prepare_model(&config).await?;
         let cache_dir = mlx_cache_dir()?;
-
-        emit_model_download_progress(ModelDownloadProgress {
-            model_name: model_ref.clone(),
-            stage: "prepare".to_string(),
-            downloaded_bytes: 0,
-            total_bytes: None,
-            percent: Some(0.0),
-            done: false,
-            error: None,
-            message: Some("Preparing MLX runtime".to_string()),
-        });
-
-        tokio::task::spawn_blocking({
-            let model_ref = model_ref.clone();
-            let cache_dir = cache_dir.clone();
-            move || ensure_parakeet_ready(&model_ref, &cache_dir)
+        let venv_dir = mlx_venv_dir()?;
+        let worker = tokio::task::spawn_blocking(move || {
+            MlxWorker::start(&venv_python_bin(&venv_dir), &model_ref, &cache_dir)
         })
         .await
-        .map_err(|e| SttError::ModelLoadError(format!("mlx setup task failed: {e}")))??;
-
-        let marker = marker_file_path(&model_ref)?;
-        if let Some(parent) = marker.parent() {
-            fs::create_dir_all(parent).map_err(|e| {
-                SttError::ModelLoadError(format!(
-                    "failed to create mlx marker directory {}: {e}",
-                    parent.display()
-                ))
-            })?;
-        }
-        fs::write(&marker, b"ready").map_err(|e| {
-            SttError::ModelLoadError(format!(
-                "failed to write mlx marker {}: {e}",
-                marker.display()
-            ))
-        })?;
-        emit_model_download_progress(ModelDownloadProgress {
-            model_name: model_ref.clone(),
-            stage: "ready".to_string(),
-            downloaded_bytes: 0,
-            total_bytes: None,
-            percent: Some(100.0),
-            done: true,
-            error: None,
-            message: Some("MLX model ready".to_string()),
-        });
+        .map_err(|e| SttError::ModelLoadError(format!("mlx worker task failed: {e}")))??;
 
         let mut state = self.state.write().await;
-        state.model_ref = Some(model_ref);
+        state.worker = Some(Arc::new(Mutex::new(worker)));
         state.config = Some(config);
         Ok(())
     }
 
+    /// Installs the runtime and converts the model without recording it as loaded.
+    pub(crate) async fn download_only(&self, model_name: &str) -> Result<()> {
+        prepare_model(&SttConfig {
+            model_name: model_name.to_string(),
+            ..Default::default()
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub(crate) async fn unload(&self) {
+        *self.state.write().await = MlxState::default();
+    }
+
     pub(crate) async fn transcribe(
         &self,
         audio_data: &[f32],
         format: AudioFormat,
     ) -> Result<Transcription> {
-        let model_ref = {
+        self.transcribe_with_progress(audio_data, format, Arc::new(|_| {}))
+            .await
+    }
+
+    /// Decodes through the worker, forwarding its partial lines and heartbeats.
+    pub(crate) async fn transcribe_with_progress(
+        &self,
+        audio_data: &[f32],
+        format: AudioFormat,
+        on_progress: ProgressCallback,
+    ) -> Result<Transcription> {
+        let (worker, config) = {
             let state = self.state.read().await;
-            state
-                .model_ref
+            let worker = state
+                .worker
                 .clone()
-                .ok_or_else(|| SttError::TranscriptionFailed("mlx adapter not initialized".into()))?
+                .ok_or_else(|| SttError::TranscriptionFailed("mlx adapter not initialized".into()))?;
+            (worker, state.config.clone().unwrap_or_default())
         };
 
-        let prepared = prepare_audio(audio_data, &format);
+        let prepared = prepare_audio(audio_data, &format, &config);
         if prepared.is_empty() {
             return Err(SttError::AudioError(
                 "no audio samples available after preprocessing".into(),
@@ -107,31 +126,70 @@ impl SharedMlxParakeetAdapter {
         }
 
         let duration_s = prepared.len() as f64 / TARGET_SAMPLE_RATE as f64;
-        let cache_dir = mlx_cache_dir()?;
-        let text = tokio::task::spawn_blocking(move || {
+        let (transcript, prepared) = tokio::task::spawn_blocking(move || {
             let temp_wav = temp_wav_path();
             write_mono_wav(&temp_wav, &prepared, TARGET_SAMPLE_RATE)?;
-            let result = run_mlx_transcription(&model_ref, &cache_dir, &temp_wav);
+            let result = worker
+                .lock()
+                .map_err(|_| SttError::TranscriptionFailed("mlx worker lock poisoned".into()))
+                .and_then(|mut worker| worker.transcribe(&temp_wav, &*on_progress));
             let _ = fs::remove_file(&temp_wav);
-            result
+            result.map(|transcript| (transcript, prepared))
         })
         .await
         .map_err(|e| SttError::TranscriptionFailed(format!("mlx decode task failed: {e}")))??;
 
-        let clean = text.trim().to_string();
-        let mut segments = Vec::new();
-        if !clean.is_empty() {
+        // parakeet-mlx does not report a language; a single-language model can only produce
+        // its own
+        let language = transcript.language.or_else(|| {
+            let descriptor = self.model_descriptor();
+            let mut languages = descriptor.languages.into_iter();
+            match (descriptor.multilingual, languages.next(), languages.next()) {
+                (false, Some(language), None) => Some(language),
+                _ => None,
+            }
+        });
+        let clean = transcript.text;
+        let mut segments: Vec<TranscriptSegment> = transcript
+            .segments
+            .into_iter()
+            .filter(|segment| !segment.text.is_empty())
+            .map(|segment| TranscriptSegment {
+                text: segment.text,
+                start: segment.start,
+                end: segment.end,
+                confidence: None,
+                words: segment
+                    .words
+                    .into_iter()
+                    .map(|word| Word {
+                        text: word.text,
+                        start: word.start,
+                        end: word.end,
+                        confidence: None,
+                    })
+                    .collect(),
+            })
+            .collect();
+        // Older parakeet-mlx results carry no sentence alignment
+        if segments.is_empty() && !clean.is_empty() {
             segments.push(TranscriptSegment {
                 text: clean.clone(),
                 start: 0.0,
                 end: duration_s,
+                confidence: None,
+                words: Vec::new(),
             });
         }
 
+        let quality = estimate_quality(&prepared, &clean);
         Ok(Transcription {
             text: clean,
-            language: Some("en".to_string()),
+            language,
+            language_confidence: None,
             confidence: None,
+            quality,
+            scores: None,
             segments,
         })
     }
@@ -140,11 +198,76 @@ impl SharedMlxParakeetAdapter {
         if !is_mlx_model_name(model_name) {
             return false;
         }
-        marker_file_path(model_name)
+        let marker_ready = marker_file_path(model_name)
             .map(|path| path.exists())
-            .unwrap_or(false)
+            .unwrap_or(false);
+        // An out-of-date venv reports unavailable so the UI prompts a reinstall.
+        marker_ready && mlx_venv_dir().is_ok_and(|dir| venv_requirements_current(&dir))
+    }
+
+}
+
+/// Ensures the MLX runtime and converted model exist, returning the resolved model ref.
+async fn prepare_model(config: &SttConfig) -> Result<String> {
+    // Fail before creating a venv whose pip install would error out on x86_64
+    if !crate::mlx_supported() {
+        return Err(SttError::AppleSiliconRequired);
+    }
+    let model_ref = resolve_model_ref(config)?;
+    let cache_dir = mlx_cache_dir()?;
+
+    emit_model_download_progress(ModelDownloadProgress {
+        model_name: model_ref.clone(),
+        stage: "prepare".to_string(),
+        downloaded_bytes: 0,
+        total_bytes: None,
+        percent: Some(0.0),
+        done: false,
+        error: None,
+        message: Some("Preparing MLX runtime".to_string()),
+    });
+
+    let force_rebuild = config.force_runtime_rebuild || rebuild_requested_by_env();
+    let scope = current_progress_scope();
+    tokio::task::spawn_blocking({
+        let model_ref = model_ref.clone();
+        let cache_dir = cache_dir.clone();
+        move || {
+            with_download_scope(scope, || {
+                ensure_parakeet_ready(&model_ref, &cache_dir, force_rebuild)
+            })
+        }
+    })
+    .await
+    .map_err(|e| SttError::ModelLoadError(format!("mlx setup task failed: {e}")))??;
+
+    let marker = marker_file_path(&model_ref)?;
+    if let Some(parent) = marker.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            SttError::ModelLoadError(format!(
+                "failed to create mlx marker directory {}: {e}",
+                parent.display()
+            ))
+        })?;
     }
+    fs::write(&marker, b"ready").map_err(|e| {
+        SttError::ModelLoadError(format!(
+            "failed to write mlx marker {}: {e}",
+            marker.display()
+        ))
+    })?;
+    emit_model_download_progress(ModelDownloadProgress {
+        model_name: model_ref.clone(),
+        stage: "ready".to_string(),
+        downloaded_bytes: 0,
+        total_bytes: None,
+        percent: Some(100.0),
+        done: true,
+        error: None,
+        message: Some("MLX model ready".to_string()),
+    });
 
+    Ok(model_ref)
 }
 
 fn resolve_model_ref(config: &SttConfig) -> Result<String> {
@@ -162,7 +285,126 @@ fn resolve_model_ref(config: &SttConfig) -> Result<String> {
     )))
 }
 
-fn ensure_parakeet_ready(model_ref: &str, cache_dir: &Path) -> Result<()> {
+/// Python child of the in-flight MLX setup, so [`cancel_setup`] can kill it.
+fn setup_child_slot() -> &'static Mutex<Option<Child>> {
+    static SLOT: OnceLock<Mutex<Option<Child>>> = OnceLock::new();
+    SLOT.get_or_init(|| Mutex::new(None))
+}
+
+static SETUP_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Aborts a running MLX setup (venv creation, pip install or weight download). Returns
+/// whether a setup step was running.
+pub(crate) fn cancel_setup() -> bool {
+    SETUP_CANCELLED.store(true, Ordering::SeqCst);
+    let Ok(mut slot) = setup_child_slot().lock() else {
+        return false;
+    };
+    match slot.as_mut() {
+        Some(child) => {
+            let _ = child.kill();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Like [`Command::output`], but tracks the child so the setup can be cancelled.
+fn run_setup_command(command: &mut Command) -> Result<Output> {
+    if SETUP_CANCELLED.load(Ordering::SeqCst) {
+        return Err(SttError::Cancelled);
+    }
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| SttError::ModelLoadError(format!("failed to start MLX setup step: {e}")))?;
+
+    // Drain pipes on their own threads so a chatty pip cannot fill them and stall
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buffer);
+            }
+            buffer
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    if let Ok(mut slot) = setup_child_slot().lock() {
+        *slot = Some(child);
+    }
+    let status = loop {
+        let polled = match setup_child_slot().lock() {
+            Ok(mut slot) => slot.as_mut().map(|child| child.try_wait()),
+            Err(_) => None,
+        };
+        match polled {
+            Some(Ok(Some(status))) => break Ok(status),
+            Some(Ok(None)) => std::thread::sleep(Duration::from_millis(100)),
+            Some(Err(e)) => break Err(e),
+            None => break Err(std::io::Error::other("MLX setup child lost")),
+        }
+    };
+    if let Ok(mut slot) = setup_child_slot().lock() {
+        *slot = None;
+    }
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if SETUP_CANCELLED.load(Ordering::SeqCst) {
+        return Err(SttError::Cancelled);
+    }
+    let status = status
+        .map_err(|e| SttError::ModelLoadError(format!("failed to wait for MLX setup step: {e}")))?;
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+/// Removes what a cancelled setup may have left half-written so the next attempt starts
+/// fresh. Completed artifacts (a current venv, weights with a ready marker) are kept.
+fn cleanup_incomplete_setup(model_ref: &str, cache_dir: &Path) {
+    if let Ok(venv_dir) = mlx_venv_dir() {
+        if !venv_requirements_current(&venv_dir) {
+            let _ = discard_venv(&venv_dir);
+        }
+    }
+    let marker_ready = marker_file_path(model_ref).is_ok_and(|path| path.exists());
+    if !marker_ready {
+        let _ = fs::remove_dir_all(weights_dir(cache_dir, model_ref));
+        if let Ok(marker) = marker_file_path(model_ref) {
+            if let Some(parent) = marker.parent() {
+                let _ = fs::remove_dir_all(parent);
+            }
+        }
+    }
+}
+
+fn ensure_parakeet_ready(model_ref: &str, cache_dir: &Path, force_rebuild: bool) -> Result<()> {
+    SETUP_CANCELLED.store(false, Ordering::SeqCst);
+    let result = run_parakeet_setup(model_ref, cache_dir, force_rebuild);
+    if matches!(result, Err(SttError::Cancelled)) {
+        cleanup_incomplete_setup(model_ref, cache_dir);
+        emit_model_download_progress(ModelDownloadProgress {
+            model_name: model_ref.to_string(),
+            stage: "cancelled".to_string(),
+            downloaded_bytes: 0,
+            total_bytes: None,
+            percent: None,
+            done: true,
+            error: None,
+            message: Some("MLX setup cancelled".to_string()),
+        });
+    }
+    result
+}
+
+fn run_parakeet_setup(model_ref: &str, cache_dir: &Path, force_rebuild: bool) -> Result<()> {
     emit_model_download_progress(ModelDownloadProgress {
         model_name: model_ref.to_string(),
         stage: "runtime-check".to_string(),
@@ -173,7 +415,8 @@ fn ensure_parakeet_ready(model_ref: &str, cache_dir: &Path) -> Result<()> {
         error: None,
         message: Some("Checking Python runtime".to_string()),
     });
-    ensure_python_available()?;
+    let venv_dir = mlx_venv_dir()?;
+    let python_version = ensure_python_available(&venv_dir)?;
 
     emit_model_download_progress(ModelDownloadProgress {
         model_name: model_ref.to_string(),
@@ -183,9 +426,11 @@ fn ensure_parakeet_ready(model_ref: &str, cache_dir: &Path) -> Result<()> {
         percent: Some(25.0),
         done: false,
         error: None,
-        message: Some("Preparing parakeet-mlx package".to_string()),
+        message: Some(format!(
+            "Preparing parakeet-mlx package (Python {python_version})"
+        )),
     });
-    ensure_parakeet_package_installed(cache_dir)?;
+    ensure_parakeet_package_installed(&venv_dir, force_rebuild)?;
 
     let script = r#"
 import sys
@@ -205,23 +450,23 @@ from_pretrained(model_ref, cache_dir=cache_dir)
         message: Some("Downloading MLX model weights".to_string()),
     });
 
-    let python_bin = venv_python_bin(cache_dir);
-    let output = Command::new(&python_bin)
-        .args(["-c", script, model_ref, &cache_dir.to_string_lossy()])
-        .output()
-        .map_err(|e| {
-            SttError::ModelLoadError(format!(
-                "failed to start MLX Python runtime ({}): {e}",
-                python_bin.display()
-            ))
-        })?;
+    let python_bin = venv_python_bin(&venv_dir);
+    let output = run_setup_command(Command::new(&python_bin).args([
+        "-c",
+        script,
+        model_ref,
+        &cache_dir.to_string_lossy(),
+    ]))?;
 
     if !output.status.success() {
-        let message = format!(
-            "failed to download/load MLX model '{}': {}",
-            model_ref,
-            compact_python_error(&output.stderr)
-        );
+        let error =
+            missing_runtime(&String::from_utf8_lossy(&output.stderr)).unwrap_or_else(|| {
+                SttError::ModelLoadError(format!(
+                    "failed to download/load MLX model '{}': {}",
+                    model_ref,
+                    compact_python_error(&output.stderr)
+                ))
+            });
         emit_model_download_progress(ModelDownloadProgress {
             model_name: model_ref.to_string(),
             stage: "download".to_string(),
@@ -229,10 +474,10 @@ from_pretrained(model_ref, cache_dir=cache_dir)
             total_bytes: None,
             percent: Some(40.0),
             done: true,
-            error: Some(message.clone()),
+            error: Some(error.to_string()),
             message: Some("MLX model setup failed".to_string()),
         });
-        return Err(SttError::ModelLoadError(message));
+        return Err(error);
     }
     emit_model_download_progress(ModelDownloadProgress {
         model_name: model_ref.to_string(),
@@ -248,146 +493,252 @@ from_pretrained(model_ref, cache_dir=cache_dir)
     Ok(())
 }
 
-fn run_mlx_transcription(model_ref: &str, cache_dir: &Path, wav_path: &Path) -> Result<String> {
-    let script = r#"
-import sys
-from parakeet_mlx import from_pretrained
-
-model_ref = sys.argv[1]
-wav_path = sys.argv[2]
-cache_dir = sys.argv[3]
-
-model = from_pretrained(model_ref, cache_dir=cache_dir)
-result = model.transcribe(wav_path)
-
-text = getattr(result, "text", None)
-if text is None and isinstance(result, dict):
-    text = result.get("text")
-if text is None and hasattr(result, "__dict__"):
-    text = result.__dict__.get("text")
-if text is None:
-    text = str(result)
-print((text or "").strip())
-"#;
-
-    let python_bin = venv_python_bin(cache_dir);
+/// Checks the interpreter setup will run with (the existing venv's, else the one that
+/// creates it) and returns its version.
+fn ensure_python_available(venv_dir: &Path) -> Result<String> {
+    let venv_python = venv_python_bin(venv_dir);
+    let python_bin = if venv_python.exists() {
+        venv_python
+    } else {
+        python_bin()
+    };
     let output = Command::new(&python_bin)
-        .args([
-            "-c",
-            script,
-            model_ref,
-            &wav_path.to_string_lossy(),
-            &cache_dir.to_string_lossy(),
-        ])
+        .arg("--version")
         .output()
         .map_err(|e| {
-            SttError::TranscriptionFailed(format!(
-                "failed to start MLX Python runtime ({}): {e}",
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return SttError::RuntimeMissing {
+                    component: "Python".to_string(),
+                    hint: INSTALL_PYTHON_HINT.to_string(),
+                };
+            }
+            SttError::ModelLoadError(format!(
+                "Python is required for MLX Parakeet runtime ({} failed to start): {e}",
                 python_bin.display()
             ))
         })?;
-
     if !output.status.success() {
-        return Err(SttError::TranscriptionFailed(format!(
-            "MLX transcription failed: {}",
-            compact_python_error(&output.stderr)
-        )));
+        // macOS' /usr/bin/python3 is a stub that asks for the Command Line Tools
+        return Err(
+            missing_runtime(&String::from_utf8_lossy(&output.stderr)).unwrap_or_else(|| {
+                SttError::ModelLoadError(format!(
+                    "Python is required for MLX Parakeet runtime but {} --version failed",
+                    python_bin.display()
+                ))
+            }),
+        );
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let text = stdout
-        .lines()
-        .map(str::trim)
-        .filter(|line| !line.is_empty())
-        .last()
-        .unwrap_or("")
-        .to_string();
+    // Python 2 and some 3.x builds print the version to stderr
+    let reported = [output.stdout, output.stderr].concat();
+    let reported = String::from_utf8_lossy(&reported);
+    let version = parse_python_version(&reported).ok_or_else(|| {
+        SttError::ModelLoadError(format!(
+            "could not read the Python version from {}: {}",
+            python_bin.display(),
+            reported.trim()
+        ))
+    })?;
+    let (min_major, min_minor) = MIN_PYTHON_VERSION;
+    if (version.0, version.1) < MIN_PYTHON_VERSION {
+        return Err(SttError::RuntimeMissing {
+            component: format!("Python {min_major}.{min_minor}+"),
+            hint: format!(
+                "{} is Python {}.{}.{}. {INSTALL_PYTHON_HINT}",
+                python_bin.display(),
+                version.0,
+                version.1,
+                version.2
+            ),
+        });
+    }
+    Ok(format!("{}.{}.{}", version.0, version.1, version.2))
+}
 
-    Ok(text)
+/// `(major, minor, patch)` from `python --version` output such as `Python 3.11.6`.
+fn parse_python_version(output: &str) -> Option<(u32, u32, u32)> {
+    let version = output
+        .split_whitespace()
+        .skip_while(|word| *word != "Python")
+        .nth(1)?;
+    let mut parts = version.split('.').map(|part| {
+        let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse::<u32>().ok()
+    });
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
 }
 
-fn ensure_python_available() -> Result<()> {
-    let output = Command::new(PYTHON_BIN)
-        .arg("--version")
-        .output()
-        .map_err(|e| {
-            SttError::ModelLoadError(format!(
-                "Python is required for MLX Parakeet runtime ({PYTHON_BIN} not found): {e}"
-            ))
-        })?;
-    if output.status.success() {
-        return Ok(());
-    }
+/// `OPENWISPR_PYTHON_BIN`, else `python3` from `PATH`. Only used to create the venv.
+fn python_bin() -> PathBuf {
+    std::env::var("OPENWISPR_PYTHON_BIN")
+        .ok()
+        .filter(|bin| !bin.trim().is_empty())
+        .map(|bin| PathBuf::from(bin.trim()))
+        .unwrap_or_else(|| PathBuf::from(PYTHON_BIN))
+}
 
-    Err(SttError::ModelLoadError(
-        "Python is required for MLX Parakeet runtime but --version failed".into(),
-    ))
+/// Venv from `OPENWISPR_MLX_VENV`, which is reused as is and never deleted.
+fn venv_override() -> Option<PathBuf> {
+    std::env::var("OPENWISPR_MLX_VENV")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(|dir| PathBuf::from(dir.trim()))
 }
 
-fn ensure_parakeet_package_installed(cache_dir: &Path) -> Result<()> {
-    let python_bin = ensure_venv_ready(cache_dir)?;
-    let check = Command::new(&python_bin)
-        .args(["-c", "import parakeet_mlx"])
-        .output()
-        .map_err(|e| {
+/// Deletes the managed venv. An external one only loses its install marker, so the pinned
+/// requirements are installed into it again.
+fn discard_venv(venv_dir: &Path) -> std::io::Result<()> {
+    if venv_override().is_some() {
+        let marker = venv_dir.join(MLX_INSTALLED_MARKER);
+        return match fs::remove_file(marker) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    if venv_dir.exists() {
+        fs::remove_dir_all(venv_dir)?;
+    }
+    Ok(())
+}
+
+fn ensure_parakeet_package_installed(venv_dir: &Path, force_rebuild: bool) -> Result<()> {
+    if force_rebuild {
+        discard_venv(venv_dir).map_err(|e| {
             SttError::ModelLoadError(format!(
-                "failed to probe parakeet-mlx in MLX runtime ({}): {e}",
-                python_bin.display()
+                "failed to reset MLX virtualenv {}: {e}",
+                venv_dir.display()
             ))
         })?;
-    if check.status.success() {
+    }
+
+    let python_bin = ensure_venv_ready(venv_dir)?;
+    if venv_requirements_current(venv_dir) {
         return Ok(());
     }
 
-    let install = Command::new(&python_bin)
-        .args(["-m", "pip", "install", "--upgrade", "parakeet-mlx"])
+    let install = run_setup_command(Command::new(&python_bin).args(pip_install_args()))?;
+    if !install.status.success() {
+        return Err(
+            missing_runtime(&String::from_utf8_lossy(&install.stderr)).unwrap_or_else(|| {
+                SttError::ModelLoadError(format!(
+                    "failed to install parakeet-mlx: {}",
+                    compact_python_error(&install.stderr)
+                ))
+            }),
+        );
+    }
+
+    let freeze = Command::new(&python_bin)
+        .args(["-m", "pip", "freeze"])
         .output()
         .map_err(|e| {
             SttError::ModelLoadError(format!(
-                "failed to install parakeet-mlx in MLX runtime ({}): {e}",
+                "failed to list MLX runtime packages ({}): {e}",
                 python_bin.display()
             ))
         })?;
-    if install.status.success() {
-        return Ok(());
+    let versions = installed_versions(&String::from_utf8_lossy(&freeze.stdout));
+    if !requirements_satisfied(&versions) {
+        return Err(SttError::ModelLoadError(format!(
+            "parakeet-mlx install finished but the venv does not match the pinned versions \
+             (found {versions:?})"
+        )));
     }
+    let marker = venv_dir.join(MLX_INSTALLED_MARKER);
+    let json = serde_json::to_string_pretty(&versions)
+        .map_err(|e| SttError::ModelLoadError(format!("failed to encode MLX versions: {e}")))?;
+    fs::write(&marker, json).map_err(|e| {
+        SttError::ModelLoadError(format!(
+            "failed to write MLX install marker {}: {e}",
+            marker.display()
+        ))
+    })?;
+    Ok(())
+}
 
-    Err(SttError::ModelLoadError(format!(
-        "failed to install parakeet-mlx: {}",
-        compact_python_error(&install.stderr)
-    )))
+/// `pip install` arguments for exactly the pinned versions.
+fn pip_install_args() -> Vec<String> {
+    let mut args = vec!["-m".to_string(), "pip".to_string(), "install".to_string()];
+    args.extend(
+        MLX_REQUIREMENTS
+            .iter()
+            .map(|(package, version)| format!("{package}=={version}")),
+    );
+    args
+}
+
+/// True when the recorded `versions.json` matches every pinned requirement.
+fn venv_requirements_current(venv_dir: &Path) -> bool {
+    let marker = venv_dir.join(MLX_INSTALLED_MARKER);
+    let Ok(json) = fs::read_to_string(marker) else {
+        return false;
+    };
+    serde_json::from_str::<BTreeMap<String, String>>(&json)
+        .is_ok_and(|installed| requirements_satisfied(&installed))
+}
+
+/// Versions of the pinned packages found in `pip freeze` output.
+fn installed_versions(freeze: &str) -> BTreeMap<String, String> {
+    freeze
+        .lines()
+        .filter_map(|line| line.split_once("=="))
+        .map(|(package, version)| (normalize_package(package), version.trim().to_string()))
+        .filter(|(package, _)| MLX_REQUIREMENTS.iter().any(|(pinned, _)| pinned == package))
+        .collect()
+}
+
+fn requirements_satisfied(installed: &BTreeMap<String, String>) -> bool {
+    MLX_REQUIREMENTS
+        .iter()
+        .all(|(package, version)| installed.get(*package).map(String::as_str) == Some(*version))
+}
+
+/// pip may report `parakeet_mlx` for the `parakeet-mlx` distribution.
+fn normalize_package(name: &str) -> String {
+    name.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+fn rebuild_requested_by_env() -> bool {
+    std::env::var("OPENWISPR_MLX_REBUILD_VENV")
+        .ok()
+        .as_deref()
+        .map(|v| v == "1")
+        .unwrap_or(false)
 }
 
-fn ensure_venv_ready(cache_dir: &Path) -> Result<PathBuf> {
-    let python_bin = venv_python_bin(cache_dir);
+fn ensure_venv_ready(venv_dir: &Path) -> Result<PathBuf> {
+    let python_bin = venv_python_bin(venv_dir);
     if python_bin.exists() {
         return Ok(python_bin);
     }
 
-    if !cache_dir.exists() {
-        fs::create_dir_all(cache_dir).map_err(|e| {
+    if let Some(parent) = venv_dir.parent().filter(|parent| !parent.exists()) {
+        fs::create_dir_all(parent).map_err(|e| {
             SttError::ModelLoadError(format!(
                 "failed to create MLX cache directory {}: {e}",
-                cache_dir.display()
+                parent.display()
             ))
         })?;
     }
 
-    let venv_dir = cache_dir.join(MLX_VENV_DIR);
-    let create = Command::new(PYTHON_BIN)
-        .args(["-m", "venv", &venv_dir.to_string_lossy()])
-        .output()
-        .map_err(|e| {
-            SttError::ModelLoadError(format!(
-                "failed to create MLX virtualenv with {PYTHON_BIN}: {e}"
-            ))
-        })?;
+    let create = run_setup_command(Command::new(python_bin()).args([
+        "-m",
+        "venv",
+        &venv_dir.to_string_lossy(),
+    ]))?;
 
     if !create.status.success() {
-        return Err(SttError::ModelLoadError(format!(
-            "failed to create MLX virtualenv: {}",
-            compact_python_error(&create.stderr)
-        )));
+        return Err(
+            missing_runtime(&String::from_utf8_lossy(&create.stderr)).unwrap_or_else(|| {
+                SttError::ModelLoadError(format!(
+                    "failed to create MLX virtualenv: {}",
+                    compact_python_error(&create.stderr)
+                ))
+            }),
+        );
     }
 
     if !python_bin.exists() {
@@ -400,8 +751,55 @@ fn ensure_venv_ready(cache_dir: &Path) -> Result<PathBuf> {
     Ok(python_bin)
 }
 
-fn venv_python_bin(cache_dir: &Path) -> PathBuf {
-    cache_dir.join(MLX_VENV_DIR).join("bin").join("python3")
+fn venv_python_bin(venv_dir: &Path) -> PathBuf {
+    venv_dir.join("bin").join("python3")
+}
+
+const INSTALL_PYTHON_HINT: &str =
+    "Install Python 3.10+ from python.org, or set OPENWISPR_PYTHON_BIN to an existing one.";
+
+/// Python or pip output that means a prerequisite the user can fix is missing.
+pub(crate) fn missing_runtime(output: &str) -> Option<SttError> {
+    let output = output.to_ascii_lowercase();
+    let mentions = |needles: &[&str]| needles.iter().any(|needle| output.contains(needle));
+    let (component, hint) = if mentions(&["xcode-select", "no developer tools"]) {
+        (
+            "Xcode Command Line Tools",
+            "Run `xcode-select --install` in Terminal, or install Python 3.10+ from python.org.",
+        )
+    } else if mentions(&["no module named venv", "ensurepip is not available"]) {
+        (
+            "Python venv module",
+            "Install Python 3.10+ from python.org; its installer includes venv.",
+        )
+    } else if mentions(&[
+        "failed to establish a new connection",
+        "temporary failure in name resolution",
+        "nodename nor servname",
+        "connection refused",
+        "read timed out",
+    ]) {
+        (
+            "Network access to PyPI",
+            "Connect to the internet (or configure pip's proxy) and try again.",
+        )
+    } else if mentions(&["no matching distribution found"]) {
+        (
+            "Compatible Python",
+            "Install Python 3.10+ for Apple Silicon from python.org, then reinstall the model.",
+        )
+    } else if mentions(&["modulenotfounderror", "importerror"]) && mentions(&["mlx"]) {
+        (
+            "MLX runtime",
+            "Reinstall the model to rebuild its Python environment.",
+        )
+    } else {
+        return None;
+    };
+    Some(SttError::RuntimeMissing {
+        component: component.to_string(),
+        hint: hint.to_string(),
+    })
 }
 
 fn compact_python_error(stderr: &[u8]) -> String {
@@ -421,6 +819,18 @@ fn compact_python_error(stderr: &[u8]) -> String {
     }
 }
 
+/// huggingface_hub cache layout used by `from_pretrained(cache_dir=...)`
+fn weights_dir(cache_dir: &Path, model_ref: &str) -> PathBuf {
+    cache_dir.join(format!("models--{}", model_ref.replace('/', "--")))
+}
+
+/// Weights and ready marker of `model_ref`; the shared venv is not included.
+pub(crate) fn model_cache_entries(model_ref: &str) -> Result<Vec<PathBuf>> {
+    let mut entries = vec![weights_dir(&mlx_cache_dir()?, model_ref)];
+    entries.extend(marker_file_path(model_ref)?.parent().map(Path::to_path_buf));
+    Ok(entries)
+}
+
 fn marker_file_path(model_ref: &str) -> Result<PathBuf> {
     Ok(mlx_cache_dir()?
         .join(".downloaded")
@@ -441,15 +851,21 @@ fn sanitize_model_ref(model_ref: &str) -> String {
         .collect()
 }
 
-fn mlx_cache_dir() -> Result<PathBuf> {
+pub(crate) fn mlx_cache_dir() -> Result<PathBuf> {
     Ok(base_model_cache_dir()?.join("mlx"))
 }
 
+/// `OPENWISPR_MLX_VENV` when set, else `.venv` under the MLX cache.
+pub(crate) fn mlx_venv_dir() -> Result<PathBuf> {
+    if let Some(venv_dir) = venv_override() {
+        return Ok(venv_dir);
+    }
+    Ok(mlx_cache_dir()?.join(MLX_VENV_DIR))
+}
+
 fn base_model_cache_dir() -> Result<PathBuf> {
-    if let Ok(override_dir) = std::env::var("OPENWISPR_MODEL_DIR") {
-        if !override_dir.trim().is_empty() {
-            return Ok(PathBuf::from(override_dir));
-        }
+    if let Some(override_dir) = crate::model_dir_override() {
+        return Ok(override_dir);
     }
 
     if let Ok(home) = std::env::var("HOME") {
@@ -505,3 +921,77 @@ fn write_mono_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()>
     })?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requirements_satisfied_matches_normalized_freeze_output() {
+        let freeze =
+            format!("mlx=={MLX_VERSION}\nnumpy==2.1.0\nParakeet_MLX=={PARAKEET_MLX_VERSION}\n");
+        let installed = installed_versions(&freeze);
+        assert_eq!(installed.len(), 2, "{installed:?}");
+        assert!(requirements_satisfied(&installed));
+    }
+
+    #[test]
+    fn pip_install_args_pin_every_package() {
+        let args = pip_install_args();
+        assert_eq!(args[..3], ["-m", "pip", "install"]);
+        let pins: BTreeMap<String, String> = args[3..]
+            .iter()
+            .map(|arg| {
+                let (package, version) = arg.split_once("==").expect("unpinned pip argument");
+                (package.to_string(), version.to_string())
+            })
+            .collect();
+        assert_eq!(
+            pins.get("parakeet-mlx").map(String::as_str),
+            Some(PARAKEET_MLX_VERSION)
+        );
+        assert_eq!(pins.get("mlx").map(String::as_str), Some(MLX_VERSION));
+        assert!(pins.values().all(|version| !version.is_empty()));
+    }
+
+    #[test]
+    fn parses_python_version_output() {
+        assert_eq!(parse_python_version("Python 3.11.6\n"), Some((3, 11, 6)));
+        assert_eq!(parse_python_version("Python 3.13.0rc2"), Some((3, 13, 0)));
+        assert_eq!(parse_python_version("Python 3.8"), Some((3, 8, 0)));
+        assert_eq!(parse_python_version("python: command not found"), None);
+    }
+
+    #[test]
+    fn missing_runtime_recognizes_common_failures() {
+        let component = |output: &str| match missing_runtime(output) {
+            Some(SttError::RuntimeMissing { component, .. }) => Some(component),
+            _ => None,
+        };
+        assert_eq!(
+            component("xcode-select: note: No developer tools were found, requesting install.")
+                .as_deref(),
+            Some("Xcode Command Line Tools")
+        );
+        assert_eq!(
+            component("Error: Command '-m ensurepip' returned non-zero exit status 1.\nensurepip is not available").as_deref(),
+            Some("Python venv module")
+        );
+        assert_eq!(
+            component("WARNING: Retrying ... NewConnectionError: Failed to establish a new connection: [Errno 8] nodename nor servname provided").as_deref(),
+            Some("Network access to PyPI")
+        );
+        assert_eq!(
+            component("ModuleNotFoundError: No module named 'mlx.core'").as_deref(),
+            Some("MLX runtime")
+        );
+        assert_eq!(component("ValueError: bad wav header"), None);
+    }
+
+    #[test]
+    fn requirements_satisfied_detects_drift() {
+        let freeze = format!("mlx==0.0.1\nparakeet-mlx=={PARAKEET_MLX_VERSION}\n");
+        assert!(!requirements_satisfied(&installed_versions(&freeze)));
+        assert!(!requirements_satisfied(&installed_versions("")));
+    }
+}
@@ -0,0 +1,441 @@
+//! Long-lived Python process that keeps a Parakeet MLX model resident between
+//! transcriptions, so each decode skips interpreter startup and `from_pretrained`.
+//!
+//! The protocol is line-delimited JSON over stdin/stdout: the worker prints
+//! `{"ready": true}` once the model is loaded, then answers every `{"wav_path": ...}` with
+//! `{"text": ..., "language": ..., "segments": [{"text", "start", "end", "words"}]}` or
+//! `{"error": ...}`, where `language` is null unless the model reports one. While
+//! decoding it may send any number of `{"partial": ...}` lines with the text so far and
+//! `{"decoding": true}` heartbeats; neither ends the request. A worker that exits is
+//! started again on the next request.
+
+use super::mlx_parakeet::missing_runtime;
+use crate::{DecodeProgress, Result, SttError};
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use tracing::{info, warn};
+
+const WORKER_SCRIPT: &str = r#"
+import json
+import sys
+import threading
+
+protocol = sys.stdout
+# Library output goes to stderr so it cannot corrupt the protocol stream
+sys.stdout = sys.stderr
+
+
+write_lock = threading.Lock()
+# Seconds of audio fed to the streaming decoder between partial lines
+PARTIAL_STEP_S = 2
+
+
+def reply(message):
+    with write_lock:
+        protocol.write(json.dumps(message) + "\n")
+        protocol.flush()
+
+
+def heartbeat(done):
+    while not done.wait(1.0):
+        reply({"decoding": True})
+
+
+def text_of(result):
+    text = getattr(result, "text", None)
+    if text is None and isinstance(result, dict):
+        text = result.get("text")
+    if text is None and hasattr(result, "__dict__"):
+        text = result.__dict__.get("text")
+    if text is None:
+        text = str(result)
+    return (text or "").strip()
+
+
+def words_of(sentence):
+    # Token text starting with a space begins a new word; anything else extends it
+    words = []
+    for token in getattr(sentence, "tokens", None) or []:
+        if words and not token.text.startswith(" "):
+            words[-1]["text"] += token.text
+            words[-1]["end"] = float(token.end)
+        else:
+            words.append(
+                {"text": token.text, "start": float(token.start), "end": float(token.end)}
+            )
+    return [dict(word, text=word["text"].strip()) for word in words if word["text"].strip()]
+
+
+def language_of(result):
+    language = getattr(result, "language", None)
+    if language is None and isinstance(result, dict):
+        language = result.get("language")
+    return language or None
+
+
+try:
+    from parakeet_mlx import from_pretrained
+
+    model = from_pretrained(sys.argv[1], cache_dir=sys.argv[2])
+except Exception as exc:
+    reply({"error": f"{type(exc).__name__}: {exc}"})
+    sys.exit(1)
+reply({"ready": True})
+
+
+def decode(wav_path):
+    # Older parakeet-mlx releases have no streaming decoder; heartbeats still flow
+    try:
+        from parakeet_mlx.audio import load_audio
+
+        sample_rate = model.preprocessor_config.sample_rate
+        audio = load_audio(wav_path, sample_rate)
+        transcribe_stream = model.transcribe_stream
+    except (ImportError, AttributeError):
+        return model.transcribe(wav_path)
+
+    step = sample_rate * PARTIAL_STEP_S
+    last = ""
+    with transcribe_stream() as transcriber:
+        for start in range(0, len(audio), step):
+            transcriber.add_audio(audio[start : start + step])
+            text = text_of(transcriber.result)
+            if text and text != last:
+                reply({"partial": text})
+                last = text
+        return transcriber.result
+
+
+for line in sys.stdin:
+    if not line.strip():
+        continue
+    done = threading.Event()
+    beat = threading.Thread(target=heartbeat, args=(done,), daemon=True)
+    beat.start()
+    try:
+        result = decode(json.loads(line)["wav_path"])
+        done.set()
+        beat.join()
+        segments = [
+            {
+                "text": s.text.strip(),
+                "start": float(s.start),
+                "end": float(s.end),
+                "words": words_of(s),
+            }
+            for s in getattr(result, "sentences", None) or []
+        ]
+        reply(
+            {
+                "text": text_of(result),
+                "language": language_of(result),
+                "segments": segments,
+            }
+        )
+    except Exception as exc:
+        done.set()
+        beat.join()
+        reply({"error": f"{type(exc).__name__}: {exc}"})
+"#;
+
+#[derive(Debug, Deserialize)]
+struct WorkerReply {
+    #[serde(default)]
+    ready: bool,
+    error: Option<String>,
+    partial: Option<String>,
+    #[serde(default)]
+    decoding: bool,
+    #[serde(default)]
+    text: String,
+    language: Option<String>,
+    #[serde(default)]
+    segments: Vec<WorkerSegment>,
+}
+
+/// Sentence with its timings in seconds from the start of the decoded audio.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct WorkerSegment {
+    pub(crate) text: String,
+    pub(crate) start: f64,
+    pub(crate) end: f64,
+    /// Words joined from the aligned tokens; empty when the model has no token timings.
+    #[serde(default)]
+    pub(crate) words: Vec<WorkerWord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct WorkerWord {
+    pub(crate) text: String,
+    pub(crate) start: f64,
+    pub(crate) end: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WorkerTranscript {
+    pub(crate) text: String,
+    /// Language code reported by the model, if it detects one.
+    pub(crate) language: Option<String>,
+    /// Sentence timings reported by parakeet-mlx; may be empty.
+    pub(crate) segments: Vec<WorkerSegment>,
+}
+
+/// Supervises the worker process, starting it again whenever it has died.
+pub(crate) struct MlxWorker {
+    command: Vec<String>,
+    process: Option<WorkerProcess>,
+    restarts: u32,
+}
+
+impl MlxWorker {
+    /// Starts a worker with `model_ref` loaded, returning once the model is ready.
+    pub(crate) fn start(python_bin: &Path, model_ref: &str, cache_dir: &Path) -> Result<Self> {
+        Self::with_command(vec![
+            python_bin.to_string_lossy().to_string(),
+            "-c".to_string(),
+            WORKER_SCRIPT.to_string(),
+            model_ref.to_string(),
+            cache_dir.to_string_lossy().to_string(),
+        ])
+    }
+
+    fn with_command(command: Vec<String>) -> Result<Self> {
+        let process = WorkerProcess::spawn(&command)?;
+        info!("MLX worker started (pid {})", process.child.id());
+        Ok(Self {
+            command,
+            process: Some(process),
+            restarts: 0,
+        })
+    }
+
+    /// Transcribes a 16-bit mono wav, passing partial lines and heartbeats to `on_progress`.
+    /// A worker that died since the last call, or dies while answering, is restarted and the
+    /// request sent once more.
+    pub(crate) fn transcribe(
+        &mut self,
+        wav_path: &Path,
+        on_progress: &dyn Fn(DecodeProgress),
+    ) -> Result<WorkerTranscript> {
+        let reply = match self.request(wav_path, on_progress) {
+            Ok(reply) => reply,
+            Err(err) => {
+                warn!("MLX worker stopped responding ({err}); restarting");
+                self.restart()?;
+                self.request(wav_path, on_progress).map_err(|err| {
+                    SttError::TranscriptionFailed(format!("MLX worker failed after restart: {err}"))
+                })?
+            }
+        };
+        if let Some(error) = reply.error {
+            return Err(SttError::TranscriptionFailed(format!(
+                "MLX transcription failed: {error}"
+            )));
+        }
+        Ok(WorkerTranscript {
+            text: reply.text.trim().to_string(),
+            language: reply.language,
+            segments: reply.segments,
+        })
+    }
+
+    fn request(
+        &mut self,
+        wav_path: &Path,
+        on_progress: &dyn Fn(DecodeProgress),
+    ) -> std::io::Result<WorkerReply> {
+        let Some(process) = self.process.as_mut() else {
+            return Err(std::io::Error::other("worker is not running"));
+        };
+        if !process.is_running() {
+            return Err(std::io::Error::other("worker is not running"));
+        }
+        let request = serde_json::json!({ "wav_path": wav_path.to_string_lossy() });
+        writeln!(process.stdin, "{request}")?;
+        process.stdin.flush()?;
+        loop {
+            let reply = process.read_reply()?;
+            if let Some(partial) = reply.partial {
+                on_progress(DecodeProgress::Partial(partial));
+            } else if reply.decoding {
+                on_progress(DecodeProgress::Decoding);
+            } else {
+                return Ok(reply);
+            }
+        }
+    }
+
+    fn restart(&mut self) -> Result<()> {
+        // Drop (and kill) the old process before loading the model a second time
+        self.process = None;
+        let process = WorkerProcess::spawn(&self.command)?;
+        self.restarts += 1;
+        info!(
+            "MLX worker restarted (pid {}, restart #{})",
+            process.child.id(),
+            self.restarts
+        );
+        self.process = Some(process);
+        Ok(())
+    }
+}
+
+struct WorkerProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl WorkerProcess {
+    fn spawn(command: &[String]) -> Result<Self> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| SttError::ModelLoadError("empty MLX worker command".into()))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| {
+                SttError::ModelLoadError(format!(
+                    "failed to start MLX Python runtime ({program}): {e}"
+                ))
+            })?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(SttError::ModelLoadError(
+                "MLX worker started without stdio pipes".into(),
+            ));
+        };
+        let mut process = Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        };
+
+        let ready = process.read_reply().map_err(|e| {
+            SttError::ModelLoadError(format!("MLX worker exited while loading the model: {e}"))
+        })?;
+        if let Some(error) = ready.error {
+            return Err(missing_runtime(&error).unwrap_or_else(|| {
+                SttError::ModelLoadError(format!("MLX worker failed to load the model: {error}"))
+            }));
+        }
+        if !ready.ready {
+            return Err(SttError::ModelLoadError(
+                "MLX worker answered before reporting ready".into(),
+            ));
+        }
+        Ok(process)
+    }
+
+    fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Next protocol message, skipping any stray non-JSON output.
+    fn read_reply(&mut self) -> std::io::Result<WorkerReply> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "worker exited",
+                ));
+            }
+            if let Ok(reply) = serde_json::from_str::<WorkerReply>(line.trim()) {
+                return Ok(reply);
+            }
+        }
+    }
+}
+
+impl Drop for WorkerProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fake worker that answers a single request and then exits.
+    fn one_shot_worker() -> Vec<String> {
+        let script = r#"echo 'loading'; echo '{"ready": true}'; read request; echo '{"text": " hi ", "segments": [{"text": "hi", "start": 0.0, "end": 0.5, "words": [{"text": "hi", "start": 0.1, "end": 0.4}]}]}'"#;
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    #[test]
+    fn restarts_a_worker_that_exited() {
+        let mut worker = MlxWorker::with_command(one_shot_worker()).unwrap();
+        let wav = Path::new("/tmp/clip.wav");
+        let expected = WorkerTranscript {
+            text: "hi".to_string(),
+            language: None,
+            segments: vec![WorkerSegment {
+                text: "hi".to_string(),
+                start: 0.0,
+                end: 0.5,
+                words: vec![WorkerWord {
+                    text: "hi".to_string(),
+                    start: 0.1,
+                    end: 0.4,
+                }],
+            }],
+        };
+
+        assert_eq!(worker.transcribe(wav, &|_| {}).unwrap(), expected);
+        assert_eq!(worker.transcribe(wav, &|_| {}).unwrap(), expected);
+        assert_eq!(worker.restarts, 1);
+    }
+
+    #[test]
+    fn forwards_partials_and_heartbeats_before_the_result() {
+        let script = r#"echo '{"ready": true}'; read request; echo '{"decoding": true}'; echo '{"partial": "hello"}'; echo '{"text": "hello world", "language": "en", "segments": []}'"#;
+        let mut worker =
+            MlxWorker::with_command(vec!["sh".into(), "-c".into(), script.into()]).unwrap();
+        let updates = std::sync::Mutex::new(Vec::new());
+        let transcript = worker
+            .transcribe(Path::new("/tmp/clip.wav"), &|update| {
+                updates.lock().unwrap().push(update)
+            })
+            .unwrap();
+
+        assert_eq!(transcript.text, "hello world");
+        assert_eq!(transcript.language.as_deref(), Some("en"));
+        assert_eq!(
+            updates.into_inner().unwrap(),
+            [
+                DecodeProgress::Decoding,
+                DecodeProgress::Partial("hello".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_load_and_transcription_errors() {
+        let failing = vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            r#"echo '{"error": "ModuleNotFoundError: parakeet_mlx"}'"#.to_string(),
+        ];
+        let Err(err) = MlxWorker::with_command(failing) else {
+            panic!("worker should fail to start");
+        };
+        assert!(err.to_string().contains("parakeet_mlx"), "{err}");
+
+        let script = r#"echo '{"ready": true}'; read request; echo '{"error": "bad wav"}'"#;
+        let mut worker =
+            MlxWorker::with_command(vec!["sh".into(), "-c".into(), script.into()]).unwrap();
+        let err = worker
+            .transcribe(Path::new("/tmp/clip.wav"), &|_| {})
+            .unwrap_err();
+        assert!(err.to_string().contains("bad wav"), "{err}");
+    }
+}
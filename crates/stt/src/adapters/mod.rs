@@ -1,18 +1,27 @@
 /// Platform-specific STT adapter implementations
 
-#[cfg(any(target_os = "macos", target_os = "windows"))]
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
 pub(crate) mod backend;
 
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 pub(crate) mod sherpa;
 
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+pub(crate) mod stream;
+
 #[cfg(target_os = "macos")]
 pub(crate) mod mlx_parakeet;
 
+#[cfg(target_os = "macos")]
+pub(crate) mod mlx_worker;
+
 #[cfg(target_os = "macos")]
 pub mod mlx;
 
 #[cfg(target_os = "windows")]
 pub mod whisper;
 
+#[cfg(target_os = "linux")]
+pub mod linux;
+
 pub mod fallback;
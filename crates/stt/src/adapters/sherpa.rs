@@ -1,21 +1,31 @@
 use crate::{
-    emit_model_download_progress, is_sherpa_model_name, AudioFormat, ModelDownloadProgress, Result,
-    SttConfig, SttError, TranscriptSegment, Transcription,
+    current_progress_scope, emit_model_download_progress, is_sherpa_model_name,
+    with_download_scope, AudioFormat, ModelDescriptor, ModelDownloadProgress, Result,
+    SherpaProvider, SttConfig, SttError, SttStream, TranscriptSegment, Transcription,
+    TranscriptionTask, SHERPA_PARAKEET_INT8_MODEL,
 };
 use bzip2::read::BzDecoder;
+use platform::checksum::github_release_sha256;
+use platform::download::{fetch_model_file, DownloadError};
 use sherpa_rs::transducer::{TransducerConfig, TransducerRecognizer};
 use std::fs::{self, File};
-use std::io::{BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
+use tracing::{info, warn};
 
-use super::backend::{prepare_audio, TARGET_SAMPLE_RATE};
+use super::backend::{
+    emit_download_cancelled, estimate_quality, prepare_audio, TARGET_SAMPLE_RATE,
+};
+use super::stream::{ChunkDecoder, WindowedStream};
 
+/// The model ships as one release archive (encoder, decoder, joiner and tokens together), so
+/// there is a single transfer to resume and verify rather than one per file.
 const SHERPA_PARKEET_RELEASE_ARCHIVE: &str = "sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8.tar.bz2";
 const SHERPA_PARKEET_RELEASE_DIR: &str = "sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8";
 const SHERPA_PARKEET_RELEASE_URL: &str =
     "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8.tar.bz2";
+
 const SHERPA_REQUIRED_FILES: &[&str] = &[
     "encoder.int8.onnx",
     "decoder.int8.onnx",
@@ -41,17 +51,35 @@ impl SharedSherpaAdapter {
         }
     }
 
+    pub(crate) fn model_descriptor(&self) -> ModelDescriptor {
+        ModelDescriptor {
+            name: SHERPA_PARAKEET_INT8_MODEL.to_string(),
+            runtime: "sherpa-onnx".to_string(),
+            approx_size_mb: 640,
+            languages: vec!["en".to_string()],
+            multilingual: false,
+            recommended: true,
+            note: Some("NVIDIA Parakeet TDT v2 int8".to_string()),
+        }
+    }
+
     pub(crate) async fn initialize(&self, config: SttConfig) -> Result<()> {
+        // Parakeet is an English-only transcriber
+        if matches!(config.task, TranscriptionTask::Translate) {
+            return Err(SttError::TranslationUnsupported(config.model_name));
+        }
+        let scope = current_progress_scope();
         let model_root = tokio::task::spawn_blocking({
             let config = config.clone();
-            move || resolve_model_root(&config)
+            move || with_download_scope(scope, || resolve_model_root(&config))
         })
         .await
         .map_err(|e| SttError::ModelLoadError(format!("sherpa model path task failed: {e}")))??;
 
         let recognizer = tokio::task::spawn_blocking({
             let model_root = model_root.clone();
-            move || create_recognizer(&model_root)
+            let provider = config.sherpa_provider;
+            move || create_recognizer(&model_root, provider)
         })
         .await
         .map_err(|e| SttError::ModelLoadError(format!("sherpa init task failed: {e}")))??;
@@ -63,20 +91,25 @@ impl SharedSherpaAdapter {
         Ok(())
     }
 
+    pub(crate) async fn unload(&self) {
+        *self.state.write().await = SherpaState::default();
+    }
+
     pub(crate) async fn transcribe(
         &self,
         audio_data: &[f32],
         format: AudioFormat,
     ) -> Result<Transcription> {
-        let recognizer = {
+        let (recognizer, config) = {
             let state = self.state.read().await;
-            state
+            let recognizer = state
                 .recognizer
                 .clone()
-                .ok_or_else(|| SttError::TranscriptionFailed("sherpa adapter not initialized".into()))?
+                .ok_or_else(|| SttError::TranscriptionFailed("sherpa adapter not initialized".into()))?;
+            (recognizer, state.config.clone().unwrap_or_default())
         };
 
-        let prepared = prepare_audio(audio_data, &format);
+        let prepared = prepare_audio(audio_data, &format, &config);
         if prepared.is_empty() {
             return Err(SttError::AudioError(
                 "no audio samples available after preprocessing".into(),
@@ -84,11 +117,12 @@ impl SharedSherpaAdapter {
         }
 
         let duration_s = prepared.len() as f64 / TARGET_SAMPLE_RATE as f64;
-        let text = tokio::task::spawn_blocking(move || {
+        let (text, prepared) = tokio::task::spawn_blocking(move || {
             let mut guard = recognizer.lock().map_err(|_| {
                 SttError::TranscriptionFailed("failed to lock sherpa recognizer".into())
             })?;
-            Ok::<String, SttError>(guard.transcribe(TARGET_SAMPLE_RATE, &prepared))
+            let text = guard.transcribe(TARGET_SAMPLE_RATE, &prepared);
+            Ok::<_, SttError>((text, prepared))
         })
         .await
         .map_err(|e| SttError::TranscriptionFailed(format!("sherpa decode task failed: {e}")))??;
@@ -100,17 +134,62 @@ impl SharedSherpaAdapter {
                 text: clean.clone(),
                 start: 0.0,
                 end: duration_s,
+                confidence: None,
+                words: Vec::new(),
             });
         }
 
+        let quality = estimate_quality(&prepared, &clean);
         Ok(Transcription {
             text: clean,
             language: Some("en".to_string()),
+            language_confidence: None,
             confidence: None,
+            quality,
+            scores: None,
             segments,
         })
     }
 
+    pub(crate) fn create_stream(&self, format: AudioFormat) -> Result<Box<dyn SttStream>> {
+        let state = self
+            .state
+            .try_read()
+            .map_err(|_| SttError::TranscriptionFailed("sherpa adapter is busy loading".into()))?;
+        let recognizer = state
+            .recognizer
+            .clone()
+            .ok_or_else(|| SttError::TranscriptionFailed("sherpa adapter not initialized".into()))?;
+        let normalization = state
+            .config
+            .as_ref()
+            .map(|config| config.normalization)
+            .unwrap_or_default();
+        Ok(Box::new(WindowedStream::new(
+            SherpaDecoder { recognizer },
+            format,
+            Some("en".to_string()),
+            normalization,
+        )))
+    }
+
+    /// Fetches and unpacks the model files without creating a recognizer.
+    pub(crate) async fn download_only(&self, model_name: &str) -> Result<()> {
+        let config = SttConfig {
+            model_name: model_name.to_string(),
+            ..Default::default()
+        };
+        let scope = current_progress_scope();
+        tokio::task::spawn_blocking(move || {
+            with_download_scope(scope, || resolve_model_root(&config))
+        })
+        .await
+        .map_err(|e| {
+            SttError::ModelLoadError(format!("sherpa model download task failed: {e}"))
+        })??;
+        Ok(())
+    }
+
     pub(crate) async fn is_model_available(&self, model_name: &str) -> bool {
         if looks_like_model_dir(model_name) {
             return has_required_files(Path::new(model_name));
@@ -125,7 +204,30 @@ impl SharedSherpaAdapter {
 
 }
 
-fn create_recognizer(model_root: &Path) -> Result<TransducerRecognizer> {
+/// Creates the recognizer on `provider`, retrying on CPU when a GPU provider fails.
+fn create_recognizer(model_root: &Path, provider: SherpaProvider) -> Result<TransducerRecognizer> {
+    if provider != SherpaProvider::Cpu {
+        match new_recognizer(model_root, provider) {
+            Ok(recognizer) => {
+                info!(
+                    "sherpa recognizer initialized with provider={}",
+                    provider.as_str()
+                );
+                return Ok(recognizer);
+            }
+            Err(err) => warn!(
+                "failed to initialize sherpa with provider={}; retrying on CPU: {}",
+                provider.as_str(),
+                err
+            ),
+        }
+    }
+    let recognizer = new_recognizer(model_root, SherpaProvider::Cpu)?;
+    info!("sherpa recognizer initialized with provider=cpu");
+    Ok(recognizer)
+}
+
+fn new_recognizer(model_root: &Path, provider: SherpaProvider) -> Result<TransducerRecognizer> {
     let cfg = TransducerConfig {
         encoder: model_root
             .join("encoder.int8.onnx")
@@ -142,7 +244,7 @@ fn create_recognizer(model_root: &Path) -> Result<TransducerRecognizer> {
         sample_rate: TARGET_SAMPLE_RATE as i32,
         feature_dim: 80,
         num_threads: optimal_threads(),
-        provider: Some("cpu".to_string()),
+        provider: Some(provider.as_str().to_string()),
         ..Default::default()
     };
 
@@ -186,6 +288,20 @@ fn resolve_model_root(config: &SttConfig) -> Result<PathBuf> {
     ensure_model_downloaded()
 }
 
+/// sherpa-rs only exposes offline transducer decoding, so streams decode window by window.
+struct SherpaDecoder {
+    recognizer: Arc<Mutex<TransducerRecognizer>>,
+}
+
+impl ChunkDecoder for SherpaDecoder {
+    fn decode(&mut self, samples: &[f32], _committed: &str) -> String {
+        match self.recognizer.lock() {
+            Ok(mut recognizer) => recognizer.transcribe(TARGET_SAMPLE_RATE, samples),
+            Err(_) => String::new(),
+        }
+    }
+}
+
 fn ensure_model_downloaded() -> Result<PathBuf> {
     let model_name = "sherpa-onnx/parakeet-tdt-0.6b-v2-int8".to_string();
     let root = sherpa_model_root_dir()?;
@@ -215,110 +331,45 @@ fn ensure_model_downloaded() -> Result<PathBuf> {
         ))
     })?;
 
-    let archive_tmp = cache_dir.join(format!("{SHERPA_PARKEET_RELEASE_ARCHIVE}.download"));
-    let response = ureq::get(SHERPA_PARKEET_RELEASE_URL)
-        .call()
-        .map_err(|e| {
-            let message = format!(
-                "failed to download sherpa model {}: {e}",
-                SHERPA_PARKEET_RELEASE_URL
-            );
-            emit_model_download_progress(ModelDownloadProgress {
-                model_name: model_name.clone(),
-                stage: "download".to_string(),
-                downloaded_bytes: 0,
-                total_bytes: None,
-                percent: None,
-                done: true,
-                error: Some(message.clone()),
-                message: Some("Download request failed".to_string()),
-            });
-            SttError::ModelLoadError(message)
-        })?;
-    let total_bytes = response
-        .header("Content-Length")
-        .and_then(|v| v.parse::<u64>().ok())
-        .filter(|v| *v > 0);
-    emit_model_download_progress(ModelDownloadProgress {
-        model_name: model_name.clone(),
-        stage: "download".to_string(),
-        downloaded_bytes: 0,
-        total_bytes,
-        percent: Some(0.0),
-        done: false,
-        error: None,
-        message: Some("Downloading sherpa model".to_string()),
-    });
-
-    let mut reader = response.into_reader();
-    let mut writer = BufWriter::new(File::create(&archive_tmp).map_err(|e| {
-        SttError::ModelLoadError(format!(
-            "failed to create temporary archive {}: {e}",
-            archive_tmp.display()
-        ))
-    })?);
-    let mut downloaded_bytes = 0_u64;
-    let mut last_emitted = 0_u64;
-    let mut buffer = [0_u8; 64 * 1024];
-    loop {
-        let n = reader.read(&mut buffer).map_err(|e| {
-            let message = format!("failed while reading sherpa model stream: {e}");
-            emit_model_download_progress(ModelDownloadProgress {
-                model_name: model_name.clone(),
-                stage: "download".to_string(),
-                downloaded_bytes,
-                total_bytes,
-                percent: total_bytes.map(|t| ((downloaded_bytes as f32 / t as f32) * 100.0).min(100.0)),
-                done: true,
-                error: Some(message.clone()),
-                message: Some("Download stream read failed".to_string()),
-            });
-            SttError::ModelLoadError(message)
-        })?;
-        if n == 0 {
-            break;
-        }
-        writer.write_all(&buffer[..n]).map_err(|e| {
-            let message = format!(
-                "failed while writing sherpa archive {}: {e}",
-                archive_tmp.display()
-            );
-            emit_model_download_progress(ModelDownloadProgress {
-                model_name: model_name.clone(),
-                stage: "download".to_string(),
-                downloaded_bytes,
-                total_bytes,
-                percent: total_bytes.map(|t| ((downloaded_bytes as f32 / t as f32) * 100.0).min(100.0)),
-                done: true,
-                error: Some(message.clone()),
-                message: Some("Write to temporary file failed".to_string()),
-            });
-            SttError::ModelLoadError(message)
-        })?;
-        downloaded_bytes += n as u64;
-        if downloaded_bytes.saturating_sub(last_emitted) >= 256 * 1024
-            || total_bytes.is_some_and(|total| downloaded_bytes >= total)
-        {
-            emit_model_download_progress(ModelDownloadProgress {
-                model_name: model_name.clone(),
-                stage: "download".to_string(),
-                downloaded_bytes,
-                total_bytes,
-                percent: total_bytes.map(|t| ((downloaded_bytes as f32 / t as f32) * 100.0).min(100.0)),
-                done: false,
-                error: None,
-                message: Some("Downloading sherpa model".to_string()),
-            });
-            last_emitted = downloaded_bytes;
+    // Every model file comes out of this one archive, so verifying it covers them all.
+    let archive_path = cache_dir.join(SHERPA_PARKEET_RELEASE_ARCHIVE);
+    let expected_sha256 = github_release_sha256(
+        "k2-fsa/sherpa-onnx",
+        "asr-models",
+        SHERPA_PARKEET_RELEASE_ARCHIVE,
+    );
+    let downloaded_bytes = fetch_model_file(
+        &model_name,
+        SHERPA_PARKEET_RELEASE_URL,
+        &archive_path,
+        expected_sha256.as_deref(),
+        None,
+        "Downloading sherpa model",
+        &emit_model_download_progress,
+    )
+    .map_err(|e| {
+        if matches!(e, DownloadError::Cancelled) {
+            emit_download_cancelled(&model_name);
+            return SttError::Cancelled;
         }
-    }
-
-    writer.flush().map_err(|e| {
-        SttError::ModelLoadError(format!(
-            "failed to flush sherpa archive {}: {e}",
-            archive_tmp.display()
-        ))
+        let message = format!("failed to download sherpa model: {e}");
+        let stage = match e {
+            DownloadError::Checksum { .. } => "verify",
+            _ => "download",
+        };
+        emit_model_download_progress(ModelDownloadProgress {
+            model_name: model_name.clone(),
+            stage: stage.to_string(),
+            downloaded_bytes: 0,
+            total_bytes: None,
+            percent: None,
+            done: true,
+            error: Some(message.clone()),
+            message: Some("Sherpa model download failed".to_string()),
+        });
+        SttError::ModelLoadError(message)
     })?;
+    let total_bytes = Some(downloaded_bytes);
 
     let unpack_dir = cache_dir.join(format!("{SHERPA_PARKEET_RELEASE_DIR}.unpack"));
     if unpack_dir.exists() {
@@ -331,10 +382,10 @@ fn ensure_model_downloaded() -> Result<PathBuf> {
         ))
     })?;
 
-    let archive_file = File::open(&archive_tmp).map_err(|e| {
+    let archive_file = File::open(&archive_path).map_err(|e| {
         SttError::ModelLoadError(format!(
             "failed to open downloaded archive {}: {e}",
-            archive_tmp.display()
+            archive_path.display()
         ))
     })?;
     emit_model_download_progress(ModelDownloadProgress {
@@ -352,7 +403,7 @@ fn ensure_model_downloaded() -> Result<PathBuf> {
     archive.unpack(&unpack_dir).map_err(|e| {
         SttError::ModelLoadError(format!(
             "failed to extract sherpa archive {}: {e}",
-            archive_tmp.display()
+            archive_path.display()
         ))
     })?;
 
@@ -386,7 +437,7 @@ fn ensure_model_downloaded() -> Result<PathBuf> {
     })?;
 
     let _ = fs::remove_dir_all(&unpack_dir);
-    let _ = fs::remove_file(&archive_tmp);
+    let _ = fs::remove_file(&archive_path);
 
     if !has_required_files(&root) {
         return Err(SttError::ModelLoadError(format!(
@@ -416,19 +467,17 @@ fn has_required_files(root: &Path) -> bool {
             .all(|name| root.join(name).exists())
 }
 
-fn sherpa_cache_dir() -> Result<PathBuf> {
+pub(crate) fn sherpa_cache_dir() -> Result<PathBuf> {
     Ok(base_model_cache_dir()?.join("sherpa-onnx"))
 }
 
-fn sherpa_model_root_dir() -> Result<PathBuf> {
+pub(crate) fn sherpa_model_root_dir() -> Result<PathBuf> {
     Ok(sherpa_cache_dir()?.join(SHERPA_PARKEET_RELEASE_DIR))
 }
 
 fn base_model_cache_dir() -> Result<PathBuf> {
-    if let Ok(override_dir) = std::env::var("OPENWISPR_MODEL_DIR") {
-        if !override_dir.trim().is_empty() {
-            return Ok(PathBuf::from(override_dir));
-        }
+    if let Some(override_dir) = crate::model_dir_override() {
+        return Ok(override_dir);
     }
 
     #[cfg(target_os = "windows")]
@@ -471,3 +520,23 @@ fn optimal_threads() -> i32 {
         .map(|n| n.get().min(8))
         .unwrap_or(4) as i32
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn required_files_include_the_separate_joiner() {
+        let root = std::env::temp_dir().join(format!("openwispr-sherpa-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        for name in ["encoder.int8.onnx", "decoder.int8.onnx", "tokens.txt"] {
+            fs::write(root.join(name), b"").unwrap();
+        }
+        // The decoder must not stand in for a missing joiner
+        assert!(!has_required_files(&root));
+
+        fs::write(root.join("joiner.int8.onnx"), b"").unwrap();
+        assert!(has_required_files(&root));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,209 @@
+//! Incremental decoding shared by the sherpa and whisper streams.
+//!
+//! Neither runtime exposes a true streaming decoder through our bindings, so a stream keeps
+//! the 16 kHz audio fed so far, re-decodes only the uncommitted tail for partials and freezes
+//! that tail (cut at a quiet frame) once it reaches [`STREAM_COMMIT_WINDOW_SECS`]. Each
+//! partial therefore costs at most one window, however long the recording gets.
+
+use crate::{
+    AudioFormat, NormalizationTuning, Result, SttError, SttStream, TranscriptSegment, Transcription,
+};
+
+use super::backend::{estimate_quality, normalize_for_asr, StreamResampler, TARGET_SAMPLE_RATE};
+
+/// Uncommitted audio after which a stream freezes its tail, bounding each partial decode.
+const STREAM_COMMIT_WINDOW_SECS: usize = 8;
+/// How far back from the window end to look for a quiet frame to cut at.
+const STREAM_SPLIT_SEARCH_SECS: usize = 1;
+const STREAM_SPLIT_FRAME_SAMPLES: usize = TARGET_SAMPLE_RATE as usize / 50;
+
+/// Decodes one stretch of 16 kHz mono audio.
+pub(crate) trait ChunkDecoder: Send {
+    /// `committed` is the transcript so far, for decoders that can use it as context.
+    fn decode(&mut self, samples: &[f32], committed: &str) -> String;
+}
+
+pub(crate) struct WindowedStream<D> {
+    decoder: D,
+    resampler: StreamResampler,
+    language: Option<String>,
+    normalization: NormalizationTuning,
+    audio: Vec<f32>,
+    committed_samples: usize,
+    committed: Vec<TranscriptSegment>,
+}
+
+impl<D: ChunkDecoder> WindowedStream<D> {
+    /// `format` describes the samples passed to [`SttStream::feed`].
+    pub(crate) fn new(
+        decoder: D,
+        format: AudioFormat,
+        language: Option<String>,
+        normalization: NormalizationTuning,
+    ) -> Self {
+        Self {
+            decoder,
+            resampler: StreamResampler::new(&format),
+            language,
+            normalization,
+            audio: Vec::new(),
+            committed_samples: 0,
+            committed: Vec::new(),
+        }
+    }
+
+    fn decode(&mut self, start: usize, end: usize) -> String {
+        if start >= end {
+            return String::new();
+        }
+        let mut clip = self.audio[start..end].to_vec();
+        normalize_for_asr(&mut clip, &self.normalization);
+        let committed = self.committed_text();
+        self.decoder.decode(&clip, &committed).trim().to_string()
+    }
+
+    fn commit_full_windows(&mut self) {
+        let window = STREAM_COMMIT_WINDOW_SECS * TARGET_SAMPLE_RATE as usize;
+        while self.audio.len() - self.committed_samples >= window {
+            let start = self.committed_samples;
+            let search_from = window - STREAM_SPLIT_SEARCH_SECS * TARGET_SAMPLE_RATE as usize;
+            let end = start + quietest_split(&self.audio[start..start + window], search_from);
+            let text = self.decode(start, end);
+            if !text.is_empty() {
+                self.committed.push(TranscriptSegment {
+                    text,
+                    start: start as f64 / TARGET_SAMPLE_RATE as f64,
+                    end: end as f64 / TARGET_SAMPLE_RATE as f64,
+                    confidence: None,
+                    words: Vec::new(),
+                });
+            }
+            self.committed_samples = end;
+        }
+    }
+
+    fn committed_text(&self) -> String {
+        self.joined_with("")
+    }
+
+    fn joined_with(&self, tail: &str) -> String {
+        self.committed
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .chain(std::iter::once(tail))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl<D: ChunkDecoder> SttStream for WindowedStream<D> {
+    fn feed(&mut self, samples: &[f32]) {
+        let converted = self.resampler.push(samples);
+        self.audio.extend_from_slice(&converted);
+        self.commit_full_windows();
+    }
+
+    fn partial(&mut self) -> String {
+        let tail = self.decode(self.committed_samples, self.audio.len());
+        self.joined_with(&tail)
+    }
+
+    fn finalize(mut self: Box<Self>) -> Result<Transcription> {
+        let rest = self.resampler.flush();
+        self.audio.extend_from_slice(&rest);
+        if self.audio.is_empty() {
+            return Err(SttError::AudioError("no audio fed to stream".into()));
+        }
+        let tail = self.decode(self.committed_samples, self.audio.len());
+        let text = self.joined_with(&tail);
+        let mut segments = std::mem::take(&mut self.committed);
+        if !tail.is_empty() {
+            segments.push(TranscriptSegment {
+                text: tail,
+                start: self.committed_samples as f64 / TARGET_SAMPLE_RATE as f64,
+                end: self.audio.len() as f64 / TARGET_SAMPLE_RATE as f64,
+                confidence: None,
+                words: Vec::new(),
+            });
+        }
+        let quality = estimate_quality(&self.audio, &text);
+        Ok(Transcription {
+            text,
+            language: self.language.take(),
+            language_confidence: None,
+            confidence: None,
+            quality,
+            scores: None,
+            segments,
+        })
+    }
+}
+
+/// End of the lowest-energy frame at or after `search_from`, so commits avoid cutting words.
+fn quietest_split(samples: &[f32], search_from: usize) -> usize {
+    samples[search_from..]
+        .chunks(STREAM_SPLIT_FRAME_SAMPLES)
+        .enumerate()
+        .map(|(i, frame)| {
+            let energy = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+            (search_from + (i + 1) * STREAM_SPLIT_FRAME_SAMPLES, energy)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(end, _)| end.min(samples.len()))
+        .unwrap_or(samples.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quietest_split_cuts_at_silent_frame() {
+        let mut samples = vec![0.5_f32; TARGET_SAMPLE_RATE as usize];
+        let silent = 10 * STREAM_SPLIT_FRAME_SAMPLES;
+        samples[silent..silent + STREAM_SPLIT_FRAME_SAMPLES].fill(0.0);
+
+        assert_eq!(
+            quietest_split(&samples, 0),
+            silent + STREAM_SPLIT_FRAME_SAMPLES
+        );
+    }
+
+    /// Reports how many samples each call saw.
+    struct CountingDecoder {
+        calls: Vec<usize>,
+    }
+
+    impl ChunkDecoder for CountingDecoder {
+        fn decode(&mut self, samples: &[f32], _committed: &str) -> String {
+            self.calls.push(samples.len());
+            format!("w{}", self.calls.len())
+        }
+    }
+
+    #[test]
+    fn partials_only_redecode_the_uncommitted_tail() {
+        let format = AudioFormat {
+            sample_rate: TARGET_SAMPLE_RATE,
+            channels: 1,
+            ..Default::default()
+        };
+        let mut stream = WindowedStream::new(
+            CountingDecoder { calls: Vec::new() },
+            format,
+            None,
+            NormalizationTuning::default(),
+        );
+        let second = vec![0.1_f32; TARGET_SAMPLE_RATE as usize];
+        for _ in 0..20 {
+            stream.feed(&second);
+            stream.partial();
+        }
+
+        let window = STREAM_COMMIT_WINDOW_SECS * TARGET_SAMPLE_RATE as usize;
+        assert!(stream.decoder.calls.iter().all(|&len| len <= window));
+        assert_eq!(stream.committed.len(), 2);
+        assert!(stream.partial().starts_with("w"));
+    }
+}
@@ -2,7 +2,8 @@
 //! Routes between whisper.cpp and Sherpa ONNX based on selected model.
 
 use crate::{
-    is_sherpa_model_name, AudioFormat, Result, SttAdapter, SttConfig, SttError, Transcription,
+    is_sherpa_model_name, warm_up_clip, AudioFormat, ModelDescriptor, Result, SttAdapter,
+    SttConfig, SttError, SttStream, Transcription,
 };
 use async_trait::async_trait;
 use tracing::info;
@@ -44,6 +45,15 @@ impl SttAdapter for WhisperAdapter {
         Ok(())
     }
 
+    async fn unload(&mut self) -> Result<()> {
+        self.whisper.unload().await;
+        self.sherpa.unload().await;
+        if let Ok(mut slot) = self.current_model.lock() {
+            *slot = None;
+        }
+        Ok(())
+    }
+
     async fn transcribe(&self, audio_data: &[f32], format: AudioFormat) -> Result<Transcription> {
         let model_name = self
             .current_model
@@ -59,6 +69,14 @@ impl SttAdapter for WhisperAdapter {
         }
     }
 
+    async fn download_only(&self, model_name: &str) -> Result<()> {
+        if is_sherpa_model_name(model_name) {
+            self.sherpa.download_only(model_name).await
+        } else {
+            self.whisper.download_only(model_name).await
+        }
+    }
+
     async fn is_model_available(&self, model_name: &str) -> bool {
         if is_sherpa_model_name(model_name) {
             self.sherpa.is_model_available(model_name).await
@@ -67,13 +85,38 @@ impl SttAdapter for WhisperAdapter {
         }
     }
 
-    fn available_models(&self) -> Vec<String> {
-        self.whisper.available_models()
+    fn available_model_descriptors(&self) -> Vec<ModelDescriptor> {
+        let mut models = self.whisper.model_descriptors();
+        models.push(self.sherpa.model_descriptor());
+        models
+    }
+
+    async fn warm_up(&self) -> Result<()> {
+        let model_name = self
+            .current_model()
+            .ok_or_else(|| SttError::TranscriptionFailed("adapter not initialized".into()))?;
+        if is_sherpa_model_name(&model_name) {
+            let (clip, format) = warm_up_clip();
+            self.sherpa.transcribe(&clip, format).await.map(|_| ())
+        } else {
+            self.whisper.warm_up().await
+        }
     }
 
     fn current_model(&self) -> Option<String> {
         self.current_model.lock().ok().and_then(|slot| slot.clone())
     }
+
+    fn create_stream(&self, format: AudioFormat) -> Result<Box<dyn SttStream>> {
+        let model_name = self
+            .current_model()
+            .ok_or_else(|| SttError::TranscriptionFailed("adapter not initialized".into()))?;
+        if is_sherpa_model_name(&model_name) {
+            self.sherpa.create_stream(format)
+        } else {
+            self.whisper.create_stream(format)
+        }
+    }
 }
 
 impl Default for WhisperAdapter {
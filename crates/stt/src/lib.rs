@@ -2,11 +2,27 @@
 //! Provides a unified interface for different STT backends (MLX, whisper.cpp, etc.)
 
 use async_trait::async_trait;
-use std::path::PathBuf;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, OnceLock, RwLock};
 use thiserror::Error;
+use tokio::sync::mpsc;
 
 pub mod adapters;
+mod registry;
+mod vad;
+mod wav;
+mod wer;
+
+pub use platform::catalog::StagedImport;
+pub use platform::download::ModelDownloadProgress;
+pub use registry::ModelRegistry;
+pub use vad::trim_silence;
+pub use wav::{probe_wav, WavInfo};
+pub use wer::{align_words, normalize_for_wer, span_word_error_rate, word_error_rate, Alignment};
 
 pub const SHERPA_PARAKEET_INT8_MODEL: &str = "sherpa-onnx/parakeet-tdt-0.6b-v2-int8";
 pub const MLX_PARAKEET_V2_MODEL: &str = "mlx-community/parakeet-tdt-0.6b-v2";
@@ -19,42 +35,225 @@ pub fn is_mlx_model_name(model_name: &str) -> bool {
     model_name == MLX_PARAKEET_V2_MODEL
 }
 
-#[derive(Debug, Clone)]
-pub struct ModelDownloadProgress {
-    pub model_name: String,
-    pub stage: String,
-    pub downloaded_bytes: u64,
-    pub total_bytes: Option<u64>,
-    pub percent: Option<f32>,
-    pub done: bool,
-    pub error: Option<String>,
-    pub message: Option<String>,
+/// MLX only runs on Apple Silicon; Intel Macs must stick to whisper/sherpa models.
+pub fn mlx_supported() -> bool {
+    cfg!(target_os = "macos") && std::env::consts::ARCH == "aarch64"
+}
+
+/// Relative transcription quality of known models (higher is better, unknown models 0).
+/// Used to pick the best model among those already downloaded.
+pub fn model_quality_rank(model_name: &str) -> u32 {
+    if is_sherpa_model_name(model_name) || is_mlx_model_name(model_name) {
+        return 85;
+    }
+    match model_name.trim_end_matches(".en") {
+        "large-v3" => 100,
+        "large-v3-turbo" => 90,
+        "medium" => 70,
+        "small" => 50,
+        "base" => 30,
+        "tiny" => 10,
+        _ => 0,
+    }
+}
+
+/// Static facts about a model, known without downloading or loading it.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ModelDescriptor {
+    pub name: String,
+    /// `whisper.cpp`, `sherpa-onnx` or `mlx-parakeet`.
+    pub runtime: String,
+    /// Approximate download size.
+    pub approx_size_mb: u64,
+    /// ISO 639-1 codes the model transcribes. Empty for multilingual whisper models, which
+    /// cover every language whisper knows.
+    pub languages: Vec<String>,
+    pub multilingual: bool,
+    /// A good default for most machines.
+    pub recommended: bool,
+    pub note: Option<String>,
 }
 
 pub type ModelDownloadProgressHandler =
     Arc<dyn Fn(ModelDownloadProgress) + Send + Sync + 'static>;
 
-fn progress_handler_slot() -> &'static RwLock<Option<ModelDownloadProgressHandler>> {
-    static SLOT: OnceLock<RwLock<Option<ModelDownloadProgressHandler>>> = OnceLock::new();
+struct RegisteredProgressHandler {
+    generation: u64,
+    sender: ProgressScope,
+}
+
+fn progress_handler_slot() -> &'static RwLock<Option<RegisteredProgressHandler>> {
+    static SLOT: OnceLock<RwLock<Option<RegisteredProgressHandler>>> = OnceLock::new();
     SLOT.get_or_init(|| RwLock::new(None))
 }
 
-pub fn set_model_download_progress_handler(handler: Option<ModelDownloadProgressHandler>) {
+static NEXT_PROGRESS_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// Where one download's progress goes: the channel handed out by
+/// [`download_model_with_progress`], or the one the global handler listens on.
+pub(crate) type ProgressScope = mpsc::UnboundedSender<ModelDownloadProgress>;
+
+tokio::task_local! {
+    /// Progress channel of the [`download_model_with_progress`] call driving this task.
+    static PROGRESS_CHANNEL: ProgressScope;
+}
+
+thread_local! {
+    /// Scope captured when the download on this blocking thread started.
+    static DOWNLOAD_SCOPE: RefCell<Option<ProgressScope>> = const { RefCell::new(None) };
+}
+
+fn progress_channel() -> Option<ProgressScope> {
+    PROGRESS_CHANNEL.try_with(Clone::clone).ok()
+}
+
+fn is_current_progress_generation(generation: u64) -> bool {
+    progress_handler_slot()
+        .read()
+        .is_ok_and(|slot| slot.as_ref().is_some_and(|current| current.generation == generation))
+}
+
+/// Replaces the progress handler and returns its generation. Pass the generation to
+/// [`clear_model_download_progress_handler`] so a finished download cannot clear a newer
+/// handler. It only sees downloads that were not started through
+/// [`download_model_with_progress`], such as those triggered by `initialize`: those report
+/// on a channel of their own, which the handler reads on a dedicated thread. Events that
+/// arrive after the handler was replaced are dropped rather than routed to another
+/// download's UI.
+pub fn set_model_download_progress_handler(handler: Option<ModelDownloadProgressHandler>) -> u64 {
+    let generation = NEXT_PROGRESS_GENERATION.fetch_add(1, Ordering::Relaxed);
+    let sender = handler.map(|handler| {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        // Exits once the handler is replaced and every download holding the sender is done
+        std::thread::spawn(move || {
+            while let Some(progress) = receiver.blocking_recv() {
+                if is_current_progress_generation(generation) {
+                    handler(progress);
+                }
+            }
+        });
+        sender
+    });
     if let Ok(mut slot) = progress_handler_slot().write() {
-        *slot = handler;
+        *slot = sender.map(|sender| RegisteredProgressHandler { generation, sender });
     }
+    generation
 }
 
-pub fn emit_model_download_progress(progress: ModelDownloadProgress) {
-    let callback = progress_handler_slot()
+/// Removes the handler only if it is still the one registered as `generation`.
+pub fn clear_model_download_progress_handler(generation: u64) {
+    if let Ok(mut slot) = progress_handler_slot().write() {
+        if slot.as_ref().is_some_and(|current| current.generation == generation) {
+            *slot = None;
+        }
+    }
+}
+
+fn global_progress_channel() -> Option<ProgressScope> {
+    progress_handler_slot()
         .read()
         .ok()
-        .and_then(|slot| slot.as_ref().map(Arc::clone));
-    if let Some(callback) = callback {
-        callback(progress);
+        .and_then(|slot| slot.as_ref().map(|current| current.sender.clone()))
+}
+
+/// The progress channel of the download this task is running for, falling back to the
+/// global handler's. Capture it before handing work to a blocking thread.
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+pub(crate) fn current_progress_scope() -> Option<ProgressScope> {
+    progress_channel().or_else(global_progress_channel)
+}
+
+/// Runs a blocking download step under the scope captured by the caller, so its events
+/// reach that download's channel.
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux", test))]
+pub(crate) fn with_download_scope<T>(scope: Option<ProgressScope>, f: impl FnOnce() -> T) -> T {
+    let previous = DOWNLOAD_SCOPE.with(|slot| slot.replace(scope));
+    let result = f();
+    DOWNLOAD_SCOPE.with(|slot| *slot.borrow_mut() = previous);
+    result
+}
+
+pub fn emit_model_download_progress(progress: ModelDownloadProgress) {
+    let scope = DOWNLOAD_SCOPE
+        .with(|slot| slot.borrow().clone())
+        .or_else(progress_channel)
+        .or_else(global_progress_channel);
+    if let Some(sender) = scope {
+        // The receiver may have been dropped by a caller that stopped listening
+        let _ = sender.send(progress);
     }
 }
 
+/// Downloads `model_name` without loading it, with progress on a channel only this download
+/// writes to, so concurrent downloads never see each other's events. Drive the returned
+/// future to run the download; the receiver closes once it completes. Events emitted outside
+/// such a download still go to the global handler.
+pub fn download_model_with_progress(
+    model_name: &str,
+) -> (
+    impl Future<Output = Result<()>> + Send,
+    mpsc::UnboundedReceiver<ModelDownloadProgress>,
+) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let model_name = model_name.to_string();
+    let download = async move {
+        PROGRESS_CHANNEL
+            .scope(sender, async move {
+                create_adapter()?.download_only(&model_name).await
+            })
+            .await
+    };
+    (download, receiver)
+}
+
+fn verbose_logs_slot() -> &'static AtomicBool {
+    static SLOT: OnceLock<AtomicBool> = OnceLock::new();
+    SLOT.get_or_init(|| {
+        let from_env = std::env::var("OPENWISPR_VERBOSE_LOGS")
+            .ok()
+            .as_deref()
+            .map(|v| v == "1")
+            .unwrap_or(false);
+        AtomicBool::new(from_env)
+    })
+}
+
+/// Whether verbose logging is on. Starts from `OPENWISPR_VERBOSE_LOGS=1` and can be
+/// flipped at runtime with [`set_verbose_logs_enabled`].
+pub fn verbose_logs_enabled() -> bool {
+    verbose_logs_slot().load(Ordering::Relaxed)
+}
+
+pub fn set_verbose_logs_enabled(enabled: bool) {
+    verbose_logs_slot().store(enabled, Ordering::Relaxed);
+}
+
+fn model_dir_override_slot() -> &'static RwLock<Option<PathBuf>> {
+    static SLOT: OnceLock<RwLock<Option<PathBuf>>> = OnceLock::new();
+    SLOT.get_or_init(|| RwLock::new(None))
+}
+
+/// Points every STT model cache at `dir` (whisper files at the root, `sherpa-onnx/` and
+/// `mlx/` below it). `OPENWISPR_MODEL_DIR` still takes precedence.
+pub fn set_model_dir_override(dir: Option<PathBuf>) {
+    if let Ok(mut slot) = model_dir_override_slot().write() {
+        *slot = dir;
+    }
+}
+
+/// Root model directory from `OPENWISPR_MODEL_DIR` or [`set_model_dir_override`], if any.
+pub(crate) fn model_dir_override() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("OPENWISPR_MODEL_DIR") {
+        if !dir.trim().is_empty() {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    model_dir_override_slot()
+        .read()
+        .ok()
+        .and_then(|slot| slot.clone())
+}
+
 /// STT-specific errors
 #[derive(Debug, Error)]
 pub enum SttError {
@@ -72,6 +271,22 @@ pub enum SttError {
 
     #[error("Unsupported platform")]
     UnsupportedPlatform,
+
+    #[error("Model setup cancelled")]
+    Cancelled,
+
+    #[error("Streaming is not supported by this model")]
+    StreamingUnsupported,
+
+    #[error("{0} cannot translate; pick a multilingual whisper model or turn off translation")]
+    TranslationUnsupported(String),
+
+    #[error("MLX models require Apple Silicon; select a whisper model instead")]
+    AppleSiliconRequired,
+
+    /// A prerequisite the user has to install or fix; `hint` says how, in UI-ready words.
+    #[error("{component} is missing: {hint}")]
+    RuntimeMissing { component: String, hint: String },
 }
 
 pub type Result<T> = std::result::Result<T, SttError>;
@@ -99,27 +314,256 @@ impl Default for AudioFormat {
 pub struct Transcription {
     pub text: String,
     pub language: Option<String>,
+    /// Whisper's probability for `language` when auto-detected; `1.0` when the language was
+    /// fixed. `None` for backends without language detection.
+    pub language_confidence: Option<f32>,
     pub confidence: Option<f32>,
+    /// Signal-based 0.0-1.0 estimate of how trustworthy the result is.
+    /// Populated even by backends that cannot report a real confidence.
+    pub quality: Option<f32>,
+    /// Raw whisper decode metrics, for debugging recognition issues.
+    pub scores: Option<DecodeScores>,
     pub segments: Vec<TranscriptSegment>,
 }
 
+impl Transcription {
+    /// Every word across all segments, in order. Empty unless the backend produced word
+    /// timings (whisper with [`SttConfig::word_timestamps`]).
+    pub fn words(&self) -> impl Iterator<Item = &Word> {
+        self.segments.iter().flat_map(|segment| segment.words.iter())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct DecodeScores {
+    /// Mean token log-probability across all segments.
+    pub avg_logprob: f32,
+    /// Mean per-segment probability that the audio contained no speech.
+    pub no_speech_prob: f32,
+    /// Text length over its zlib-compressed length; high values indicate repetition loops.
+    pub compression_ratio: f32,
+}
+
 #[derive(Debug, Clone)]
 pub struct TranscriptSegment {
     pub text: String,
     pub start: f64,
     pub end: f64,
+    /// Mean token probability (0.0-1.0), for backends that report one.
+    pub confidence: Option<f32>,
+    /// Per-word timings; empty unless requested and supported by the backend.
+    pub words: Vec<Word>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub text: String,
+    /// Seconds from the start of the audio.
+    pub start: f64,
+    pub end: f64,
+    /// Mean probability of the word's tokens.
+    pub confidence: Option<f32>,
 }
 
 /// Configuration for STT models
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SttConfig {
     pub model_name: String,
     pub model_path: Option<PathBuf>,
     pub language: Option<String>,
     pub task: TranscriptionTask,
+    /// Recreate Python-based runtimes (MLX venv) from scratch before loading.
+    pub force_runtime_rebuild: bool,
+    /// Remove whisper special markers and non-speech annotations (`[Music]`, `♪`).
+    pub strip_special_tokens: bool,
+    /// Ask whisper for one continuous segment (no mid-sentence splits when pasting).
+    /// Trades away per-segment timestamp granularity.
+    pub single_segment: bool,
+    /// Maximum segment length in characters; enables token timestamps when set.
+    pub max_len: Option<u32>,
+    /// With `max_len`, split on word boundaries instead of mid-token.
+    pub split_on_word: bool,
+    /// When every GPU decode comes back empty, reload the model on CPU and decode once more.
+    pub cpu_fallback_on_empty: bool,
+    /// Whisper temperature fallback schedule and rejection thresholds.
+    pub decode_tuning: DecodeTuning,
+    /// Free GPU (or unified) memory required before trying Metal/Vulkan; below it the model
+    /// loads on CPU. `None` uses `OPENWISPR_MIN_GPU_MEMORY_MB`, else 1.5x the model size.
+    pub min_gpu_memory_mb: Option<u64>,
+    /// Raw whisper `FullParams` overrides by setter name (e.g. `length_penalty`,
+    /// `initial_prompt`), applied last. Unknown keys are ignored with a warning.
+    pub whisper_overrides: HashMap<String, WhisperParam>,
+    /// Fill [`TranscriptSegment::words`] from whisper token timestamps. Whisper only;
+    /// sherpa and MLX never report word timings.
+    pub word_timestamps: bool,
+    /// Drop leading, trailing and long internal silences before decoding. Segment and word
+    /// timestamps then refer to the trimmed audio.
+    pub trim_silence: bool,
+    /// High-pass the capture to strip DC offset from cheap microphones before normalizing.
+    pub remove_dc_offset: bool,
+    /// Gain applied to quiet captures before decoding.
+    pub normalization: NormalizationTuning,
+    /// Where whisper runs. `OPENWISPR_STT_BACKEND` overrides it.
+    pub compute_backend: ComputeBackend,
+    /// Where sherpa-onnx runs Parakeet. GPU providers fall back to CPU when unavailable.
+    pub sherpa_provider: SherpaProvider,
 }
 
-#[derive(Debug, Clone)]
+/// Compute backend for whisper. A build links at most one GPU backend (Metal on Apple
+/// Silicon, Vulkan on Windows), so requesting any other GPU makes
+/// [`SttAdapter::initialize`] fail with [`SttError::UnsupportedPlatform`]. A supported GPU
+/// that fails to initialize still falls back to CPU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComputeBackend {
+    /// The platform's GPU backend when there is one, else CPU.
+    #[default]
+    Auto,
+    Cpu,
+    Metal,
+    Vulkan,
+    Cuda,
+}
+
+impl ComputeBackend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Cpu => "cpu",
+            Self::Metal => "metal",
+            Self::Vulkan => "vulkan",
+            Self::Cuda => "cuda",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "cpu" => Some(Self::Cpu),
+            "metal" => Some(Self::Metal),
+            "vulkan" => Some(Self::Vulkan),
+            "cuda" => Some(Self::Cuda),
+            _ => None,
+        }
+    }
+
+    /// `OPENWISPR_STT_BACKEND`, if set to a known backend.
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var("OPENWISPR_STT_BACKEND").ok()?;
+        let backend = Self::parse(&value);
+        if backend.is_none() && !value.trim().is_empty() {
+            tracing::warn!("ignoring unknown OPENWISPR_STT_BACKEND={value:?}");
+        }
+        backend
+    }
+}
+
+/// onnxruntime execution provider for sherpa-onnx models. GPU providers only work when the
+/// linked onnxruntime was built with them; if the recognizer cannot be created on the
+/// requested provider, it is created on CPU instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SherpaProvider {
+    #[default]
+    Cpu,
+    Cuda,
+    DirectMl,
+}
+
+impl SherpaProvider {
+    /// The provider name sherpa-onnx expects.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Cuda => "cuda",
+            Self::DirectMl => "directml",
+        }
+    }
+}
+
+/// Value for [`SttConfig::whisper_overrides`]. Booleans are numbers: `0` is false.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WhisperParam {
+    Number(f32),
+    Text(String),
+}
+
+impl From<f32> for WhisperParam {
+    fn from(value: f32) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<&str> for WhisperParam {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+/// Whisper decode knobs. Widening the schedule (e.g. `temperature: 0.0`,
+/// `temperature_inc: 0.2`, up to 1.0) recovers more text from noisy recordings at the cost
+/// of extra decode passes; `beam_size: 1` trades some accuracy for speed on slow machines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodeTuning {
+    /// Beam width for the primary decode; `1` (or `0`) decodes greedily.
+    pub beam_size: u32,
+    /// Candidates sampled per temperature step when decoding greedily.
+    pub best_of: u32,
+    /// Starting sampling temperature.
+    pub temperature: f32,
+    /// Step added on each fallback retry; `0.0` disables temperature fallback.
+    pub temperature_inc: f32,
+    /// Retry when segment entropy exceeds this (whisper.cpp's stand-in for the
+    /// compression-ratio threshold).
+    pub entropy_thold: f32,
+    /// Retry when the average token log-probability falls below this. Only applies to the
+    /// primary profile; the permissive fallback accepts everything.
+    pub logprob_thold: f32,
+    /// Drop segments whose no-speech probability exceeds this. Primary profile only.
+    pub no_speech_thold: f32,
+}
+
+impl Default for DecodeTuning {
+    fn default() -> Self {
+        Self {
+            beam_size: 5,
+            best_of: 1,
+            temperature: 0.2,
+            temperature_inc: 0.2,
+            entropy_thold: 2.4,
+            logprob_thold: -1.0,
+            no_speech_thold: 0.6,
+        }
+    }
+}
+
+/// Quiet-clip boost. A capture whose peak is below `activation_peak` is scaled towards
+/// `target_peak`, by at most `max_gain`; louder captures pass through untouched. Lower
+/// `max_gain` (or disable) for quiet but clean microphones where the boost mostly lifts hiss.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NormalizationTuning {
+    /// `false` hands samples to the model exactly as captured.
+    pub enabled: bool,
+    /// Captures peaking at or above this are left alone.
+    pub activation_peak: f32,
+    /// Peak a quiet capture is lifted to.
+    pub target_peak: f32,
+    /// Upper bound on the applied gain.
+    pub max_gain: f32,
+}
+
+impl Default for NormalizationTuning {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            activation_peak: 0.20,
+            target_peak: 0.35,
+            max_gain: 80.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum TranscriptionTask {
     Transcribe,
     Translate, // Translate to English
@@ -132,6 +576,21 @@ impl Default for SttConfig {
             model_path: None,
             language: None,
             task: TranscriptionTask::Transcribe,
+            force_runtime_rebuild: false,
+            strip_special_tokens: true,
+            single_segment: false,
+            max_len: None,
+            split_on_word: false,
+            cpu_fallback_on_empty: true,
+            decode_tuning: DecodeTuning::default(),
+            min_gpu_memory_mb: None,
+            whisper_overrides: HashMap::new(),
+            word_timestamps: false,
+            trim_silence: false,
+            remove_dc_offset: true,
+            normalization: NormalizationTuning::default(),
+            compute_backend: ComputeBackend::Auto,
+            sherpa_provider: SherpaProvider::Cpu,
         }
     }
 }
@@ -142,19 +601,96 @@ pub trait SttAdapter: Send + Sync {
     /// Initialize the adapter and load the model
     async fn initialize(&mut self, config: SttConfig) -> Result<()>;
 
+    /// Release the loaded model and its memory. Until the next `initialize`, transcribing
+    /// fails with "adapter not initialized". Open streams keep their model alive until dropped.
+    async fn unload(&mut self) -> Result<()>;
+
     /// Transcribe audio data to text
     async fn transcribe(&self, audio_data: &[f32], format: AudioFormat) -> Result<Transcription>;
 
+    /// [`SttAdapter::transcribe`], reporting interim text through `on_progress` while the
+    /// decode runs. Adapters that only produce a final result never call it.
+    async fn transcribe_with_progress(
+        &self,
+        audio_data: &[f32],
+        format: AudioFormat,
+        on_progress: ProgressCallback,
+    ) -> Result<Transcription> {
+        let _ = on_progress;
+        self.transcribe(audio_data, format).await
+    }
+
+    /// Fetch model files without loading the model, for pre-downloads
+    async fn download_only(&self, model_name: &str) -> Result<()>;
+
     /// Check if a model is available/downloaded
     async fn is_model_available(&self, model_name: &str) -> bool;
 
-    /// List available models
-    fn available_models(&self) -> Vec<String>;
+    /// Models this adapter can load, with the metadata a model picker shows
+    fn available_model_descriptors(&self) -> Vec<ModelDescriptor>;
+
+    /// Names of [`SttAdapter::available_model_descriptors`]
+    fn available_models(&self) -> Vec<String> {
+        self.available_model_descriptors()
+            .into_iter()
+            .map(|model| model.name)
+            .collect()
+    }
 
     /// Get the current model name
     fn current_model(&self) -> Option<String>;
+
+    /// Run a throwaway decode on the loaded model so the first real transcription does not
+    /// pay for lazy allocations and GPU kernel compilation. Call after `initialize`.
+    async fn warm_up(&self) -> Result<()> {
+        let (clip, format) = warm_up_clip();
+        self.transcribe(&clip, format).await.map(|_| ())
+    }
+
+    /// Start an incremental decoding session for live captions. `format` describes the
+    /// samples that will be fed, as with [`SttAdapter::transcribe`].
+    fn create_stream(&self, format: AudioFormat) -> Result<Box<dyn SttStream>> {
+        let _ = format;
+        Err(SttError::StreamingUnsupported)
+    }
+}
+
+/// Half a second of 16 kHz mono silence for [`SttAdapter::warm_up`].
+pub(crate) fn warm_up_clip() -> (Vec<f32>, AudioFormat) {
+    let format = AudioFormat {
+        sample_rate: 16_000,
+        channels: 1,
+        bits_per_sample: 16,
+    };
+    (vec![0.0; 8_000], format)
+}
+
+/// Incremental decoding session from [`SttAdapter::create_stream`]. Each partial decodes
+/// only recent audio, so cost stays flat as the recording grows; [`SttAdapter::transcribe`]
+/// remains the way to decode a complete buffer.
+pub trait SttStream: Send {
+    /// Append newly captured audio, in the format given to `create_stream`
+    fn feed(&mut self, samples: &[f32]);
+
+    /// Best transcript of everything fed so far
+    fn partial(&mut self) -> String;
+
+    /// Decode whatever is left and return the full transcript
+    fn finalize(self: Box<Self>) -> Result<Transcription>;
+}
+
+/// Update from a running [`SttAdapter::transcribe_with_progress`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeProgress {
+    /// Transcript of the audio decoded so far; each update supersedes the previous one
+    Partial(String),
+    /// Still decoding, with no new text since the last update
+    Decoding,
 }
 
+/// Receives [`DecodeProgress`], possibly from a blocking decode thread.
+pub type ProgressCallback = Arc<dyn Fn(DecodeProgress) + Send + Sync>;
+
 /// Factory function to create the appropriate STT adapter for the current platform
 pub fn create_adapter() -> Result<Box<dyn SttAdapter>> {
     #[cfg(target_os = "macos")]
@@ -167,8 +703,406 @@ pub fn create_adapter() -> Result<Box<dyn SttAdapter>> {
         Ok(Box::new(adapters::whisper::WhisperAdapter::new()))
     }
 
+    #[cfg(target_os = "linux")]
+    {
+        Ok(Box::new(adapters::linux::LinuxWhisperAdapter::new()))
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Err(SttError::UnsupportedPlatform)
+    }
+}
+
+/// Kills a running MLX model setup and removes its partial artifacts. Returns whether a
+/// setup was running; always false on platforms without MLX.
+pub fn cancel_model_setup() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        adapters::mlx_parakeet::cancel_setup()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        false
+    }
+}
+
+/// Effective model directories after applying `OPENWISPR_MODEL_DIR` or the settings override.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ModelCachePaths {
+    pub whisper: PathBuf,
+    pub sherpa: PathBuf,
+    /// MLX paths exist only on macOS.
+    pub mlx: Option<PathBuf>,
+    pub mlx_venv: Option<PathBuf>,
+}
+
+pub fn model_cache_paths() -> Result<ModelCachePaths> {
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        #[cfg(target_os = "macos")]
+        let (mlx, mlx_venv) = (
+            Some(adapters::mlx_parakeet::mlx_cache_dir()?),
+            Some(adapters::mlx_parakeet::mlx_venv_dir()?),
+        );
+        #[cfg(not(target_os = "macos"))]
+        let (mlx, mlx_venv) = (None, None);
+
+        Ok(ModelCachePaths {
+            whisper: adapters::backend::model_cache_dir()?,
+            sherpa: adapters::sherpa::sherpa_cache_dir()?,
+            mlx,
+            mlx_venv,
+        })
+    }
+
     #[cfg(not(any(target_os = "macos", target_os = "windows")))]
     {
         Err(SttError::UnsupportedPlatform)
     }
 }
+
+/// Bytes `model_name` occupies in the cache: its whisper `.bin`, sherpa directory or MLX
+/// weights, with directories summed recursively. 0 when it is not downloaded. The MLX venv
+/// is shared by every MLX model and not counted.
+pub fn model_disk_usage(model_name: &str) -> Result<u64> {
+    let entries = model_cache_entries(model_name)?;
+    Ok(entries.paths.iter().map(|path| disk_usage(path)).sum())
+}
+
+/// Deletes a downloaded model's files, resolved the same way downloads lay them out. Fails
+/// with [`SttError::ModelNotFound`] when none are on disk. The MLX venv is kept for other
+/// MLX models.
+pub fn delete_model(model_name: &str) -> Result<()> {
+    let entries = model_cache_entries(model_name)?;
+    if let Some(outside) = entries
+        .paths
+        .iter()
+        .find(|path| !is_inside(&entries.root, path))
+    {
+        return Err(SttError::ModelLoadError(format!(
+            "refusing to delete {}: outside the model cache {}",
+            outside.display(),
+            entries.root.display()
+        )));
+    }
+
+    let existing: Vec<&PathBuf> = entries
+        .paths
+        .iter()
+        .filter(|path| std::fs::symlink_metadata(path).is_ok())
+        .collect();
+    if existing.is_empty() {
+        return Err(SttError::ModelNotFound(format!(
+            "{model_name} is not downloaded"
+        )));
+    }
+    for path in existing {
+        let removed = if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+        removed.map_err(|e| {
+            SttError::ModelLoadError(format!("failed to delete {}: {e}", path.display()))
+        })?;
+    }
+    tracing::info!("deleted model {model_name}");
+    Ok(())
+}
+
+/// How long a `.download` partial may sit untouched before the startup sweep removes it;
+/// anything newer is left for the next download to resume.
+const STALE_PARTIAL_DOWNLOAD_AGE: std::time::Duration =
+    std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Removes stale `.download` partials from the whisper and sherpa model caches and returns
+/// how many were removed. Meant to run once at startup, before any download is queued.
+pub fn sweep_partial_downloads() -> usize {
+    let dirs: Vec<Result<PathBuf>> = vec![
+        #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+        adapters::backend::model_cache_dir(),
+        #[cfg(any(target_os = "macos", target_os = "windows"))]
+        adapters::sherpa::sherpa_cache_dir(),
+    ];
+
+    let removed: Vec<PathBuf> = dirs
+        .iter()
+        .flatten()
+        .flat_map(|dir| {
+            platform::download::sweep_partial_downloads(dir, STALE_PARTIAL_DOWNLOAD_AGE)
+        })
+        .collect();
+    for path in &removed {
+        tracing::info!("removed stale partial download {}", path.display());
+    }
+    removed.len()
+}
+
+/// A model's files in the cache and the runtime cache directory they belong to.
+struct ModelCacheEntries {
+    root: PathBuf,
+    paths: Vec<PathBuf>,
+}
+
+fn model_cache_entries(model_name: &str) -> Result<ModelCacheEntries> {
+    #[cfg(target_os = "macos")]
+    {
+        if is_mlx_model_name(model_name) {
+            return Ok(ModelCacheEntries {
+                root: adapters::mlx_parakeet::mlx_cache_dir()?,
+                paths: adapters::mlx_parakeet::model_cache_entries(model_name)?,
+            });
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        if is_sherpa_model_name(model_name) {
+            return Ok(ModelCacheEntries {
+                root: adapters::sherpa::sherpa_cache_dir()?,
+                paths: vec![adapters::sherpa::sherpa_model_root_dir()?],
+            });
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    {
+        Ok(ModelCacheEntries {
+            root: adapters::backend::model_cache_dir()?,
+            paths: vec![adapters::backend::cached_model_path(model_name)?],
+        })
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = model_name;
+        Err(SttError::UnsupportedPlatform)
+    }
+}
+
+/// True when `path` names something strictly below `root` without `..` or absolute
+/// components, which `Path::starts_with` alone would let through.
+fn is_inside(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root).is_ok_and(|relative| {
+        relative.components().next().is_some()
+            && relative
+                .components()
+                .all(|component| matches!(component, std::path::Component::Normal(_)))
+    })
+}
+
+/// Size of a file, or of everything under a directory; 0 when `path` does not exist.
+/// Symlinks (the HuggingFace cache links snapshots to blobs) count as themselves.
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_usage(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Stages a local `ggml-*.bin` as a whisper model, for users who cannot download from
+/// HuggingFace. Validate [`StagedImport::path`] before committing it.
+pub fn stage_whisper_import(source: &Path) -> Result<StagedImport> {
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    {
+        adapters::backend::stage_import(source)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = source;
+        Err(SttError::UnsupportedPlatform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Waits for the handler thread to bring `received` up to `expected`.
+    fn wait_for_count(received: &AtomicU64, expected: u64) {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while received.load(Ordering::Relaxed) < expected && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(received.load(Ordering::Relaxed), expected);
+    }
+
+    #[test]
+    fn stale_download_generation_events_are_dropped() {
+        let received = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&received);
+        let old = set_model_download_progress_handler(Some(Arc::new(move |_: ModelDownloadProgress| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        })));
+        let progress = || ModelDownloadProgress {
+            model_name: "base".into(),
+            stage: "download".into(),
+            downloaded_bytes: 0,
+            total_bytes: None,
+            percent: None,
+            done: false,
+            error: None,
+            message: None,
+        };
+
+        let old_scope = global_progress_channel();
+        with_download_scope(old_scope.clone(), || emit_model_download_progress(progress()));
+        wait_for_count(&received, 1);
+
+        let counter = Arc::clone(&received);
+        let new = set_model_download_progress_handler(Some(Arc::new(move |_: ModelDownloadProgress| {
+            counter.fetch_add(100, Ordering::Relaxed);
+        })));
+        with_download_scope(old_scope, || emit_model_download_progress(progress()));
+
+        clear_model_download_progress_handler(old);
+        emit_model_download_progress(progress());
+        wait_for_count(&received, 101);
+
+        clear_model_download_progress_handler(new);
+        emit_model_download_progress(progress());
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(received.load(Ordering::Relaxed), 101);
+    }
+
+    #[test]
+    fn channel_scope_routes_events_from_tasks_and_blocking_threads() {
+        let progress = |stage: &str| ModelDownloadProgress {
+            model_name: "small".into(),
+            stage: stage.into(),
+            downloaded_bytes: 0,
+            total_bytes: None,
+            percent: None,
+            done: false,
+            error: None,
+            message: None,
+        };
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        tokio_test::block_on(PROGRESS_CHANNEL.scope(sender, async move {
+            emit_model_download_progress(progress("queued"));
+            let scope = current_progress_scope();
+            std::thread::spawn(move || {
+                with_download_scope(scope, || emit_model_download_progress(progress("download")))
+            })
+            .join()
+            .unwrap();
+        }));
+
+        assert_eq!(receiver.try_recv().unwrap().stage, "queued");
+        assert_eq!(receiver.try_recv().unwrap().stage, "download");
+        assert!(matches!(
+            receiver.try_recv(),
+            Err(mpsc::error::TryRecvError::Disconnected)
+        ));
+    }
+
+    #[test]
+    fn compute_backend_parses_case_insensitively() {
+        assert_eq!(ComputeBackend::parse(" CPU "), Some(ComputeBackend::Cpu));
+        assert_eq!(ComputeBackend::parse("opencl"), None);
+        for backend in [
+            ComputeBackend::Auto,
+            ComputeBackend::Metal,
+            ComputeBackend::Vulkan,
+            ComputeBackend::Cuda,
+        ] {
+            assert_eq!(ComputeBackend::parse(backend.as_str()), Some(backend));
+        }
+    }
+
+    #[test]
+    fn disk_usage_sums_nested_files() {
+        let root =
+            std::env::temp_dir().join(format!("openwispr-disk-usage-{}", std::process::id()));
+        let nested = root.join("model").join("nested");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join("ggml-base.bin"), [0u8; 100]).unwrap();
+        std::fs::write(root.join("model").join("encoder.onnx"), [0u8; 40]).unwrap();
+        std::fs::write(nested.join("tokens.txt"), [0u8; 2]).unwrap();
+
+        assert_eq!(disk_usage(&root.join("ggml-base.bin")), 100);
+        assert_eq!(disk_usage(&root.join("model")), 42);
+        assert_eq!(disk_usage(&root), 142);
+        assert_eq!(disk_usage(&root.join("missing")), 0);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn delete_guard_rejects_paths_escaping_the_cache() {
+        let root = Path::new("/cache/models");
+        assert!(is_inside(root, &root.join("ggml-base.bin")));
+        assert!(is_inside(root, &root.join("sherpa-onnx").join("model")));
+        assert!(!is_inside(root, root));
+        assert!(!is_inside(root, &root.join("../ggml-base.bin")));
+        assert!(!is_inside(root, &root.join("/etc/passwd")));
+        assert!(!is_inside(
+            root,
+            Path::new("/cache/models-old/ggml-base.bin")
+        ));
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    fn relative_entries(model_name: &str) -> Vec<PathBuf> {
+        let entries = model_cache_entries(model_name).unwrap();
+        entries
+            .paths
+            .iter()
+            .map(|path| path.strip_prefix(&entries.root).unwrap().to_path_buf())
+            .collect()
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+    #[test]
+    fn whisper_models_resolve_to_their_ggml_file() {
+        assert_eq!(
+            relative_entries("base.en"),
+            [PathBuf::from("ggml-base.en.bin")]
+        );
+        let escaping = model_cache_entries("../../secret.bin").unwrap();
+        assert!(!is_inside(&escaping.root, &escaping.paths[0]));
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    #[test]
+    fn sherpa_model_resolves_to_the_release_dir() {
+        assert_eq!(
+            relative_entries(SHERPA_PARAKEET_INT8_MODEL),
+            [PathBuf::from("sherpa-onnx-nemo-parakeet-tdt-0.6b-v2-int8")]
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn mlx_model_resolves_to_weights_and_marker() {
+        assert_eq!(
+            relative_entries(MLX_PARAKEET_V2_MODEL),
+            [
+                PathBuf::from("models--mlx-community--parakeet-tdt-0.6b-v2"),
+                PathBuf::from(".downloaded").join("mlx-community-parakeet-tdt-0.6b-v2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn model_quality_rank_orders_known_models() {
+        assert!(model_quality_rank("large-v3") > model_quality_rank("large-v3-turbo"));
+        assert!(model_quality_rank("large-v3-turbo") > model_quality_rank(SHERPA_PARAKEET_INT8_MODEL));
+        assert!(model_quality_rank("small.en") > model_quality_rank("base"));
+        assert_eq!(model_quality_rank("base.en"), model_quality_rank("base"));
+        assert_eq!(model_quality_rank("ggml-custom.bin"), 0);
+    }
+}
@@ -0,0 +1,408 @@
+//! Several initialized models held side by side, so the same audio can be routed to either
+//! of them without re-initializing (e.g. to A/B compare models). Every model gets its own
+//! adapter from [`create_adapter`]; once their combined approximate size exceeds the memory
+//! budget, the least recently used ones are dropped.
+//!
+//! The desktop dictation adapter is a registry too, but it unloads the current model before
+//! loading another, so it only ever holds one and two models never share memory there.
+//! Keeping several resident is for callers that want that on purpose, such as a model
+//! comparison.
+
+use crate::{
+    create_adapter, AudioFormat, ModelDescriptor, ProgressCallback, Result, SttAdapter, SttConfig,
+    SttError, SttStream, Transcription,
+};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::{info, warn};
+
+type AdapterFactory = Box<dyn Fn() -> Result<Box<dyn SttAdapter>> + Send + Sync>;
+
+struct LoadedModel {
+    name: String,
+    /// What the model was initialized with; loading it again with anything else reloads it.
+    config: SttConfig,
+    size_mb: u64,
+    adapter: Arc<dyn SttAdapter>,
+}
+
+/// Registry of initialized models keyed by name. As an [`SttAdapter`] it behaves like a
+/// single-model adapter for whichever model was initialized last, while the others stay
+/// resident for [`ModelRegistry::transcribe_with`].
+pub struct ModelRegistry {
+    factory: AdapterFactory,
+    /// Never initialized; answers downloads, availability and descriptor queries.
+    catalog: Box<dyn SttAdapter>,
+    /// Least recently used first.
+    loaded: Mutex<Vec<LoadedModel>>,
+    memory_budget_mb: Mutex<Option<u64>>,
+    current: Mutex<Option<String>>,
+}
+
+impl ModelRegistry {
+    /// `memory_budget_mb` caps the combined approximate size of loaded models; `None` keeps
+    /// every model loaded until it is unloaded explicitly.
+    pub fn new(memory_budget_mb: Option<u64>) -> Result<Self> {
+        Self::with_factory(memory_budget_mb, Box::new(create_adapter))
+    }
+
+    fn with_factory(memory_budget_mb: Option<u64>, factory: AdapterFactory) -> Result<Self> {
+        let catalog = factory()?;
+        Ok(Self {
+            factory,
+            catalog,
+            loaded: Mutex::new(Vec::new()),
+            memory_budget_mb: Mutex::new(memory_budget_mb),
+            current: Mutex::new(None),
+        })
+    }
+
+    /// Changes the budget, evicting least recently used models if the new one is exceeded.
+    pub fn set_memory_budget_mb(&self, memory_budget_mb: Option<u64>) -> Result<()> {
+        if let Ok(mut budget) = self.memory_budget_mb.lock() {
+            *budget = memory_budget_mb;
+        }
+        let mut loaded = self.loaded()?;
+        self.evict_over_budget(&mut loaded);
+        Ok(())
+    }
+
+    /// Names of the resident models, least recently used first.
+    pub fn loaded_models(&self) -> Vec<String> {
+        self.loaded
+            .lock()
+            .map(|loaded| loaded.iter().map(|model| model.name.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Initializes `config.model_name` unless it is already resident with the same config,
+    /// then evicts least recently used models until the budget holds. A resident model with
+    /// a different config (task, backend, decode tuning, ...) is dropped and loaded again.
+    /// The model just loaded is never evicted, even when it alone exceeds the budget.
+    pub async fn load(&self, config: SttConfig) -> Result<()> {
+        let name = config.model_name.clone();
+        let resident_config_matches = self
+            .loaded()?
+            .iter()
+            .find(|model| model.name == name)
+            .map(|model| model.config == config);
+        match resident_config_matches {
+            Some(true) => {
+                self.touch(&name)?;
+                return Ok(());
+            }
+            Some(false) => {
+                info!("reloading {} with a changed config", name);
+                // Free the old copy first so both never sit in memory at once
+                self.loaded()?.retain(|model| model.name != name);
+            }
+            None => {}
+        }
+
+        let mut adapter = (self.factory)()?;
+        adapter.initialize(config.clone()).await?;
+        let size_mb = self
+            .catalog
+            .available_model_descriptors()
+            .into_iter()
+            .find(|model| model.name == name)
+            .map_or(0, |model| model.approx_size_mb);
+
+        let mut loaded = self.loaded()?;
+        // A concurrent load of the same model may have finished first
+        loaded.retain(|model| model.name != name);
+        info!("loaded {} into the model registry ({} MB)", name, size_mb);
+        loaded.push(LoadedModel {
+            name,
+            config,
+            size_mb,
+            adapter: Arc::from(adapter),
+        });
+        self.evict_over_budget(&mut loaded);
+        Ok(())
+    }
+
+    /// Transcribes with a resident model without changing the current one.
+    pub async fn transcribe_with(
+        &self,
+        model_name: &str,
+        audio_data: &[f32],
+        format: AudioFormat,
+    ) -> Result<Transcription> {
+        let adapter = self.touch(model_name)?.ok_or_else(|| {
+            SttError::TranscriptionFailed(format!("model {model_name} is not loaded"))
+        })?;
+        adapter.transcribe(audio_data, format).await
+    }
+
+    /// Drops `model_name`, returning whether it was resident. Transcriptions already running
+    /// on it finish first; its memory is released once they do.
+    pub fn unload_model(&self, model_name: &str) -> Result<bool> {
+        let mut loaded = self.loaded()?;
+        let before = loaded.len();
+        loaded.retain(|model| model.name != model_name);
+        if let Ok(mut current) = self.current.lock() {
+            if current.as_deref() == Some(model_name) {
+                *current = None;
+            }
+        }
+        Ok(loaded.len() != before)
+    }
+
+    fn loaded(&self) -> Result<MutexGuard<'_, Vec<LoadedModel>>> {
+        self.loaded
+            .lock()
+            .map_err(|_| SttError::ModelLoadError("model registry lock poisoned".into()))
+    }
+
+    /// Marks `model_name` as most recently used and returns its adapter.
+    fn touch(&self, model_name: &str) -> Result<Option<Arc<dyn SttAdapter>>> {
+        let mut loaded = self.loaded()?;
+        let Some(index) = loaded.iter().position(|model| model.name == model_name) else {
+            return Ok(None);
+        };
+        let model = loaded.remove(index);
+        let adapter = model.adapter.clone();
+        loaded.push(model);
+        Ok(Some(adapter))
+    }
+
+    fn current_adapter(&self) -> Result<Arc<dyn SttAdapter>> {
+        let current = self
+            .current_model()
+            .ok_or_else(|| SttError::TranscriptionFailed("adapter not initialized".into()))?;
+        self.touch(&current)?.ok_or_else(|| {
+            SttError::TranscriptionFailed(format!("model {current} was evicted from the registry"))
+        })
+    }
+
+    fn evict_over_budget(&self, loaded: &mut Vec<LoadedModel>) {
+        let Some(budget_mb) = self.memory_budget_mb.lock().ok().and_then(|budget| *budget) else {
+            return;
+        };
+        let mut total_mb: u64 = loaded.iter().map(|model| model.size_mb).sum();
+        while total_mb > budget_mb && loaded.len() > 1 {
+            let evicted = loaded.remove(0);
+            total_mb -= evicted.size_mb;
+            info!(
+                "evicted {} ({} MB) to stay within the {} MB model budget",
+                evicted.name, evicted.size_mb, budget_mb
+            );
+            if let Ok(mut current) = self.current.lock() {
+                if current.as_deref() == Some(evicted.name.as_str()) {
+                    *current = None;
+                }
+            }
+        }
+        if total_mb > budget_mb {
+            warn!(
+                "model registry holds {} MB, over its {} MB budget",
+                total_mb, budget_mb
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl SttAdapter for ModelRegistry {
+    async fn initialize(&mut self, config: SttConfig) -> Result<()> {
+        let model_name = config.model_name.clone();
+        self.load(config).await?;
+        if let Ok(mut current) = self.current.lock() {
+            *current = Some(model_name);
+        }
+        Ok(())
+    }
+
+    /// Unloads the current model; other resident models stay loaded.
+    async fn unload(&mut self) -> Result<()> {
+        if let Some(current) = self.current_model() {
+            self.unload_model(&current)?;
+        }
+        Ok(())
+    }
+
+    async fn transcribe(&self, audio_data: &[f32], format: AudioFormat) -> Result<Transcription> {
+        self.current_adapter()?.transcribe(audio_data, format).await
+    }
+
+    async fn transcribe_with_progress(
+        &self,
+        audio_data: &[f32],
+        format: AudioFormat,
+        on_progress: ProgressCallback,
+    ) -> Result<Transcription> {
+        self.current_adapter()?
+            .transcribe_with_progress(audio_data, format, on_progress)
+            .await
+    }
+
+    async fn download_only(&self, model_name: &str) -> Result<()> {
+        self.catalog.download_only(model_name).await
+    }
+
+    async fn is_model_available(&self, model_name: &str) -> bool {
+        self.catalog.is_model_available(model_name).await
+    }
+
+    fn available_model_descriptors(&self) -> Vec<ModelDescriptor> {
+        self.catalog.available_model_descriptors()
+    }
+
+    fn current_model(&self) -> Option<String> {
+        self.current.lock().ok().and_then(|current| current.clone())
+    }
+
+    async fn warm_up(&self) -> Result<()> {
+        self.current_adapter()?.warm_up().await
+    }
+
+    fn create_stream(&self, format: AudioFormat) -> Result<Box<dyn SttStream>> {
+        self.current_adapter()?.create_stream(format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TranscriptionTask;
+
+    const MODELS: &[(&str, u64)] = &[("small", 466), ("medium", 1_500), ("large", 3_100)];
+
+    /// Echoes the name of the model it was initialized with, and whether it translates.
+    #[derive(Default)]
+    struct EchoAdapter {
+        model: Option<String>,
+    }
+
+    #[async_trait]
+    impl SttAdapter for EchoAdapter {
+        async fn initialize(&mut self, config: SttConfig) -> Result<()> {
+            self.model = Some(match config.task {
+                TranscriptionTask::Transcribe => config.model_name,
+                TranscriptionTask::Translate => format!("{} (translate)", config.model_name),
+            });
+            Ok(())
+        }
+
+        async fn unload(&mut self) -> Result<()> {
+            self.model = None;
+            Ok(())
+        }
+
+        async fn transcribe(&self, _audio: &[f32], _format: AudioFormat) -> Result<Transcription> {
+            Ok(Transcription {
+                text: self.model.clone().unwrap_or_default(),
+                language: None,
+                language_confidence: None,
+                confidence: None,
+                quality: None,
+                scores: None,
+                segments: Vec::new(),
+            })
+        }
+
+        async fn download_only(&self, _model_name: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn is_model_available(&self, _model_name: &str) -> bool {
+            true
+        }
+
+        fn available_model_descriptors(&self) -> Vec<ModelDescriptor> {
+            MODELS
+                .iter()
+                .map(|&(name, approx_size_mb)| ModelDescriptor {
+                    name: name.to_string(),
+                    runtime: "echo".to_string(),
+                    approx_size_mb,
+                    languages: Vec::new(),
+                    multilingual: true,
+                    recommended: false,
+                    note: None,
+                })
+                .collect()
+        }
+
+        fn current_model(&self) -> Option<String> {
+            self.model.clone()
+        }
+    }
+
+    fn echo_registry(memory_budget_mb: Option<u64>) -> ModelRegistry {
+        ModelRegistry::with_factory(
+            memory_budget_mb,
+            Box::new(|| Ok(Box::new(EchoAdapter::default()))),
+        )
+        .unwrap()
+    }
+
+    fn config(model_name: &str) -> SttConfig {
+        SttConfig {
+            model_name: model_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn routes_audio_to_each_resident_model() {
+        let mut registry = echo_registry(None);
+        tokio_test::block_on(async {
+            registry.initialize(config("small")).await.unwrap();
+            registry.initialize(config("medium")).await.unwrap();
+
+            let format = AudioFormat::default();
+            let small = registry.transcribe_with("small", &[0.0], format.clone());
+            assert_eq!(small.await.unwrap().text, "small");
+            let current = registry.transcribe(&[0.0], format.clone());
+            assert_eq!(current.await.unwrap().text, "medium");
+            let missing = registry.transcribe_with("large", &[0.0], format);
+            assert!(missing.await.is_err());
+        });
+        assert_eq!(registry.loaded_models(), ["small", "medium"]);
+    }
+
+    #[test]
+    fn reinitializing_with_a_changed_config_reloads_the_model() {
+        let mut registry = echo_registry(None);
+        let transcribe = |registry: &ModelRegistry| {
+            tokio_test::block_on(registry.transcribe(&[0.0], AudioFormat::default()))
+                .unwrap()
+                .text
+        };
+
+        tokio_test::block_on(registry.initialize(config("small"))).unwrap();
+        assert_eq!(transcribe(&registry), "small");
+
+        let translate = SttConfig {
+            task: TranscriptionTask::Translate,
+            ..config("small")
+        };
+        tokio_test::block_on(registry.initialize(translate)).unwrap();
+        assert_eq!(transcribe(&registry), "small (translate)");
+        assert_eq!(registry.loaded_models(), ["small"]);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_models_over_budget() {
+        let registry = echo_registry(Some(3_700));
+        tokio_test::block_on(async {
+            registry.load(config("small")).await.unwrap();
+            registry.load(config("medium")).await.unwrap();
+            assert_eq!(registry.loaded_models(), ["small", "medium"]);
+
+            // Using small leaves medium as the least recently used
+            registry
+                .transcribe_with("small", &[0.0], AudioFormat::default())
+                .await
+                .unwrap();
+            registry.load(config("large")).await.unwrap();
+        });
+        assert_eq!(registry.loaded_models(), ["small", "large"]);
+
+        // The most recent model stays even when it alone exceeds the budget
+        registry.set_memory_budget_mb(Some(1_000)).unwrap();
+        assert_eq!(registry.loaded_models(), ["large"]);
+    }
+}
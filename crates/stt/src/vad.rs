@@ -0,0 +1,169 @@
+//! Energy + zero-crossing voice activity detection, used to drop silence before decoding.
+
+/// Analysis frame length.
+const FRAME_MS: u32 = 10;
+/// Audio kept around detected speech so word-initial consonants are not clipped.
+const SPEECH_PADDING_MS: u32 = 150;
+/// Frames whose peak stays below this are silence however quiet the room is.
+const MIN_SPEECH_PEAK: f32 = 0.01;
+/// Caps the estimated background level, so a clip that is nearly all speech cannot mistake
+/// its quieter words for the noise floor.
+const MAX_NOISE_FLOOR: f32 = 0.01;
+/// Voiced speech sits well above the noise floor.
+const VOICED_FLOOR_RATIO: f32 = 3.0;
+/// Fricatives (`s`, `f`, `th`) are quiet but cross zero often.
+const UNVOICED_FLOOR_RATIO: f32 = 1.5;
+const UNVOICED_MIN_ZCR: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SignalStats {
+    pub(crate) peak: f32,
+    pub(crate) rms: f32,
+    pub(crate) zero_crossing_rate: f32,
+}
+
+pub(crate) fn signal_stats(samples: &[f32]) -> SignalStats {
+    if samples.is_empty() {
+        return SignalStats::default();
+    }
+
+    let peak = samples
+        .iter()
+        .map(|s| s.abs())
+        .fold(0.0_f32, |acc, v| acc.max(v));
+    let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+
+    let mut crossings = 0usize;
+    for pair in samples.windows(2) {
+        let a = pair[0];
+        let b = pair[1];
+        if (a >= 0.0 && b < 0.0) || (a < 0.0 && b >= 0.0) {
+            crossings += 1;
+        }
+    }
+    let zcr = crossings as f32 / samples.len() as f32;
+
+    SignalStats {
+        peak,
+        rms,
+        zero_crossing_rate: zcr,
+    }
+}
+
+/// Removes leading, trailing and long internal silences from mono audio, keeping
+/// [`SPEECH_PADDING_MS`] around every stretch of speech (so gaps up to twice that survive
+/// intact). Audio with no detectable speech is returned unchanged.
+pub fn trim_silence(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    let frame_len = (sample_rate * FRAME_MS / 1000) as usize;
+    if frame_len == 0 || samples.len() < frame_len {
+        return samples.to_vec();
+    }
+
+    let speech = speech_frames(samples, frame_len);
+    if !speech.contains(&true) {
+        return samples.to_vec();
+    }
+
+    let padding = SPEECH_PADDING_MS.div_ceil(FRAME_MS) as usize;
+    let mut keep = vec![false; speech.len()];
+    for (i, _) in speech.iter().enumerate().filter(|(_, is_speech)| **is_speech) {
+        let from = i.saturating_sub(padding);
+        let to = (i + padding + 1).min(keep.len());
+        keep[from..to].fill(true);
+    }
+
+    samples
+        .chunks(frame_len)
+        .zip(&keep)
+        .filter(|(_, keep)| **keep)
+        .flat_map(|(frame, _)| frame.iter().copied())
+        .collect()
+}
+
+/// Whether any frame of mono `samples` holds speech rather than silence or steady noise.
+pub(crate) fn contains_speech(samples: &[f32], sample_rate: u32) -> bool {
+    let frame_len = (sample_rate * FRAME_MS / 1000) as usize;
+    frame_len > 0 && speech_frames(samples, frame_len).contains(&true)
+}
+
+/// Per [`FRAME_MS`] frame, whether it stands out from the noise floor as speech.
+fn speech_frames(samples: &[f32], frame_len: usize) -> Vec<bool> {
+    let stats: Vec<SignalStats> = samples.chunks(frame_len).map(signal_stats).collect();
+    if stats.is_empty() {
+        return Vec::new();
+    }
+    let floor = noise_floor(&stats);
+    stats
+        .iter()
+        .map(|frame| {
+            frame.peak >= MIN_SPEECH_PEAK
+                && (frame.rms >= floor * VOICED_FLOOR_RATIO
+                    || (frame.rms >= floor * UNVOICED_FLOOR_RATIO
+                        && frame.zero_crossing_rate >= UNVOICED_MIN_ZCR))
+        })
+        .collect()
+}
+
+/// 10th-percentile frame RMS: the background level between words.
+fn noise_floor(stats: &[SignalStats]) -> f32 {
+    let mut levels: Vec<f32> = stats.iter().map(|frame| frame.rms).collect();
+    levels.sort_by(f32::total_cmp);
+    levels[levels.len() / 10].min(MAX_NOISE_FLOOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn tone(ms: u32) -> Vec<f32> {
+        (0..RATE * ms / 1000)
+            .map(|i| (i as f32 * 0.05).sin() * 0.2)
+            .collect()
+    }
+
+    fn silence(ms: u32) -> Vec<f32> {
+        vec![0.0; (RATE * ms / 1000) as usize]
+    }
+
+    #[test]
+    fn signal_stats_reports_peak_and_rms() {
+        let input = vec![0.5, -0.5, 0.5, -0.5];
+        let stats = signal_stats(&input);
+        assert!((stats.peak - 0.5).abs() < 0.0001);
+        assert!((stats.rms - 0.5).abs() < 0.0001);
+        assert!(stats.zero_crossing_rate > 0.0);
+    }
+
+    #[test]
+    fn trim_silence_keeps_padding_around_speech() {
+        let input = [silence(1000), tone(500), silence(2000), tone(500), silence(1000)].concat();
+        let out = trim_silence(&input, RATE);
+
+        let ms = |samples: usize| samples as u32 * 1000 / RATE;
+        // Both tones, 150 ms padding at each edge and 300 ms left of the internal gap
+        assert_eq!(ms(out.len()), 500 + 500 + 150 + 300 + 150);
+        assert_eq!(out[..(RATE * 150 / 1000) as usize], silence(150)[..]);
+    }
+
+    #[test]
+    fn trim_silence_leaves_short_pauses_and_silent_clips_alone() {
+        let input = [tone(500), silence(200), tone(500)].concat();
+        assert_eq!(trim_silence(&input, RATE).len(), input.len());
+
+        let quiet = silence(1000);
+        assert_eq!(trim_silence(&quiet, RATE), quiet);
+    }
+
+    #[test]
+    fn contains_speech_ignores_silence_and_faint_noise() {
+        assert!(contains_speech(&[silence(500), tone(300)].concat(), RATE));
+        assert!(!contains_speech(&silence(1000), RATE));
+        let hiss: Vec<f32> = (0..RATE)
+            .map(|i| if i % 2 == 0 { 0.002 } else { -0.002 })
+            .collect();
+        assert!(!contains_speech(&hiss, RATE));
+        assert!(!contains_speech(&[], RATE));
+    }
+}
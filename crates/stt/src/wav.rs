@@ -0,0 +1,79 @@
+use crate::{Result, SttError};
+use serde::Serialize;
+use std::path::Path;
+
+/// Header properties of a WAV file, read without decoding its samples.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WavInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub duration_secs: f64,
+    /// `"int"` or `"float"`.
+    pub sample_format: String,
+}
+
+pub fn probe_wav(path: &Path) -> Result<WavInfo> {
+    let reader = hound::WavReader::open(path)
+        .map_err(|e| SttError::AudioError(format!("failed to open {}: {e}", path.display())))?;
+    let spec = reader.spec();
+    // `duration` counts frames (samples per channel).
+    let duration_secs = if spec.sample_rate == 0 {
+        0.0
+    } else {
+        reader.duration() as f64 / spec.sample_rate as f64
+    };
+    let sample_format = match spec.sample_format {
+        hound::SampleFormat::Int => "int",
+        hound::SampleFormat::Float => "float",
+    };
+
+    Ok(WavInfo {
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        bits_per_sample: spec.bits_per_sample,
+        duration_secs,
+        sample_format: sample_format.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_wav_reports_header_and_duration() {
+        let path =
+            std::env::temp_dir().join(format!("openwispr-probe-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..(8_000 * 2 * 2) {
+            writer.write_sample(0_i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let info = probe_wav(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            info,
+            WavInfo {
+                sample_rate: 8_000,
+                channels: 2,
+                bits_per_sample: 16,
+                duration_secs: 2.0,
+                sample_format: "int".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn probe_wav_rejects_missing_file() {
+        let err = probe_wav(Path::new("/nonexistent/openwispr.wav")).unwrap_err();
+        assert!(matches!(err, SttError::AudioError(_)));
+    }
+}
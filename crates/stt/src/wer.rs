@@ -0,0 +1,175 @@
+/// One step of a word-level alignment between a reference and a hypothesis transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Alignment {
+    Match(String),
+    Substitution {
+        reference: String,
+        hypothesis: String,
+    },
+    /// Word present only in the hypothesis.
+    Insertion(String),
+    /// Reference word missing from the hypothesis.
+    Deletion(String),
+}
+
+/// Lowercases and strips punctuation (apostrophes kept) so comparisons ignore formatting.
+pub fn normalize_for_wer(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '\'')
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Minimum-edit alignment of whitespace-separated words (Levenshtein on words).
+/// Pass both sides through [`normalize_for_wer`] first to ignore case and punctuation.
+pub fn align_words(reference: &str, hypothesis: &str) -> Vec<Alignment> {
+    let reference: Vec<&str> = reference.split_whitespace().collect();
+    let hypothesis: Vec<&str> = hypothesis.split_whitespace().collect();
+    let (rows, cols) = (reference.len() + 1, hypothesis.len() + 1);
+
+    let mut cost = vec![0usize; rows * cols];
+    for i in 0..rows {
+        cost[i * cols] = i;
+    }
+    for (j, slot) in cost.iter_mut().take(cols).enumerate() {
+        *slot = j;
+    }
+    for i in 1..rows {
+        for j in 1..cols {
+            let substitution =
+                cost[(i - 1) * cols + j - 1] + usize::from(reference[i - 1] != hypothesis[j - 1]);
+            let deletion = cost[(i - 1) * cols + j] + 1;
+            let insertion = cost[i * cols + j - 1] + 1;
+            cost[i * cols + j] = substitution.min(deletion).min(insertion);
+        }
+    }
+
+    let mut alignment = Vec::with_capacity(rows.max(cols));
+    let (mut i, mut j) = (reference.len(), hypothesis.len());
+    // On ties prefer matches, then deletions/insertions, so substitutions stay meaningful.
+    while i > 0 || j > 0 {
+        let here = cost[i * cols + j];
+        if i > 0
+            && j > 0
+            && reference[i - 1] == hypothesis[j - 1]
+            && here == cost[(i - 1) * cols + j - 1]
+        {
+            alignment.push(Alignment::Match(reference[i - 1].to_string()));
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && here == cost[(i - 1) * cols + j] + 1 {
+            alignment.push(Alignment::Deletion(reference[i - 1].to_string()));
+            i -= 1;
+        } else if j > 0 && here == cost[i * cols + j - 1] + 1 {
+            alignment.push(Alignment::Insertion(hypothesis[j - 1].to_string()));
+            j -= 1;
+        } else {
+            alignment.push(Alignment::Substitution {
+                reference: reference[i - 1].to_string(),
+                hypothesis: hypothesis[j - 1].to_string(),
+            });
+            i -= 1;
+            j -= 1;
+        }
+    }
+    alignment.reverse();
+    alignment
+}
+
+/// `(substitutions + deletions + insertions) / reference words`. An empty reference scores
+/// 0.0 against an empty hypothesis and 1.0 otherwise.
+pub fn word_error_rate(reference: &str, hypothesis: &str) -> f32 {
+    let reference_words = reference.split_whitespace().count();
+    let errors = align_words(reference, hypothesis)
+        .iter()
+        .filter(|step| !matches!(step, Alignment::Match(_)))
+        .count();
+    if reference_words == 0 {
+        return if errors == 0 { 0.0 } else { 1.0 };
+    }
+    errors as f32 / reference_words as f32
+}
+
+/// Like [`word_error_rate`], but against whichever run of consecutive hypothesis words
+/// matches `reference` best, so speech around the phrase costs nothing. Use it when
+/// `reference` is only part of what was said.
+pub fn span_word_error_rate(reference: &str, hypothesis: &str) -> f32 {
+    let reference: Vec<&str> = reference.split_whitespace().collect();
+    let hypothesis: Vec<&str> = hypothesis.split_whitespace().collect();
+    if reference.is_empty() {
+        return 0.0;
+    }
+
+    // Edit distance where the span may start and end anywhere in the hypothesis for free
+    let mut previous = vec![0usize; hypothesis.len() + 1];
+    for (i, reference_word) in reference.iter().enumerate() {
+        let mut current = vec![i + 1; hypothesis.len() + 1];
+        for (j, hypothesis_word) in hypothesis.iter().enumerate() {
+            let substitution = previous[j] + usize::from(reference_word != hypothesis_word);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    let errors = previous.into_iter().min().unwrap_or(reference.len());
+    errors as f32 / reference.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_words_reports_each_edit_kind() {
+        let alignment = align_words("the cat sat on the mat", "the cat sit on mat today");
+        assert_eq!(
+            alignment,
+            vec![
+                Alignment::Match("the".into()),
+                Alignment::Match("cat".into()),
+                Alignment::Substitution {
+                    reference: "sat".into(),
+                    hypothesis: "sit".into(),
+                },
+                Alignment::Match("on".into()),
+                Alignment::Deletion("the".into()),
+                Alignment::Match("mat".into()),
+                Alignment::Insertion("today".into()),
+            ]
+        );
+        assert_eq!(
+            word_error_rate("the cat sat on the mat", "the cat sit on mat today"),
+            0.5
+        );
+    }
+
+    #[test]
+    fn normalization_ignores_case_and_punctuation() {
+        let reference = normalize_for_wer("Hello, World! It's me.");
+        assert_eq!(reference, "hello world it's me");
+        assert_eq!(
+            word_error_rate(&reference, &normalize_for_wer("hello world it's me")),
+            0.0
+        );
+    }
+
+    #[test]
+    fn empty_reference_is_all_or_nothing() {
+        assert_eq!(word_error_rate("", ""), 0.0);
+        assert_eq!(word_error_rate("", "extra words"), 1.0);
+    }
+
+    #[test]
+    fn span_error_rate_ignores_speech_around_the_phrase() {
+        let hypothesis = "okay so the cat sit on the mat and then left";
+        assert_eq!(span_word_error_rate("the cat sat on the mat", hypothesis), 1.0 / 6.0);
+        assert_eq!(span_word_error_rate("cat sat", "cat sat"), 0.0);
+        assert_eq!(span_word_error_rate("cat sat", ""), 1.0);
+        assert_eq!(span_word_error_rate("", "anything"), 0.0);
+    }
+}
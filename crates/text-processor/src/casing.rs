@@ -0,0 +1,45 @@
+/// Restore casing the LLM changed for acronyms in `original` (all-caps tokens of two or
+/// more letters) and for `pinned_terms` such as personal dictionary entries. Matching is
+/// per whitespace-separated token, ignoring surrounding punctuation.
+pub fn preserve_casing(original: &str, formatted: &str, pinned_terms: &[String]) -> String {
+    let mut protected: Vec<&str> = original
+        .split_whitespace()
+        .map(token_core)
+        .filter(|token| is_acronym(token))
+        .collect();
+    protected.extend(
+        pinned_terms
+            .iter()
+            .map(|term| term.trim())
+            .filter(|term| !term.is_empty() && !term.contains(char::is_whitespace)),
+    );
+    if protected.is_empty() {
+        return formatted.to_string();
+    }
+
+    let mut out = String::with_capacity(formatted.len());
+    for piece in formatted.split_inclusive(char::is_whitespace) {
+        let core = token_core(piece);
+        let replacement = protected
+            .iter()
+            .find(|term| **term != core && term.to_lowercase() == core.to_lowercase());
+        match (replacement, piece.find(core)) {
+            (Some(term), Some(start)) if !core.is_empty() => {
+                out.push_str(&piece[..start]);
+                out.push_str(term);
+                out.push_str(&piece[start + core.len()..]);
+            }
+            _ => out.push_str(piece),
+        }
+    }
+    out
+}
+
+fn token_core(token: &str) -> &str {
+    token.trim_matches(|c: char| !c.is_alphanumeric())
+}
+
+fn is_acronym(token: &str) -> bool {
+    token.chars().filter(|c| c.is_alphabetic()).count() >= 2
+        && token.chars().all(|c| c.is_uppercase() || c.is_ascii_digit())
+}
@@ -0,0 +1,159 @@
+use std::ops::Range;
+
+/// A piece of input small enough for one formatting pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub text: String,
+    /// Token count as measured by the splitter's `count_tokens`.
+    pub tokens: usize,
+    /// The whitespace that followed this chunk in the input, so rejoining the chunks keeps
+    /// paragraph breaks. Empty for the last chunk.
+    pub separator: String,
+}
+
+/// Splits `text` at blank lines into paragraphs, each paired with the whitespace that
+/// followed it (empty for the last one) so they can be rejoined as dictated.
+pub fn split_paragraphs(text: &str) -> Vec<(&str, &str)> {
+    let mut paragraphs = Vec::new();
+    let mut start = 0;
+    // Start of the current whitespace run and the line breaks in it
+    let mut run: Option<(usize, usize)> = None;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            let (run_start, breaks) = run.unwrap_or((i, 0));
+            run = Some((run_start, breaks + usize::from(c == '\n')));
+            continue;
+        }
+        if let Some((run_start, breaks)) = run.take() {
+            if breaks >= 2 && run_start > start {
+                paragraphs.push((&text[start..run_start], &text[run_start..i]));
+                start = i;
+            }
+        }
+    }
+    let end = run.map_or(text.len(), |(run_start, _)| run_start.max(start));
+    paragraphs.push((&text[start..end], &text[end..]));
+    paragraphs
+}
+
+/// Splits `text` into chunks of at most `max_tokens` as measured by `count_tokens`. Chunks
+/// break at sentence ends; a sentence that is too long on its own is split between words.
+/// Text that already fits comes back as one chunk.
+pub fn split_by_tokens<E>(
+    text: &str,
+    max_tokens: usize,
+    count_tokens: impl Fn(&str) -> Result<usize, E>,
+) -> Result<Vec<Chunk>, E> {
+    let max_tokens = max_tokens.max(1);
+    let total = count_tokens(text)?;
+    if total <= max_tokens {
+        return Ok(vec![Chunk {
+            text: text.to_string(),
+            tokens: total,
+            separator: String::new(),
+        }]);
+    }
+
+    // Consecutive runs of sentences (or words) packed up to `max_tokens` each
+    let mut runs: Vec<(Range<usize>, usize)> = Vec::new();
+    for sentence in sentences(text) {
+        let tokens = count_tokens(&text[sentence.clone()])?;
+        let pieces = if tokens > max_tokens {
+            split_words(text, sentence, tokens, max_tokens)
+        } else {
+            vec![(sentence, tokens)]
+        };
+        for (piece, tokens) in pieces {
+            match runs.last_mut() {
+                Some((run, run_tokens)) if *run_tokens + tokens <= max_tokens => {
+                    run.end = piece.end;
+                    *run_tokens += tokens;
+                }
+                _ => runs.push((piece, tokens)),
+            }
+        }
+    }
+
+    let next_starts = runs.iter().skip(1).map(|(run, _)| run.start);
+    let next_starts = next_starts.chain(std::iter::once(text.len()));
+    Ok(runs
+        .iter()
+        .zip(next_starts)
+        .map(|((run, tokens), next_start)| Chunk {
+            text: text[run.clone()].to_string(),
+            tokens: *tokens,
+            separator: text[run.end..next_start].to_string(),
+        })
+        .collect())
+}
+
+/// Byte ranges of the sentences of `text`, each ending at `.`, `?` or `!` followed by
+/// whitespace, without surrounding whitespace.
+fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let at_boundary = matches!(c, '.' | '?' | '!')
+            && chars.peek().is_some_and(|(_, next)| next.is_whitespace());
+        if at_boundary {
+            let end = i + c.len_utf8();
+            sentences.push(trimmed(text, start..end));
+            start = end;
+        }
+    }
+    sentences.push(trimmed(text, start..text.len()));
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+/// Splits an oversized sentence into runs of words, assuming its `tokens` are spread evenly
+/// across its words.
+fn split_words(
+    text: &str,
+    sentence: Range<usize>,
+    tokens: usize,
+    max_tokens: usize,
+) -> Vec<(Range<usize>, usize)> {
+    let words = words(text, sentence);
+    let words_per_chunk = (words.len() * max_tokens / tokens).max(1);
+    words
+        .chunks(words_per_chunk)
+        .map(|run| {
+            let range = run[0].start..run[run.len() - 1].end;
+            (range, (tokens * run.len()).div_ceil(words.len()))
+        })
+        .collect()
+}
+
+/// Byte ranges of the whitespace-separated words within `range`.
+fn words(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text[range.clone()].char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(word_start)) => {
+                words.push(range.start + word_start..range.start + i);
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(word_start) = start {
+        words.push(range.start + word_start..range.end);
+    }
+    words
+}
+
+/// Byte ranges of all whitespace-separated words of `text`.
+pub(crate) fn word_ranges(text: &str) -> Vec<Range<usize>> {
+    words(text, 0..text.len())
+}
+
+fn trimmed(text: &str, range: Range<usize>) -> Range<usize> {
+    let slice = &text[range.clone()];
+    let start = range.start + (slice.len() - slice.trim_start().len());
+    let end = range.end - (slice.len() - slice.trim_end().len());
+    start..end.max(start)
+}
@@ -1,11 +1,21 @@
 use llm::{
-    adapters::llamacpp::LlamaCppAdapter, LlmAdapter, LlmConfig,
+    adapters::{llamacpp::LlamaCppAdapter, ollama::OllamaAdapter},
+    LlmAdapter, LlmConfig,
 };
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+mod casing;
+mod chunking;
 mod prompts;
+mod spoken;
+mod stats;
+
+pub use casing::preserve_casing;
+pub use chunking::{split_by_tokens, split_paragraphs, Chunk};
+pub use spoken::apply_spoken_punctuation;
+pub use stats::{formatting_stats, FormattingStats};
 
 #[derive(Debug, Error)]
 pub enum ProcessorError {
@@ -32,12 +42,26 @@ pub enum FormattingMode {
 
 impl FormattingMode {
     pub fn from_str(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "quick" => Self::Quick,
-            "standard" => Self::Standard,
-            "smart" => Self::Smart,
-            "disabled" => Self::Disabled,
-            _ => Self::Standard, // Default fallback
+        Self::try_from_str(s).unwrap_or(Self::Standard) // Default fallback
+    }
+
+    /// Strict variant of [`FormattingMode::from_str`] for validating user input.
+    pub fn try_from_str(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "quick" => Some(Self::Quick),
+            "standard" => Some(Self::Standard),
+            "smart" => Some(Self::Smart),
+            "disabled" => Some(Self::Disabled),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Quick => "quick",
+            Self::Standard => "standard",
+            Self::Smart => "smart",
+            Self::Disabled => "disabled",
         }
     }
 }
@@ -50,24 +74,134 @@ pub struct ProcessingResult {
     pub mode_used: FormattingMode,
 }
 
+/// Default upper bound for formatting output, matching the previous fixed budget.
+pub const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 512;
+const MIN_OUTPUT_TOKENS: u32 = 16;
+
+/// Formatting output ends at a chat end marker the model spelled out as text. Blank lines
+/// are not stops: dictated paragraph breaks must survive, so each paragraph is formatted on
+/// its own and commentary after a blank line in its answer is dropped instead.
+pub const DEFAULT_STOP_SEQUENCES: &[&str] = &[
+    "<|im_end|>",
+    "<|eot_id|>",
+    "<|endoftext|>",
+    "<end_of_turn>",
+    "</s>",
+];
+
+/// How long a formatting pass may generate before it is cut off.
+pub const DEFAULT_FORMATTING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A timed-out answer is kept only if it already covers this share of the input's words;
+/// formatting drops fillers, so a complete answer can be somewhat shorter than the input.
+const PARTIAL_MIN_WORD_RATIO: f32 = 0.8;
+
+/// How many input words ahead a salvaged word may match, enough to skip dropped fillers.
+const SALVAGE_LOOKAHEAD: usize = 4;
+
+/// The partial output of a formatting pass that hit its deadline, if it is long enough to
+/// stand in for the full answer. The input words the answer had not reached yet are
+/// appended unformatted, so a timeout never loses dictation.
+pub fn salvage_partial_output(original: &str, partial: &str) -> Option<String> {
+    let partial = partial.trim();
+    let original_words = chunking::word_ranges(original);
+    let partial_words = chunking::word_ranges(partial);
+    if partial_words.is_empty()
+        || (partial_words.len() as f32) < original_words.len() as f32 * PARTIAL_MIN_WORD_RATIO
+    {
+        return None;
+    }
+
+    // Walk the answer against the input; the answer is kept up to its last word that
+    // matches an input word, and the input resumes right after that word
+    let mut next_original = 0;
+    let mut kept = 0;
+    for word in &partial_words {
+        let key = salvage_key(&partial[word.clone()]);
+        let matched = original_words[next_original..]
+            .iter()
+            .take(SALVAGE_LOOKAHEAD)
+            .position(|original_word| salvage_key(&original[original_word.clone()]) == key);
+        if let (false, Some(offset)) = (key.is_empty(), matched) {
+            next_original += offset + 1;
+            kept = word.end;
+        }
+    }
+    if kept == 0 {
+        return None;
+    }
+
+    let prefix = &partial[..kept];
+    Some(match original_words.get(next_original) {
+        Some(rest) => format!("{} {}", prefix, original[rest.start..].trim_end()),
+        None => prefix.to_string(),
+    })
+}
+
+/// A word compared case- and punctuation-insensitively.
+fn salvage_key(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// `text` up to its first blank line, ignoring leading whitespace.
+fn first_paragraph(text: &str) -> &str {
+    split_paragraphs(text.trim_start())
+        .first()
+        .map_or("", |(paragraph, _)| paragraph)
+}
+
+/// Output budget for a formatting pass: `ceil(1.5 * input_tokens) + 8`, clamped to
+/// `[16, ceiling]`. Formatting rarely grows text, so 1.5x leaves room for added
+/// punctuation and number rewrites without paying for a fixed 512-token budget.
+pub fn output_token_budget(input_tokens: usize, ceiling: u32) -> u32 {
+    let ceiling = ceiling.max(MIN_OUTPUT_TOKENS);
+    let scaled = (input_tokens as u64 * 3).div_ceil(2) + 8;
+    scaled.clamp(MIN_OUTPUT_TOKENS as u64, ceiling as u64) as u32
+}
+
+/// Largest input whose [`output_token_budget`] stays under `ceiling`. Longer input is
+/// formatted in chunks so the answer is not cut off at the ceiling.
+pub fn input_token_limit(ceiling: u32) -> usize {
+    let ceiling = ceiling.max(MIN_OUTPUT_TOKENS);
+    (ceiling as usize - 8) * 2 / 3
+}
+
 pub struct TextProcessor {
-    llm_adapter: LlamaCppAdapter,
+    llm_adapter: Box<dyn LlmAdapter>,
     mode: FormattingMode,
     min_words_for_processing: usize,
+    max_output_tokens: u32,
+    pinned_terms: Vec<String>,
+    timeout: Duration,
 }
 
 impl TextProcessor {
     pub async fn new(model_name: &str, mode: FormattingMode) -> Result<Self> {
-        let mut adapter = LlamaCppAdapter::new();
-        
-        // Create config
+        Self::with_adapter(Box::new(LlamaCppAdapter::new()), model_name, mode).await
+    }
+
+    /// Formats through a local Ollama server instead of the bundled llama.cpp runtime.
+    pub async fn with_ollama(base_url: &str, model_name: &str, mode: FormattingMode) -> Result<Self> {
+        Self::with_adapter(Box::new(OllamaAdapter::new(base_url)), model_name, mode).await
+    }
+
+    async fn with_adapter(
+        mut adapter: Box<dyn LlmAdapter>,
+        model_name: &str,
+        mode: FormattingMode,
+    ) -> Result<Self> {
+        // Per-model sampling preset; model path auto-resolves from cache
         let config = LlmConfig {
-            model_name: model_name.to_string(),
-            model_path: None, // Will auto-resolve from cache
-            temperature: 0.7,
-            max_tokens: 512,
-            top_p: 0.9,
-            top_k: 40,
+            max_tokens: DEFAULT_MAX_OUTPUT_TOKENS,
+            stop: DEFAULT_STOP_SEQUENCES
+                .iter()
+                .map(|stop| stop.to_string())
+                .collect(),
+            grammar: Some(llm::prompts::PLAIN_PROSE_GRAMMAR.to_string()),
+            ..LlmConfig::for_model(model_name)
         };
         
         // Initialize the adapter
@@ -77,9 +211,12 @@ impl TextProcessor {
             .map_err(|e| ProcessorError::LlmError(e.to_string()))?;
 
         Ok(Self {
-            llm_adapter:adapter,
+            llm_adapter: adapter,
             mode,
             min_words_for_processing: 3, // Skip LLM for very short text
+            max_output_tokens: DEFAULT_MAX_OUTPUT_TOKENS,
+            pinned_terms: Vec::new(),
+            timeout: DEFAULT_FORMATTING_TIMEOUT,
         })
     }
 
@@ -88,6 +225,24 @@ impl TextProcessor {
         self
     }
 
+    /// Caps the per-call output budget computed by [`output_token_budget`].
+    pub fn with_max_output_tokens(mut self, max_output_tokens: u32) -> Self {
+        self.max_output_tokens = max_output_tokens;
+        self
+    }
+
+    /// Stops generation after `timeout`; a long-enough partial answer is still used.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Terms (e.g. personal dictionary entries) whose casing survives formatting as written.
+    pub fn with_pinned_terms(mut self, terms: Vec<String>) -> Self {
+        self.pinned_terms = terms;
+        self
+    }
+
     pub async fn process(&self, raw_text: &str) -> Result<ProcessingResult> {
         let start = Instant::now();
 
@@ -109,42 +264,99 @@ impl TextProcessor {
             });
         }
 
+        // Each paragraph is formatted on its own; one too long for a single output budget is
+        // formatted a few sentences at a time
+        let limit = input_token_limit(self.max_output_tokens);
+        let mut chunks = Vec::new();
+        for (paragraph, separator) in split_paragraphs(trimmed) {
+            let mut pieces =
+                split_by_tokens(paragraph, limit, |text| self.llm_adapter.count_tokens(text))
+                    .map_err(|e| ProcessorError::LlmError(e.to_string()))?;
+            if let Some(last) = pieces.last_mut() {
+                last.separator = separator.to_string();
+            }
+            chunks.extend(pieces);
+        }
+        let deadline = start + self.timeout;
+        let mut formatted = String::new();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let result = if Instant::now() >= deadline {
+                Err(ProcessorError::Timeout)
+            } else {
+                self.format_chunk(&chunk.text, chunk.tokens, deadline).await
+            };
+            match result {
+                Ok(text) => formatted.push_str(&text),
+                // Out of time after some chunks: keep those and pass the rest through as is
+                Err(ProcessorError::Timeout) if index > 0 => formatted.push_str(&chunk.text),
+                Err(err) => return Err(err),
+            }
+            formatted.push_str(&chunk.separator);
+        }
+
+        // Undo casing changes to acronyms/pinned terms
+        let final_text = preserve_casing(trimmed, &formatted, &self.pinned_terms);
+
+        Ok(ProcessingResult {
+            formatted_text: final_text,
+            original_text: raw_text.to_string(),
+            processing_time_ms: start.elapsed().as_millis() as u64,
+            mode_used: self.mode,
+        })
+    }
+
+    /// One formatting pass over `chunk`, a single paragraph, falling back to the chunk itself
+    /// if the LLM returns nothing. Anything after a blank line in the answer is commentary.
+    async fn format_chunk(
+        &self,
+        chunk: &str,
+        input_tokens: usize,
+        deadline: Instant,
+    ) -> Result<String> {
         // Generate prompt based on mode
         let prompt = match self.mode {
-            FormattingMode::Quick => prompts::quick_format_prompt(trimmed),
-            FormattingMode::Standard => prompts::standard_format_prompt(trimmed),
-            FormattingMode::Smart => prompts::smart_format_prompt(trimmed),
-            FormattingMode::Disabled => unreachable!(), // Already handled above
+            FormattingMode::Quick => prompts::quick_format_prompt(chunk),
+            FormattingMode::Standard => prompts::standard_format_prompt(chunk),
+            FormattingMode::Smart => prompts::smart_format_prompt(chunk),
+            FormattingMode::Disabled => unreachable!(), // Handled in `process`
         };
 
-        // Run LLM inference
-        let formatted = self
+        // Size the output budget from the input rather than always reserving the ceiling
+        let max_tokens = output_token_budget(input_tokens, self.max_output_tokens);
+
+        // Run LLM inference; the adapter stops decoding at the deadline
+        let output = self
             .llm_adapter
-            .run_prompt(prompt, 512) // Max 512 tokens output
+            .run_prompt_with_deadline(prompt, max_tokens, deadline)
             .await
-            .map_err(|e| ProcessorError::LlmError(e.to_string()))?
-            .trim()
-            .to_string();
-
-        // Fallback if LLM returns empty
-        let final_text = if formatted.is_empty() {
-            trimmed.to_string()
+            .map_err(|e| ProcessorError::LlmError(e.to_string()))?;
+        let answer = first_paragraph(&output.text);
+        let formatted = if output.timed_out {
+            salvage_partial_output(chunk, answer).ok_or(ProcessorError::Timeout)?
         } else {
-            formatted
+            answer.trim().to_string()
         };
 
-        Ok(ProcessingResult {
-            formatted_text: final_text,
-            original_text: raw_text.to_string(),
-            processing_time_ms: start.elapsed().as_millis() as u64,
-            mode_used: self.mode,
+        Ok(if formatted.is_empty() {
+            chunk.to_string()
+        } else {
+            formatted
         })
     }
 
+    /// Frees the formatting model; the processor must not be used afterwards.
+    pub async fn unload(&mut self) {
+        self.llm_adapter.unload().await;
+    }
+
     pub fn set_mode(&mut self, mode: FormattingMode) {
         self.mode = mode;
     }
 
+    pub fn set_pinned_terms(&mut self, terms: Vec<String>) {
+        self.pinned_terms = terms;
+    }
+
     pub fn mode(&self) -> FormattingMode {
         self.mode
     }
@@ -0,0 +1,139 @@
+/// How a spoken-punctuation symbol joins the words around it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Spacing {
+    /// Hugs the previous word: `hello,` / `world)`.
+    Left,
+    /// Hugs the next word: `(hello` / `"quote`.
+    Right,
+    /// Stands alone between spaces: `a - b`.
+    Around,
+    /// Line break; surrounding spaces are dropped.
+    Break,
+}
+
+/// Spoken phrase, symbol, spacing. Matching is case-insensitive and prefers the longest phrase.
+const COMMANDS: &[(&str, &str, Spacing)] = &[
+    ("comma", ",", Spacing::Left),
+    ("period", ".", Spacing::Left),
+    ("full stop", ".", Spacing::Left),
+    ("question mark", "?", Spacing::Left),
+    ("exclamation mark", "!", Spacing::Left),
+    ("exclamation point", "!", Spacing::Left),
+    ("colon", ":", Spacing::Left),
+    ("semicolon", ";", Spacing::Left),
+    ("ellipsis", "...", Spacing::Left),
+    ("open paren", "(", Spacing::Right),
+    ("open parenthesis", "(", Spacing::Right),
+    ("close paren", ")", Spacing::Left),
+    ("close parenthesis", ")", Spacing::Left),
+    ("open quote", "\"", Spacing::Right),
+    ("close quote", "\"", Spacing::Left),
+    ("end quote", "\"", Spacing::Left),
+    ("dash", "-", Spacing::Around),
+    ("new line", "\n", Spacing::Break),
+    ("newline", "\n", Spacing::Break),
+    ("new paragraph", "\n\n", Spacing::Break),
+];
+
+/// Punctuation whisper adds on its own; dropped next to a spoken symbol so "hello, comma"
+/// does not become `hello,,`.
+const INFERRED_PUNCTUATION: &[char] = &[',', '.', ';', ':', '!', '?'];
+
+/// Replace spoken punctuation ("comma", "open paren", "new paragraph", ...) with symbols.
+/// Text without any spoken command is returned unchanged.
+pub fn apply_spoken_punctuation(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let cores: Vec<String> = words
+        .iter()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .collect();
+
+    let mut out = String::with_capacity(text.len());
+    let mut matched = false;
+    // No space before the next piece (start of text, after `(` or a line break)
+    let mut glue_next = true;
+    let mut capitalize_next = false;
+    let mut last_was_word = false;
+    let mut i = 0;
+    while i < words.len() {
+        let Some((len, symbol, spacing)) = match_command(&cores[i..]) else {
+            if !glue_next {
+                out.push(' ');
+            }
+            if capitalize_next {
+                out.push_str(&capitalize(words[i]));
+            } else {
+                out.push_str(words[i]);
+            }
+            glue_next = false;
+            capitalize_next = false;
+            last_was_word = true;
+            i += 1;
+            continue;
+        };
+
+        matched = true;
+        match spacing {
+            Spacing::Left => {
+                if last_was_word {
+                    out.truncate(out.trim_end_matches(INFERRED_PUNCTUATION).len());
+                }
+                out.push_str(symbol);
+                glue_next = false;
+                capitalize_next = matches!(symbol, "." | "?" | "!");
+            }
+            Spacing::Right => {
+                if !glue_next {
+                    out.push(' ');
+                }
+                out.push_str(symbol);
+                glue_next = true;
+            }
+            Spacing::Around => {
+                if !glue_next {
+                    out.push(' ');
+                }
+                out.push_str(symbol);
+                glue_next = false;
+            }
+            Spacing::Break => {
+                out.truncate(out.trim_end_matches(' ').len());
+                out.push_str(symbol);
+                glue_next = true;
+                capitalize_next = true;
+            }
+        }
+        last_was_word = false;
+        i += len;
+    }
+
+    if matched {
+        out
+    } else {
+        text.to_string()
+    }
+}
+
+/// Longest command whose words start `cores`, as (word count, symbol, spacing).
+fn match_command(cores: &[String]) -> Option<(usize, &'static str, Spacing)> {
+    COMMANDS
+        .iter()
+        .filter_map(|(phrase, symbol, spacing)| {
+            let phrase_words: Vec<&str> = phrase.split(' ').collect();
+            let matches = phrase_words.len() <= cores.len()
+                && phrase_words
+                    .iter()
+                    .zip(cores)
+                    .all(|(expected, core)| *expected == core.as_str());
+            matches.then_some((phrase_words.len(), *symbol, *spacing))
+        })
+        .max_by_key(|(len, _, _)| *len)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
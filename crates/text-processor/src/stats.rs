@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+/// Words counted as fillers when comparing raw and formatted text.
+const FILLER_WORDS: &[&str] = &["um", "umm", "uh", "uhm", "erm", "er", "ah", "hmm", "mm"];
+
+/// What a formatting pass changed, for per-dictation feedback.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormattingStats {
+    pub fillers_removed: u64,
+    pub punctuation_added: u64,
+    pub chars_changed: u64,
+}
+
+pub fn formatting_stats(original: &str, formatted: &str) -> FormattingStats {
+    FormattingStats {
+        fillers_removed: count_fillers(original).saturating_sub(count_fillers(formatted)),
+        punctuation_added: count_punctuation(formatted).saturating_sub(count_punctuation(original)),
+        chars_changed: char_edit_distance(original.trim(), formatted.trim()),
+    }
+}
+
+fn count_fillers(text: &str) -> u64 {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|word| FILLER_WORDS.contains(&word.as_str()))
+        .count() as u64
+}
+
+fn count_punctuation(text: &str) -> u64 {
+    text.chars()
+        .filter(|c| matches!(c, '.' | ',' | '?' | '!' | ';' | ':'))
+        .count() as u64
+}
+
+/// Levenshtein distance over chars; dictation-sized inputs keep the O(n*m) cost small.
+fn char_edit_distance(a: &str, b: &str) -> u64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()] as u64
+}
@@ -1,4 +1,5 @@
 use super::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 // Note: These are unit tests that test the logic without actual LLM calls.
 // Full integration tests with real LLM would require downloading models.
@@ -37,6 +38,20 @@ fn test_formatting_mode_from_str() {
     assert_eq!(FormattingMode::from_str("invalid"), FormattingMode::Standard); // Default
 }
 
+#[test]
+fn test_formatting_mode_try_from_str() {
+    assert_eq!(FormattingMode::try_from_str(" Smart "), Some(FormattingMode::Smart));
+    assert_eq!(FormattingMode::try_from_str("invalid"), None);
+    for mode in [
+        FormattingMode::Quick,
+        FormattingMode::Standard,
+        FormattingMode::Smart,
+        FormattingMode::Disabled,
+    ] {
+        assert_eq!(FormattingMode::try_from_str(mode.as_str()), Some(mode));
+    }
+}
+
 #[test]
 fn test_whitespace_trimming_logic() {
     let text_with_whitespace = "  \n\t this is a test  \n  ";
@@ -61,6 +76,314 @@ fn test_prompt_generation() {
     assert!(smart.contains("numbers"));
 }
 
+#[test]
+fn test_output_token_budget_scales_with_input() {
+    assert_eq!(output_token_budget(0, DEFAULT_MAX_OUTPUT_TOKENS), 16); // Floor
+    assert_eq!(output_token_budget(10, DEFAULT_MAX_OUTPUT_TOKENS), 23); // ceil(15) + 8
+    assert_eq!(output_token_budget(11, DEFAULT_MAX_OUTPUT_TOKENS), 25); // ceil(16.5) + 8
+    assert_eq!(output_token_budget(1000, DEFAULT_MAX_OUTPUT_TOKENS), 512); // Ceiling
+    assert_eq!(output_token_budget(1000, 2048), 1508);
+}
+
+#[test]
+fn test_input_token_limit_keeps_budget_under_ceiling() {
+    let limit = input_token_limit(DEFAULT_MAX_OUTPUT_TOKENS);
+    assert_eq!(limit, 336);
+    assert!(output_token_budget(limit, DEFAULT_MAX_OUTPUT_TOKENS) <= DEFAULT_MAX_OUTPUT_TOKENS);
+    assert_eq!(input_token_limit(0), 5); // Floor of 16
+}
+
+fn chunk(text: &str, tokens: usize, separator: &str) -> Chunk {
+    Chunk {
+        text: text.to_string(),
+        tokens,
+        separator: separator.to_string(),
+    }
+}
+
+#[test]
+fn test_split_by_tokens_breaks_at_sentences_then_words() {
+    let count_words = |text: &str| Ok::<_, ()>(text.split_whitespace().count());
+
+    let fits = split_by_tokens("um so this is short. okay?", 10, count_words).unwrap();
+    assert_eq!(fits, vec![chunk("um so this is short. okay?", 6, "")]);
+
+    let text = "first one here. second one here!\n\nthird one\nhere? and a fourth";
+    let chunks = split_by_tokens(text, 6, count_words).unwrap();
+    assert_eq!(
+        chunks,
+        vec![
+            chunk("first one here. second one here!", 6, "\n\n"),
+            chunk("third one\nhere? and a fourth", 6, ""),
+        ]
+    );
+
+    let long = split_by_tokens("one two three four five six seven", 3, count_words).unwrap();
+    assert_eq!(
+        long,
+        vec![
+            chunk("one two three", 3, " "),
+            chunk("four five six", 3, " "),
+            chunk("seven", 1, ""),
+        ]
+    );
+}
+
+/// Counts words as tokens; the first prompt is formatted, later ones run into the deadline.
+struct FirstChunkOnlyAdapter {
+    prompts: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl LlmAdapter for FirstChunkOnlyAdapter {
+    async fn initialize(&mut self, _config: LlmConfig) -> llm::Result<()> {
+        Ok(())
+    }
+
+    async fn format_text(
+        &self,
+        request: llm::TextFormattingRequest,
+    ) -> llm::Result<llm::TextFormattingResponse> {
+        Ok(llm::TextFormattingResponse {
+            formatted_text: request.raw_text.clone(),
+            original_text: request.raw_text,
+        })
+    }
+
+    async fn run_prompt(&self, _prompt: String, _max_tokens: u32) -> llm::Result<String> {
+        unreachable!("formatting always passes a deadline")
+    }
+
+    async fn run_prompt_with_deadline(
+        &self,
+        _prompt: String,
+        _max_tokens: u32,
+        deadline: Instant,
+    ) -> llm::Result<llm::PromptOutput> {
+        let prompts = self.prompts.fetch_add(1, Ordering::SeqCst);
+        if prompts == 0 {
+            return Ok(llm::PromptOutput {
+                text: "First one here, second one here!".to_string(),
+                timed_out: false,
+            });
+        }
+        tokio::time::sleep_until(deadline.into()).await;
+        Ok(llm::PromptOutput {
+            text: String::new(),
+            timed_out: true,
+        })
+    }
+
+    fn count_tokens(&self, text: &str) -> llm::Result<usize> {
+        Ok(text.split_whitespace().count())
+    }
+
+    async fn is_model_available(&self, _model_name: &str) -> bool {
+        true
+    }
+
+    fn current_model(&self) -> Option<String> {
+        None
+    }
+}
+
+#[tokio::test]
+async fn test_chunks_after_the_deadline_pass_through_unformatted() {
+    let adapter = Box::new(FirstChunkOnlyAdapter {
+        prompts: AtomicUsize::new(0),
+    });
+    let processor = TextProcessor::with_adapter(adapter, "mock", FormattingMode::Standard)
+        .await
+        .unwrap()
+        // An input limit of six tokens
+        .with_max_output_tokens(17)
+        .with_timeout(Duration::from_millis(50));
+
+    let input =
+        "first one here. second one here!\n\nthird slow here? and a fourth. fifth sentence.";
+    let result = processor.process(input).await.unwrap();
+    assert_eq!(
+        result.formatted_text,
+        "First one here, second one here!\n\nthird slow here? and a fourth. fifth sentence."
+    );
+}
+
+#[test]
+fn test_split_paragraphs_keeps_separators() {
+    assert_eq!(
+        split_paragraphs("one.\n\ntwo\nlines. \n \n three"),
+        vec![("one.", "\n\n"), ("two\nlines.", " \n \n "), ("three", "")]
+    );
+    assert_eq!(split_paragraphs("just one\nparagraph"), vec![("just one\nparagraph", "")]);
+}
+
+/// Counts words as tokens and answers with the prompt's input followed by commentary.
+struct ChattyEchoAdapter;
+
+#[async_trait::async_trait]
+impl LlmAdapter for ChattyEchoAdapter {
+    async fn initialize(&mut self, _config: LlmConfig) -> llm::Result<()> {
+        Ok(())
+    }
+
+    async fn format_text(
+        &self,
+        request: llm::TextFormattingRequest,
+    ) -> llm::Result<llm::TextFormattingResponse> {
+        Ok(llm::TextFormattingResponse {
+            formatted_text: request.raw_text.clone(),
+            original_text: request.raw_text,
+        })
+    }
+
+    async fn run_prompt(&self, _prompt: String, _max_tokens: u32) -> llm::Result<String> {
+        unreachable!("formatting always passes a deadline")
+    }
+
+    async fn run_prompt_with_deadline(
+        &self,
+        prompt: String,
+        _max_tokens: u32,
+        _deadline: Instant,
+    ) -> llm::Result<llm::PromptOutput> {
+        let input = prompt
+            .split_once("Input: \"")
+            .and_then(|(_, rest)| rest.split_once("\"\n\nOutput"))
+            .map_or("", |(input, _)| input);
+        Ok(llm::PromptOutput {
+            text: format!("{input}\n\nI removed the filler words."),
+            timed_out: false,
+        })
+    }
+
+    fn count_tokens(&self, text: &str) -> llm::Result<usize> {
+        Ok(text.split_whitespace().count())
+    }
+
+    async fn is_model_available(&self, _model_name: &str) -> bool {
+        true
+    }
+
+    fn current_model(&self) -> Option<String> {
+        None
+    }
+}
+
+#[tokio::test]
+async fn test_multi_paragraph_input_survives_process() {
+    let processor =
+        TextProcessor::with_adapter(Box::new(ChattyEchoAdapter), "mock", FormattingMode::Standard)
+            .await
+            .unwrap();
+
+    let input = apply_spoken_punctuation(
+        "first paragraph here new paragraph second one new line still second new paragraph third",
+    );
+    let result = processor.process(&input).await.unwrap();
+    assert_eq!(
+        result.formatted_text,
+        "first paragraph here\n\nSecond one\nStill second\n\nThird"
+    );
+}
+
+#[test]
+fn test_formatting_stats_counts_changes() {
+    let stats = formatting_stats(
+        "um I need to uh schedule a meeting",
+        "I need to schedule a meeting.",
+    );
+    assert_eq!(stats.fillers_removed, 2);
+    assert_eq!(stats.punctuation_added, 1);
+    assert_eq!(stats.chars_changed, 7); // "um " + "uh " deleted, "." added
+
+    let unchanged = formatting_stats("Hello, world.", "Hello, world.");
+    assert_eq!(unchanged, FormattingStats::default());
+}
+
+#[test]
+fn test_preserve_casing_restores_acronyms() {
+    let original = "the NASA API endpoint";
+    let formatted = "The Nasa api endpoint.";
+    assert_eq!(
+        preserve_casing(original, formatted, &[]),
+        "The NASA API endpoint."
+    );
+    // Text the LLM left alone is unchanged
+    assert_eq!(
+        preserve_casing(original, "The NASA API endpoint.", &[]),
+        "The NASA API endpoint."
+    );
+}
+
+#[test]
+fn test_preserve_casing_pins_dictionary_terms() {
+    let pinned = vec!["OpenWispr".to_string()];
+    assert_eq!(
+        preserve_casing("i use openwispr daily", "I use Openwispr daily.", &pinned),
+        "I use OpenWispr daily."
+    );
+}
+
+#[test]
+fn test_salvage_partial_output_requires_most_of_the_input() {
+    let original = "um so the meeting is moved to thursday at three";
+    assert_eq!(salvage_partial_output(original, "So the meeting"), None);
+    assert_eq!(salvage_partial_output(original, "   "), None);
+}
+
+#[test]
+fn test_salvage_partial_output_keeps_every_input_word() {
+    let original = "um so the meeting is moved to thursday at three";
+    let salvaged = salvage_partial_output(original, " So the meeting is moved to Thursday at ");
+    assert_eq!(
+        salvaged.as_deref(),
+        Some("So the meeting is moved to Thursday at three")
+    );
+    // A word cut off mid-token is dropped in favour of the input's own word
+    let salvaged = salvage_partial_output(original, "So the meeting is moved to Thursday a");
+    assert_eq!(
+        salvaged.as_deref(),
+        Some("So the meeting is moved to Thursday at three")
+    );
+
+    let salvaged = salvaged.unwrap().to_lowercase();
+    for word in original.split_whitespace().filter(|word| *word != "um") {
+        assert!(salvaged.split_whitespace().any(|kept| kept == word), "lost {word:?}");
+    }
+}
+
+#[test]
+fn test_spoken_punctuation_maps_common_commands() {
+    assert_eq!(
+        apply_spoken_punctuation("open paren hello comma world close paren period"),
+        "(hello, world)."
+    );
+    assert_eq!(
+        apply_spoken_punctuation("is it done question mark yes exclamation point"),
+        "is it done? Yes!"
+    );
+    assert_eq!(
+        apply_spoken_punctuation("he said colon open quote hi close quote"),
+        "he said: \"hi\""
+    );
+}
+
+#[test]
+fn test_spoken_punctuation_line_breaks() {
+    assert_eq!(
+        apply_spoken_punctuation("first line new line second new paragraph third"),
+        "first line\nSecond\n\nThird"
+    );
+}
+
+#[test]
+fn test_spoken_punctuation_drops_whisper_duplicates() {
+    // Whisper often punctuates the spoken command itself
+    assert_eq!(apply_spoken_punctuation("Hello, comma, world. Period."), "Hello, world.");
+    // Text without commands is left alone, including its whitespace
+    assert_eq!(apply_spoken_punctuation("Hello,  world."), "Hello,  world.");
+}
+
 #[test]
 fn test_processing_result_structure() {
     let result = ProcessingResult {